@@ -5,17 +5,92 @@ use axum::{
   Router,
 };
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::Semaphore;
 
 use keyword_analyzer_shared::{
-  generate_html_content, generate_json_content, is_git_url, is_valid_github_repo_url,
-  is_valid_gitlab_repo_url, KeywordAnalyzer, Language, OutputFormat,
+  find_language, generate_html_content, generate_json_content, generate_occurrences_json,
+  generate_repository_html_content, generate_repository_json_content, is_git_url,
+  is_valid_github_repo_url, is_valid_gitlab_repo_url, register_language, registry, AnalysisOptions,
+  AnalysisResult, AnalyzerError, Encoding, FileFilter, Glob, KeywordAnalyzer, LanguageDefinition,
+  LanguageSpec, OutputFormat,
 };
 
+/// Directory of extra `.toml`/`.json` language definitions loaded at startup.
+const LANGUAGE_DIR_ENV: &str = "KEYWORD_ANALYZER_LANGUAGE_DIR";
+/// Threads analyzing the files of one request; unset or 0 uses every core.
+const JOBS_ENV: &str = "KEYWORD_ANALYZER_JOBS";
+
+/// Repository analyses run at once; further requests wait for one to finish.
+const MAX_ANALYSES_ENV: &str = "KEYWORD_ANALYZER_MAX_ANALYSES";
+const DEFAULT_MAX_ANALYSES: usize = 2;
+
+static JOBS: OnceLock<usize> = OnceLock::new();
+static MAX_ANALYSES: OnceLock<usize> = OnceLock::new();
+
+fn jobs() -> usize {
+  *JOBS.get_or_init(|| {
+    std::env::var(JOBS_ENV)
+      .ok()
+      .and_then(|jobs| jobs.parse().ok())
+      .unwrap_or(0)
+  })
+}
+
+fn max_analyses() -> usize {
+  *MAX_ANALYSES.get_or_init(|| {
+    std::env::var(MAX_ANALYSES_ENV)
+      .ok()
+      .and_then(|max| max.parse().ok())
+      .filter(|&max| max > 0)
+      .unwrap_or(DEFAULT_MAX_ANALYSES)
+  })
+}
+
 #[derive(Deserialize)]
 struct RepositoryAnalyzeRequest {
+  /// Language id or alias; empty or `auto` analyzes every detected language.
+  #[serde(default)]
   language: String,
+  /// Inline definition used instead of `language`.
+  language_definition: Option<LanguageDefinition>,
   format: Option<String>,
   repository_url: String,
+  /// Only count keywords of this category, e.g. `control flow`.
+  category: Option<String>,
+  /// `utf8` (default), `lossy` or `detect`; files that cannot be decoded are
+  /// listed as skipped.
+  #[serde(default)]
+  encoding: Encoding,
+  /// Globs of the only files to analyze, e.g. `src/**/*.rs`.
+  #[serde(default)]
+  include: Vec<String>,
+  /// Globs of files and directories to leave out, e.g. `*.pb.go`.
+  #[serde(default)]
+  exclude: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct FindKeywordRequest {
+  keyword: String,
+  /// Language id or alias; empty or `auto` searches every language that has
+  /// the keyword.
+  #[serde(default)]
+  language: String,
+  repository_url: String,
+  /// Lines of context kept around each occurrence.
+  #[serde(default)]
+  context: usize,
+  /// Same as [`RepositoryAnalyzeRequest::encoding`].
+  #[serde(default)]
+  encoding: Encoding,
+  /// Same as [`RepositoryAnalyzeRequest::include`].
+  #[serde(default)]
+  include: Vec<String>,
+  /// Same as [`RepositoryAnalyzeRequest::exclude`].
+  #[serde(default)]
+  exclude: Vec<String>,
 }
 
 #[derive(Serialize)]
@@ -27,17 +102,40 @@ struct ApiResponse<T> {
 
 #[tokio::main]
 async fn main() {
+  if let Ok(dir) = std::env::var(LANGUAGE_DIR_ENV) {
+    match LanguageDefinition::load_dir(Path::new(&dir)) {
+      Ok(definitions) => {
+        for definition in definitions {
+          println!("🔤 Loaded language definition: {}", definition.id);
+          register_language(Arc::new(definition));
+        }
+      }
+      Err(e) => eprintln!("Failed to load language definitions from {dir}: {e}"),
+    }
+  }
+
   let app = Router::new()
     .route("/", get(health_check))
     .route("/health", get(health_check))
-    .route("/analyze-repository", post(analyze_repository));
+    .route("/analyze-repository", post(analyze_repository))
+    .route("/find-keyword", post(find_keyword));
 
   let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
 
   println!("🚀 Keyword Analyzer API Server starting on http://0.0.0.0:3000");
+  let options = AnalysisOptions {
+    jobs: jobs(),
+    ..AnalysisOptions::default()
+  };
+  println!(
+    "🧵 Analysis threads per request: {}, requests analyzed at once: {}",
+    options.worker_count(),
+    max_analyses()
+  );
   println!("📖 API Documentation:");
   println!("  GET  /health                     - Health check");
   println!("  POST /analyze-repository         - Analyze repository with format support");
+  println!("  POST /find-keyword               - List a keyword's locations in a repository");
   println!();
 
   axum::serve(listener, app).await.unwrap();
@@ -51,10 +149,21 @@ async fn health_check() -> Json<ApiResponse<&'static str>> {
   })
 }
 
-async fn analyze_repository(
-  Json(request): Json<RepositoryAnalyzeRequest>,
-) -> Result<Response, StatusCode> {
-  let lang = parse_language(&request.language)?;
+async fn analyze_repository(Json(request): Json<RepositoryAnalyzeRequest>) -> Response {
+  let lang: Option<Arc<dyn LanguageSpec>> = match request.language_definition {
+    Some(mut definition) => {
+      definition.merge_categories();
+      if let Err(e) = definition.validate() {
+        return error_response(&e);
+      }
+      Some(Arc::new(definition))
+    }
+    None if is_auto_language(&request.language) => None,
+    None => match parse_language(&request.language) {
+      Ok(lang) => Some(lang),
+      Err(e) => return error_response(&e),
+    },
+  };
 
   let format_str = request
     .format
@@ -64,106 +173,218 @@ async fn analyze_repository(
   let format = match format_str.as_str() {
     "json" => OutputFormat::Json,
     "html" => OutputFormat::Html,
-    _ => return Err(StatusCode::BAD_REQUEST),
+    _ => {
+      return error_response(&AnalyzerError::InvalidArgument(format!(
+        "Unsupported format `{format_str}`; expected json or html"
+      )))
+    }
   };
 
-  // Validate that repository_url is GitHub or GitLab only
-  if !is_git_url(&request.repository_url) {
-    let error_response = r#"{"success": false, "data": null, "error": "Only GitHub and GitLab repository URLs are supported. Expected format: https://github.com/username/repository or https://gitlab.com/username/repository"}"#.to_string();
-    return Ok(
-      Response::builder()
-        .status(StatusCode::BAD_REQUEST)
-        .header(CONTENT_TYPE, "application/json")
-        .body(error_response.into())
-        .unwrap(),
-    );
-  }
-
-  // Validate GitHub repository URL format
-  if request.repository_url.contains("github.com")
-    && !is_valid_github_repo_url(&request.repository_url)
-  {
-    let error_response = r#"{"success": false, "data": null, "error": "Invalid GitHub repository URL format. Expected format: https://github.com/username/repository"}"#.to_string();
-    return Ok(
-      Response::builder()
-        .status(StatusCode::BAD_REQUEST)
-        .header(CONTENT_TYPE, "application/json")
-        .body(error_response.into())
-        .unwrap(),
-    );
+  // Reject an unknown category before cloning anything
+  if let (Some(lang), Some(category)) = (&lang, &request.category) {
+    if let Err(e) = AnalysisResult::new(lang.as_ref()).retain_category(category) {
+      return error_response(&e);
+    }
   }
 
-  // Validate GitLab repository URL format
-  if request.repository_url.contains("gitlab.com")
-    && !is_valid_gitlab_repo_url(&request.repository_url)
-  {
-    let error_response = r#"{"success": false, "data": null, "error": "Invalid GitLab repository URL format. Expected format: https://gitlab.com/username/repository"}"#.to_string();
-    return Ok(
-      Response::builder()
-        .status(StatusCode::BAD_REQUEST)
-        .header(CONTENT_TYPE, "application/json")
-        .body(error_response.into())
-        .unwrap(),
-    );
+  if let Some(response) = repository_url_error(&request.repository_url) {
+    return response;
   }
 
-  match KeywordAnalyzer::analyze_path(&request.repository_url, lang) {
-    Ok(result) => {
-      let sorted_counts = result.get_sorted_counts();
-
-      match format {
-        OutputFormat::Json => {
-          let json_content = generate_json_content(&sorted_counts, result.file_count);
-          Ok(
-            Response::builder()
-              .status(StatusCode::OK)
-              .header(CONTENT_TYPE, "application/json")
-              .body(json_content.into())
-              .unwrap(),
-          )
-        }
-        OutputFormat::Html => {
-          let html_content =
-            generate_html_content(&sorted_counts, result.file_count, result.language);
-          Ok(
-            Response::builder()
-              .status(StatusCode::OK)
-              .header(CONTENT_TYPE, "text/html")
-              .body(html_content.into())
-              .unwrap(),
-          )
-        }
-        _ => Err(StatusCode::BAD_REQUEST),
+  let options = analysis_options(request.encoding, &request.include, &request.exclude);
+  let url = request.repository_url;
+  let category = request.category;
+  run_analysis(move || {
+    let content = match lang {
+      Some(lang) => KeywordAnalyzer::analyze_path_with_options(&url, lang.as_ref(), &options)
+        .and_then(|mut result| {
+          if let Some(category) = &category {
+            result.retain_category(category)?;
+          }
+          Ok(match format {
+            OutputFormat::Html => generate_html_content(&result),
+            _ => generate_json_content(&result),
+          })
+        }),
+      None => {
+        KeywordAnalyzer::analyze_repository_with_options(&url, &options).and_then(|mut analysis| {
+          if let Some(category) = &category {
+            analysis.retain_category(category)?;
+          }
+          Ok(match format {
+            OutputFormat::Html => generate_repository_html_content(&analysis),
+            _ => generate_repository_json_content(&analysis),
+          })
+        })
       }
-    }
-    Err(e) => {
-      let error_response = format!(
-        r#"{{"success": false, "data": null, "error": "{}"}}"#,
-        e.to_string().replace('"', "\\\"")
-      );
-      Ok(
+    };
+
+    match content {
+      Ok(content) => {
+        let content_type = match format {
+          OutputFormat::Html => "text/html",
+          _ => "application/json",
+        };
         Response::builder()
-          .status(StatusCode::INTERNAL_SERVER_ERROR)
-          .header(CONTENT_TYPE, "application/json")
-          .body(error_response.into())
-          .unwrap(),
-      )
+          .status(StatusCode::OK)
+          .header(CONTENT_TYPE, content_type)
+          .body(content.into())
+          .unwrap()
+      }
+      Err(e) => error_response(&e),
+    }
+  })
+  .await
+}
+
+async fn find_keyword(Json(request): Json<FindKeywordRequest>) -> Response {
+  let lang = if is_auto_language(&request.language) {
+    None
+  } else {
+    match parse_language(&request.language) {
+      Ok(lang) => Some(lang),
+      Err(e) => return error_response(&e),
     }
+  };
+
+  let is_keyword = match &lang {
+    Some(lang) => lang.is_keyword(&request.keyword),
+    None => registry::global()
+      .read()
+      .unwrap()
+      .languages()
+      .iter()
+      .any(|spec| spec.is_keyword(&request.keyword)),
+  };
+  if !is_keyword {
+    return error_response(&AnalyzerError::InvalidArgument(format!(
+      "`{}` is not a keyword of the selected languages",
+      request.keyword
+    )));
+  }
+
+  if let Some(response) = repository_url_error(&request.repository_url) {
+    return response;
+  }
+
+  let options = analysis_options(request.encoding, &request.include, &request.exclude);
+  run_analysis(move || {
+    match KeywordAnalyzer::find_occurrences(
+      &request.repository_url,
+      &request.keyword,
+      lang.as_deref(),
+      request.context,
+      &options,
+    ) {
+      Ok(occurrences) => json_response(
+        StatusCode::OK,
+        generate_occurrences_json(&request.keyword, &occurrences),
+      ),
+      Err(e) => error_response(&e),
+    }
+  })
+  .await
+}
+
+/// Runs `work`, which clones and analyzes a repository, on the blocking
+/// thread pool once fewer than [`max_analyses`] others are running, so that
+/// neither the runtime nor the number of analysis threads grows with the
+/// requests.
+async fn run_analysis(work: impl FnOnce() -> Response + Send + 'static) -> Response {
+  static SLOTS: OnceLock<Semaphore> = OnceLock::new();
+  let slots = SLOTS.get_or_init(|| Semaphore::new(max_analyses()));
+  let _permit = slots
+    .acquire()
+    .await
+    .expect("the semaphore is never closed");
+  match tokio::task::spawn_blocking(work).await {
+    Ok(response) => response,
+    Err(_) => json_response(
+      StatusCode::INTERNAL_SERVER_ERROR,
+      error_json("The analysis failed unexpectedly".to_string()),
+    ),
   }
 }
 
-fn parse_language(lang_str: &str) -> Result<Language, StatusCode> {
-  match lang_str.to_lowercase().as_str() {
-    "rust" | "rs" => Ok(Language::Rust),
-    "javascript" | "js" | "typescript" | "ts" => Ok(Language::JavaScript),
-    "ruby" | "rb" => Ok(Language::Ruby),
-    "go" | "golang" => Ok(Language::Golang),
-    "python" | "py" => Ok(Language::Python),
-    "dart" => Ok(Language::Dart),
-    _ => Err(StatusCode::BAD_REQUEST),
+/// The options of a request, analyzed on [`jobs`] threads.
+fn analysis_options(encoding: Encoding, include: &[String], exclude: &[String]) -> AnalysisOptions {
+  AnalysisOptions {
+    jobs: jobs(),
+    encoding,
+    filter: FileFilter {
+      include: include.iter().map(|glob| Glob::new(glob)).collect(),
+      exclude: exclude.iter().map(|glob| Glob::new(glob)).collect(),
+      ..FileFilter::default()
+    },
+    ..AnalysisOptions::default()
   }
 }
 
+/// A 400 response when `url` is not a GitHub or GitLab repository URL.
+fn repository_url_error(url: &str) -> Option<Response> {
+  let message = if !is_git_url(url) {
+    "Only GitHub and GitLab repository URLs are supported. Expected format: https://github.com/username/repository or https://gitlab.com/username/repository"
+  } else if url.contains("github.com") && !is_valid_github_repo_url(url) {
+    "Invalid GitHub repository URL format. Expected format: https://github.com/username/repository"
+  } else if url.contains("gitlab.com") && !is_valid_gitlab_repo_url(url) {
+    "Invalid GitLab repository URL format. Expected format: https://gitlab.com/username/repository"
+  } else {
+    return None;
+  };
+
+  Some(error_response(&AnalyzerError::InvalidArgument(
+    message.to_string(),
+  )))
+}
+
+fn json_response(status: StatusCode, body: String) -> Response {
+  Response::builder()
+    .status(status)
+    .header(CONTENT_TYPE, "application/json")
+    .body(body.into())
+    .unwrap()
+}
+
+/// The [`ApiResponse`] envelope for `error`, with the status of its kind.
+fn error_response(error: &AnalyzerError) -> Response {
+  json_response(error_status(error), error_json(error.to_string()))
+}
+
+fn error_json(message: String) -> String {
+  let body = ApiResponse::<()> {
+    success: false,
+    data: None,
+    error: Some(message),
+  };
+  serde_json::to_string(&body).unwrap()
+}
+
+/// Client mistakes are 4xx, failures of the server or of `git` are 5xx.
+fn error_status(error: &AnalyzerError) -> StatusCode {
+  match error {
+    AnalyzerError::PathNotFound(_) | AnalyzerError::RepositoryNotFound(_) => StatusCode::NOT_FOUND,
+    AnalyzerError::PermissionDenied(_) => StatusCode::FORBIDDEN,
+    AnalyzerError::NonUtf8File(_) | AnalyzerError::NothingToVisualize => {
+      StatusCode::UNPROCESSABLE_ENTITY
+    }
+    AnalyzerError::UnsupportedLanguage(_)
+    | AnalyzerError::InvalidLanguageDefinition { .. }
+    | AnalyzerError::InvalidArgument(_) => StatusCode::BAD_REQUEST,
+    AnalyzerError::CloneFailed { .. } => StatusCode::BAD_GATEWAY,
+    AnalyzerError::Io { .. } | AnalyzerError::OutputWriteFailed { .. } => {
+      StatusCode::INTERNAL_SERVER_ERROR
+    }
+  }
+}
+
+fn is_auto_language(lang_str: &str) -> bool {
+  lang_str.is_empty() || lang_str.eq_ignore_ascii_case("auto")
+}
+
+fn parse_language(lang_str: &str) -> Result<Arc<dyn LanguageSpec>, AnalyzerError> {
+  find_language(lang_str).ok_or_else(|| AnalyzerError::UnsupportedLanguage(lang_str.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -175,6 +396,7 @@ mod tests {
       .route("/", get(health_check))
       .route("/health", get(health_check))
       .route("/analyze-repository", post(analyze_repository))
+      .route("/find-keyword", post(find_keyword))
   }
 
   #[tokio::test]
@@ -206,6 +428,9 @@ mod tests {
     let response = server.post("/analyze-repository").json(&request_body).await;
 
     response.assert_status_bad_request();
+    let body: Value = response.json();
+    assert_eq!(body["success"], false);
+    assert_eq!(body["error"], "Unsupported language `invalid`");
   }
 
   #[tokio::test]
@@ -307,6 +532,32 @@ mod tests {
     }
   }
 
+  #[tokio::test]
+  async fn test_analyze_repository_auto_language() {
+    let app = create_test_app();
+    let server = TestServer::new(app).unwrap();
+
+    // Omitting the language selects auto detection, which passes validation
+    let request_body = json!({
+      "format": "json",
+      "repository_url": "https://github.com/rust-lang"
+    });
+
+    let response = server.post("/analyze-repository").json(&request_body).await;
+
+    response.assert_status_bad_request();
+
+    let body: Value = response.json();
+    assert!(body["error"]
+      .as_str()
+      .unwrap()
+      .contains("Invalid GitHub repository URL format"));
+
+    assert!(is_auto_language(""));
+    assert!(is_auto_language("Auto"));
+    assert!(!is_auto_language("rust"));
+  }
+
   #[tokio::test]
   async fn test_parse_language_valid() {
     assert!(parse_language("rust").is_ok());
@@ -314,6 +565,8 @@ mod tests {
     assert!(parse_language("js").is_ok());
     assert!(parse_language("typescript").is_ok());
     assert!(parse_language("ts").is_ok());
+    assert_eq!(parse_language("js").unwrap().id(), "javascript");
+    assert_eq!(parse_language("ts").unwrap().id(), "typescript");
     assert!(parse_language("ruby").is_ok());
     assert!(parse_language("rb").is_ok());
     assert!(parse_language("go").is_ok());
@@ -346,10 +599,193 @@ mod tests {
       .contains("Invalid GitLab repository URL format"));
   }
 
+  #[tokio::test]
+  async fn test_analyze_repository_inline_language_definition() {
+    let app = create_test_app();
+    let server = TestServer::new(app).unwrap();
+
+    // A valid definition is accepted, so the request fails on the URL check instead
+    let request_body = json!({
+      "language_definition": {
+        "id": "toy",
+        "extensions": ["toy"],
+        "keywords": ["when", "then"],
+        "line_comments": ["--"]
+      },
+      "format": "json",
+      "repository_url": "https://example.com/repo"
+    });
+
+    let response = server.post("/analyze-repository").json(&request_body).await;
+
+    response.assert_status_bad_request();
+    let body: Value = response.json();
+    assert!(body["error"]
+      .as_str()
+      .unwrap()
+      .contains("Only GitHub and GitLab repository URLs are supported"));
+  }
+
+  #[tokio::test]
+  async fn test_analyze_repository_invalid_language_definition() {
+    let app = create_test_app();
+    let server = TestServer::new(app).unwrap();
+
+    let request_body = json!({
+      "language_definition": { "id": "toy", "keywords": ["when"] },
+      "format": "json",
+      "repository_url": "https://github.com/dtolnay/anyhow"
+    });
+
+    let response = server.post("/analyze-repository").json(&request_body).await;
+
+    response.assert_status_bad_request();
+  }
+
+  #[tokio::test]
+  async fn test_analyze_repository_category() {
+    let app = create_test_app();
+    let server = TestServer::new(app).unwrap();
+
+    let request_body = json!({
+      "language": "rust",
+      "repository_url": "https://github.com/dtolnay/anyhow",
+      "category": "loops"
+    });
+    let response = server.post("/analyze-repository").json(&request_body).await;
+    response.assert_status_bad_request();
+    let body: Value = response.json();
+    assert!(body["error"]
+      .as_str()
+      .unwrap()
+      .contains("Unknown category `loops` for Rust"));
+
+    // A known category passes, so the request fails on the URL check instead
+    let request_body = json!({
+      "language": "rust",
+      "repository_url": "https://example.com/repo",
+      "category": "control-flow"
+    });
+    let response = server.post("/analyze-repository").json(&request_body).await;
+    response.assert_status_bad_request();
+    let body: Value = response.json();
+    assert!(body["error"]
+      .as_str()
+      .unwrap()
+      .contains("Only GitHub and GitLab repository URLs are supported"));
+  }
+
+  #[tokio::test]
+  async fn test_find_keyword() {
+    let app = create_test_app();
+    let server = TestServer::new(app).unwrap();
+
+    let request_body = json!({
+      "keyword": "frobnicate",
+      "repository_url": "https://github.com/dtolnay/anyhow"
+    });
+    let response = server.post("/find-keyword").json(&request_body).await;
+    response.assert_status_bad_request();
+    let body: Value = response.json();
+    assert!(body["error"]
+      .as_str()
+      .unwrap()
+      .contains("`frobnicate` is not a keyword"));
+
+    // `def` is a Python keyword but not a Rust one
+    let request_body = json!({
+      "keyword": "def",
+      "language": "rust",
+      "repository_url": "https://github.com/dtolnay/anyhow"
+    });
+    let response = server.post("/find-keyword").json(&request_body).await;
+    response.assert_status_bad_request();
+
+    let request_body = json!({
+      "keyword": "unsafe",
+      "language": "rust",
+      "context": 2,
+      "repository_url": "https://github.com/rust-lang"
+    });
+    let response = server.post("/find-keyword").json(&request_body).await;
+    response.assert_status_bad_request();
+    let body: Value = response.json();
+    assert!(body["error"]
+      .as_str()
+      .unwrap()
+      .contains("Invalid GitHub repository URL format"));
+  }
+
   #[tokio::test]
   async fn test_parse_language_invalid() {
     assert!(parse_language("invalid").is_err());
     assert!(parse_language("").is_err());
     assert!(parse_language("cpp").is_err());
   }
+
+  #[tokio::test]
+  async fn test_error_response_is_valid_json() {
+    let error = AnalyzerError::CloneFailed {
+      url: "https://github.com/a/b".to_string(),
+      message: "fatal: \"b\"\n\tC:\\path\u{1}".to_string(),
+    };
+    let response = error_response(&error);
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+      .await
+      .unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["success"], false);
+    assert_eq!(body["data"], Value::Null);
+    assert_eq!(body["error"], error.to_string());
+  }
+
+  #[tokio::test]
+  async fn test_run_analysis() {
+    let response = run_analysis(|| json_response(StatusCode::OK, "{}".to_string())).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = run_analysis(|| panic!("analysis panicked")).await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+  }
+
+  #[test]
+  fn test_find_keyword_request_filters() {
+    let request: FindKeywordRequest = serde_json::from_value(json!({
+      "keyword": "unsafe",
+      "repository_url": "https://github.com/dtolnay/anyhow",
+      "encoding": "detect",
+      "exclude": ["vendor/"]
+    }))
+    .unwrap();
+    let options = analysis_options(request.encoding, &request.include, &request.exclude);
+    assert_eq!(options.encoding, Encoding::Detect);
+    assert_eq!(options.filter.exclude, [Glob::new("vendor/")]);
+    assert!(options.filter.include.is_empty());
+  }
+
+  #[test]
+  fn test_error_status() {
+    let status = |error| error_status(&error);
+    assert_eq!(
+      status(AnalyzerError::RepositoryNotFound("url".to_string())),
+      StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+      status(AnalyzerError::CloneFailed {
+        url: "url".to_string(),
+        message: "timeout".to_string(),
+      }),
+      StatusCode::BAD_GATEWAY
+    );
+    assert_eq!(
+      status(AnalyzerError::InvalidArgument("x".to_string())),
+      StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+      status(AnalyzerError::NonUtf8File("a.rs".into())),
+      StatusCode::UNPROCESSABLE_ENTITY
+    );
+  }
 }
@@ -1,39 +1,331 @@
-use keyword_analyzer_shared::{KeywordAnalyzer, Language, OutputFormat};
+use keyword_analyzer_shared::{
+  find_language, format_comments_plain, format_identifiers, format_lines_plain, format_occurrences,
+  format_per_file_plain, format_repository_comments_plain, format_repository_lines_plain,
+  format_repository_per_file_plain, format_repository_tree_plain, format_tree_plain,
+  format_unsafe_audit, generate_identifiers_json, generate_occurrences_json,
+  generate_unsafe_audit_json, golang, is_git_url, register_language, AnalysisOptions,
+  AnalysisResult, AnalyzerError, Encoding, Glob, KeywordAnalyzer, LanguageDefinition, LanguageSpec,
+  NamingConvention, OutputFormat,
+};
 use std::env;
+use std::path::Path;
+use std::sync::Arc;
+
+struct CliOptions<'a> {
+  target_path: &'a str,
+  output_format: OutputFormat,
+  language: Option<String>,
+  language_defs: Vec<String>,
+  output_file: Option<String>,
+  go_directives: bool,
+  view: PlainView,
+  sort_by: Option<String>,
+  category: Option<String>,
+  /// Keyword given to the `find` mode.
+  find: Option<String>,
+  context: usize,
+  /// Whether to run the `identifiers` mode.
+  identifiers: bool,
+  /// Whether to run the `unsafe` audit.
+  unsafe_audit: bool,
+  naming: Option<String>,
+  top: usize,
+  analysis: AnalysisOptions,
+  /// Highest file complexity accepted before exiting with an error.
+  max_complexity: Option<usize>,
+}
+
+/// What plain output shows. The other formats always include every section.
+#[derive(Clone, Copy, PartialEq)]
+enum PlainView {
+  Summary,
+  PerFile,
+  Tree,
+  Lines,
+  Comments,
+}
 
 fn main() {
   let args: Vec<String> = env::args().collect();
-  let (target_path, output_format, language, output_file) = parse_args(&args);
+  let options = parse_args(&args);
+
+  let spec = resolve_language(&options).unwrap_or_else(|e| exit_with(e));
 
-  if !matches!(output_format, OutputFormat::Json) {
-    println!("Analyzing files in: {target_path}");
+  // Catch an unknown category before walking the tree when the language is known
+  if let (Some(spec), Some(category)) = (&spec, &options.category) {
+    exit_on_error(AnalysisResult::new(spec.as_ref()).retain_category(category));
   }
 
-  match KeywordAnalyzer::analyze_path(target_path, language) {
-    Ok(result) => {
-      eprintln!("Analysis completed! Found {} files", result.file_count);
-      eprintln!("Generating results...\n");
+  if let Some(keyword) = &options.find {
+    find_keyword(&options, keyword, spec.as_deref());
+    return;
+  }
+  if options.identifiers {
+    report_identifiers(&options, spec.as_deref());
+    return;
+  }
+  if options.unsafe_audit {
+    audit_unsafe(&options);
+    return;
+  }
+
+  if !matches!(options.output_format, OutputFormat::Json) {
+    println!("Analyzing files in: {}", options.target_path);
+  }
 
-      match KeywordAnalyzer::format_output(&result, output_format, output_file) {
-        Ok(message) => {
-          println!("{message}");
+  let view = match options.output_format {
+    OutputFormat::Plain => options.view,
+    _ => PlainView::Summary,
+  };
+  let sort_by = options.sort_by.as_deref();
+  let category = options.category.as_deref();
+  let mut too_complex = Vec::new();
+
+  let outcome = match spec {
+    Some(spec) => KeywordAnalyzer::analyze_path_with_options(
+      options.target_path,
+      spec.as_ref(),
+      &options.analysis,
+    )
+    .map(|mut result| {
+      report_completed(result.file_count);
+      if let Some(category) = category {
+        exit_on_error(result.retain_category(category));
+      }
+      if let Some(max) = options.max_complexity {
+        too_complex.extend(complexity_violations(&result, max));
+      }
+      match view {
+        PlainView::PerFile => Ok(format_per_file_plain(&result, sort_by)),
+        PlainView::Tree => Ok(format_tree_plain(&result)),
+        PlainView::Lines => Ok(format_lines_plain(&result)),
+        PlainView::Comments => Ok(format_comments_plain(&result)),
+        PlainView::Summary => {
+          KeywordAnalyzer::format_output(&result, options.output_format, options.output_file)
         }
-        Err(e) => eprintln!("Error formatting output: {e}"),
       }
+    }),
+    None => {
+      KeywordAnalyzer::analyze_repository_with_options(options.target_path, &options.analysis).map(
+        |mut analysis| {
+          report_completed(analysis.file_count);
+          if let Some(category) = category {
+            exit_on_error(analysis.retain_category(category));
+          }
+          if let Some(max) = options.max_complexity {
+            for result in &analysis.results {
+              too_complex.extend(complexity_violations(result, max));
+            }
+          }
+          match view {
+            PlainView::PerFile => Ok(format_repository_per_file_plain(&analysis, sort_by)),
+            PlainView::Tree => Ok(format_repository_tree_plain(&analysis)),
+            PlainView::Lines => Ok(format_repository_lines_plain(&analysis)),
+            PlainView::Comments => Ok(format_repository_comments_plain(&analysis)),
+            PlainView::Summary => KeywordAnalyzer::format_repository_output(
+              &analysis,
+              options.output_format,
+              options.output_file,
+            ),
+          }
+        },
+      )
     }
-    Err(e) => {
-      eprintln!("Error: {e}");
+  };
+
+  match outcome {
+    Ok(Ok(message)) => println!("{message}"),
+    Ok(Err(e)) | Err(e) => exit_with(e),
+  }
+
+  if options.go_directives {
+    print_go_directives(options.target_path, &options.analysis);
+  }
+
+  if let (Some(max), false) = (options.max_complexity, too_complex.is_empty()) {
+    eprintln!(
+      "Error: {} files exceed the maximum complexity of {max}:",
+      too_complex.len()
+    );
+    for violation in &too_complex {
+      eprintln!("  {violation}");
     }
+    std::process::exit(1);
+  }
+}
+
+/// `path: complexity N` for every file of `result` more complex than `max`,
+/// most complex first.
+fn complexity_violations(result: &AnalysisResult, max: usize) -> Vec<String> {
+  result
+    .most_complex_files(usize::MAX)
+    .into_iter()
+    .filter(|file| file.complexity.complexity > max)
+    .map(|file| format!("{}: complexity {}", file.path, file.complexity.complexity))
+    .collect()
+}
+
+/// Lists every occurrence of `keyword`, grep-style or as JSON.
+fn find_keyword(options: &CliOptions, keyword: &str, spec: Option<&dyn LanguageSpec>) {
+  let occurrences = KeywordAnalyzer::find_occurrences(
+    options.target_path,
+    keyword,
+    spec,
+    options.context,
+    &options.analysis,
+  );
+  let occurrences = occurrences.unwrap_or_else(|e| exit_with(e));
+
+  match options.output_format {
+    OutputFormat::Json => println!("{}", generate_occurrences_json(keyword, &occurrences)),
+    _ => {
+      print!("{}", format_occurrences(&occurrences));
+      eprintln!("Found {} occurrences of `{keyword}`", occurrences.len());
+    }
+  }
+}
+
+/// Prints the most common identifiers, naming violations and vocabulary per
+/// file of each language.
+fn report_identifiers(options: &CliOptions, spec: Option<&dyn LanguageSpec>) {
+  let naming = match options.naming.as_deref().map(NamingConvention::parse) {
+    Some(Ok(naming)) => Some(naming),
+    Some(Err(e)) => exit_with(e),
+    None => None,
+  };
+
+  let reports =
+    KeywordAnalyzer::analyze_identifiers(options.target_path, spec, naming, &options.analysis)
+      .unwrap_or_else(|e| exit_with(e));
+
+  match options.output_format {
+    OutputFormat::Json => println!("{}", generate_identifiers_json(&reports, options.top)),
+    _ => print!("{}", format_identifiers(&reports, options.top)),
+  }
+}
+
+/// Lists every `unsafe` block, function, impl and trait of the Rust files,
+/// with per-crate totals.
+fn audit_unsafe(options: &CliOptions) {
+  let audit = KeywordAnalyzer::audit_unsafe(options.target_path, &options.analysis)
+    .unwrap_or_else(|e| exit_with(e));
+
+  match options.output_format {
+    OutputFormat::Json => println!("{}", generate_unsafe_audit_json(&audit)),
+    _ => print!("{}", format_unsafe_audit(&audit)),
+  }
+}
+
+fn print_go_directives(target_path: &str, analysis: &AnalysisOptions) {
+  if is_git_url(target_path) {
+    eprintln!("Error: --go-directives needs a local path");
+    return;
+  }
+
+  match golang::collect_directives(Path::new(target_path), analysis) {
+    Ok(directives) => print!("{}", golang::format_directives(&directives)),
+    Err(e) => eprintln!("Error collecting Go directives: {e}"),
+  }
+}
+
+fn exit_on_error(result: Result<(), AnalyzerError>) {
+  if let Err(e) = result {
+    exit_with(e);
+  }
+}
+
+/// Prints `error` and exits with its [`exit_code`].
+fn exit_with(error: AnalyzerError) -> ! {
+  eprintln!("Error: {error}");
+  std::process::exit(exit_code(&error))
+}
+
+/// A distinct exit code per kind of failure, so scripts can tell a typo in
+/// a path from a failed clone. 1 is left for other failures, such as the
+/// `--max-complexity` gate.
+fn exit_code(error: &AnalyzerError) -> i32 {
+  match error {
+    AnalyzerError::InvalidArgument(_) => 2,
+    AnalyzerError::UnsupportedLanguage(_) => 3,
+    AnalyzerError::InvalidLanguageDefinition { .. } => 4,
+    AnalyzerError::PathNotFound(_) => 5,
+    AnalyzerError::PermissionDenied(_) => 6,
+    AnalyzerError::NonUtf8File(_) => 7,
+    AnalyzerError::Io { .. } => 8,
+    AnalyzerError::RepositoryNotFound(_) => 9,
+    AnalyzerError::CloneFailed { .. } => 10,
+    AnalyzerError::NothingToVisualize => 11,
+    AnalyzerError::OutputWriteFailed { .. } => 12,
   }
 }
 
-fn parse_args(args: &[String]) -> (&str, OutputFormat, Language, Option<String>) {
+fn report_completed(file_count: usize) {
+  eprintln!("Analysis completed! Found {file_count} files");
+  eprintln!("Generating results...\n");
+}
+
+/// Registers every `--language-def` file and picks the language to analyze.
+/// Without `--language`, the last loaded definition wins. `None` means every
+/// detected language is analyzed (`--language auto`, the default).
+fn resolve_language(options: &CliOptions) -> Result<Option<Arc<dyn LanguageSpec>>, AnalyzerError> {
+  let mut last_loaded = None;
+  for def_path in &options.language_defs {
+    let definition = LanguageDefinition::from_file(Path::new(def_path))?;
+    eprintln!(
+      "Loaded language definition: {} ({def_path})",
+      definition.name()
+    );
+    last_loaded = Some(definition.id.clone());
+    register_language(Arc::new(definition));
+  }
+
+  let spec = match options.language.as_deref().or(last_loaded.as_deref()) {
+    None | Some("auto") => None,
+    Some(name) => {
+      Some(find_language(name).ok_or_else(|| AnalyzerError::UnsupportedLanguage(name.to_string()))?)
+    }
+  };
+  Ok(spec)
+}
+
+fn parse_args(args: &[String]) -> CliOptions<'_> {
   let mut target_path = ".";
   let mut output_format = OutputFormat::Plain;
-  let mut language = Language::Rust;
+  let mut language: Option<String> = None;
+  let mut language_defs = Vec::new();
   let mut output_file: Option<String> = None;
+  let mut go_directives = false;
+  let mut view = PlainView::Summary;
+  let mut sort_by: Option<String> = None;
+  let mut category: Option<String> = None;
+  let mut find: Option<String> = None;
+  let mut context = 0;
+  let mut identifiers = false;
+  let mut unsafe_audit = false;
+  let mut naming: Option<String> = None;
+  let mut top = 20;
+  let mut analysis = AnalysisOptions::default();
+  let mut max_complexity = None;
 
   let mut i = 1;
+  if args.get(1).map(String::as_str) == Some("find") {
+    match args.get(2) {
+      Some(keyword) => find = Some(keyword.clone()),
+      None => {
+        exit_with(AnalyzerError::InvalidArgument(
+          "find needs a keyword, e.g. `keyword-analyzer find unsafe src/`".to_string(),
+        ));
+      }
+    }
+    i = 3;
+  } else if args.get(1).map(String::as_str) == Some("identifiers") {
+    identifiers = true;
+    i = 2;
+  } else if args.get(1).map(String::as_str) == Some("unsafe") {
+    unsafe_audit = true;
+    i = 2;
+  }
+
   while i < args.len() {
     match args[i].as_str() {
       "--format" | "-f" => {
@@ -52,15 +344,15 @@ fn parse_args(args: &[String]) -> (&str, OutputFormat, Language, Option<String>)
       }
       "--language" | "-l" => {
         if i + 1 < args.len() {
-          language = match args[i + 1].as_str() {
-            "js" | "ts" | "javascript" | "typescript" => Language::JavaScript,
-            "rust" | "rs" => Language::Rust,
-            "ruby" | "rb" => Language::Ruby,
-            "go" | "golang" => Language::Golang,
-            "python" | "py" => Language::Python,
-            "dart" => Language::Dart,
-            _ => Language::Rust,
-          };
+          language = Some(args[i + 1].clone());
+          i += 2;
+        } else {
+          i += 1;
+        }
+      }
+      "--language-def" => {
+        if i + 1 < args.len() {
+          language_defs.push(args[i + 1].clone());
           i += 2;
         } else {
           i += 1;
@@ -74,6 +366,135 @@ fn parse_args(args: &[String]) -> (&str, OutputFormat, Language, Option<String>)
           i += 1;
         }
       }
+      "--go-directives" => {
+        go_directives = true;
+        i += 1;
+      }
+      "--per-file" => {
+        view = PlainView::PerFile;
+        i += 1;
+      }
+      "--tree" => {
+        view = PlainView::Tree;
+        i += 1;
+      }
+      "--lines" => {
+        view = PlainView::Lines;
+        i += 1;
+      }
+      "--comments" => {
+        view = PlainView::Comments;
+        i += 1;
+      }
+      "--sort-by" => {
+        if i + 1 < args.len() {
+          view = PlainView::PerFile;
+          sort_by = Some(args[i + 1].clone());
+          i += 2;
+        } else {
+          i += 1;
+        }
+      }
+      "--category" => {
+        if i + 1 < args.len() {
+          category = Some(args[i + 1].clone());
+          i += 2;
+        } else {
+          i += 1;
+        }
+      }
+      "--context" | "-C" => {
+        if i + 1 < args.len() {
+          context = args[i + 1].parse().unwrap_or(0);
+          i += 2;
+        } else {
+          i += 1;
+        }
+      }
+      "--naming" => {
+        if i + 1 < args.len() {
+          naming = Some(args[i + 1].clone());
+          i += 2;
+        } else {
+          i += 1;
+        }
+      }
+      "--top" => {
+        if i + 1 < args.len() {
+          top = args[i + 1].parse().unwrap_or(top);
+          i += 2;
+        } else {
+          i += 1;
+        }
+      }
+      "--ngram" => {
+        if i + 1 < args.len() {
+          let length = args[i + 1].parse().unwrap_or(analysis.sequences.length);
+          analysis.sequences.length = length.max(2);
+          i += 2;
+        } else {
+          i += 1;
+        }
+      }
+      "--window" => {
+        if i + 1 < args.len() {
+          let window = args[i + 1].parse().unwrap_or(analysis.sequences.window);
+          analysis.sequences.window = window.max(1);
+          i += 2;
+        } else {
+          i += 1;
+        }
+      }
+      "--include" | "--exclude" => {
+        if i + 1 < args.len() {
+          let glob = Glob::new(&args[i + 1]);
+          if args[i] == "--include" {
+            analysis.filter.include.push(glob);
+          } else {
+            analysis.filter.exclude.push(glob);
+          }
+          i += 2;
+        } else {
+          i += 1;
+        }
+      }
+      "--jobs" | "-j" => {
+        if i + 1 < args.len() {
+          analysis.jobs = args[i + 1].parse().unwrap_or(analysis.jobs);
+          i += 2;
+        } else {
+          i += 1;
+        }
+      }
+      "--no-ignore" => {
+        analysis.filter.no_ignore_files = true;
+        i += 1;
+      }
+      "--include-generated" => {
+        analysis.filter.include_generated = true;
+        i += 1;
+      }
+      "--encoding" => {
+        if i + 1 < args.len() {
+          analysis.encoding = Encoding::parse(&args[i + 1]).unwrap_or_else(|| {
+            exit_with(AnalyzerError::InvalidArgument(format!(
+              "Unknown encoding `{}` (expected utf8, lossy or detect)",
+              args[i + 1]
+            )))
+          });
+          i += 2;
+        } else {
+          i += 1;
+        }
+      }
+      "--max-complexity" => {
+        if i + 1 < args.len() {
+          max_complexity = args[i + 1].parse().ok();
+          i += 2;
+        } else {
+          i += 1;
+        }
+      }
       "--help" | "-h" => {
         print_help();
         std::process::exit(0);
@@ -86,7 +507,25 @@ fn parse_args(args: &[String]) -> (&str, OutputFormat, Language, Option<String>)
     }
   }
 
-  (target_path, output_format, language, output_file)
+  CliOptions {
+    target_path,
+    output_format,
+    language,
+    language_defs,
+    output_file,
+    go_directives,
+    view,
+    sort_by,
+    category,
+    find,
+    context,
+    identifiers,
+    unsafe_audit,
+    naming,
+    top,
+    analysis,
+    max_complexity,
+  }
 }
 
 fn print_help() {
@@ -94,17 +533,68 @@ fn print_help() {
   println!();
   println!("USAGE:");
   println!("    keyword-analyzer [PATH] [OPTIONS]");
+  println!("    keyword-analyzer find <KEYWORD> [PATH] [OPTIONS]");
+  println!("    keyword-analyzer identifiers [PATH] [OPTIONS]");
+  println!("    keyword-analyzer unsafe [PATH] [OPTIONS]");
   println!();
   println!("ARGS:");
   println!("    <PATH>    Directory, file, or Git URL (GitHub/GitLab) to analyze [default: .]");
+  println!(
+    "    find      List every use of a keyword as path:line:column, skipping comments and strings"
+  );
+  println!(
+    "    identifiers  Count identifiers: most common names, naming violations, vocabulary per file"
+  );
+  println!(
+    "    unsafe    Audit Rust `unsafe` blocks, fns, impls and traits for SAFETY comments, per crate"
+  );
   println!();
   println!("OPTIONS:");
-  println!("    -l, --language <LANG>    Language to analyze [default: rust] [possible values: rust, rs, js, ts, ruby, rb, go, golang, python, py, dart]");
+  println!("    -l, --language <LANG>    Language to analyze [default: auto] [possible values: auto, rust, rs, javascript, js, typescript, ts, ruby, rb, go, golang, python, py, dart]");
+  println!(
+    "        --language-def <FILE>  Load a language definition (.toml or .json); may be repeated"
+  );
   println!("    -f, --format <FORMAT>    Output format [default: plain] [possible values: plain, json, csv, html, graph]");
   println!("    -o, --output <FILE>      Output file path (for json, csv, html, graph formats)");
+  println!(
+    "        --go-directives      List //go:build and //go:generate directives (local paths)"
+  );
+  println!("        --per-file           Show a per-file keyword table instead of the summary");
+  println!(
+    "        --sort-by <KEYWORD>  Sort the per-file table by a keyword (implies --per-file)"
+  );
+  println!("        --tree               Show keyword totals rolled up per directory and package");
+  println!(
+    "        --lines              Show code, comment, blank and mixed lines per language and directory"
+  );
+  println!(
+    "        --comments           Show TODO/FIXME/HACK/XXX markers, comment ratio and doc coverage"
+  );
+  println!(
+    "        --category <NAME>    Only count keywords of one category, e.g. \"control flow\""
+  );
+  println!("    -C, --context <N>        Lines of context around each `find` result [default: 0]");
+  println!("        --ngram <N>          Keywords per reported keyword sequence [default: 2]");
+  println!("        --window <N>         Most tokens between consecutive keywords of a sequence [default: 2]");
+  println!("        --max-complexity <N> Exit with an error when a file's complexity exceeds N");
+  println!(
+    "    -j, --jobs <N>           Files analyzed in parallel; 0 uses every core [default: 0]"
+  );
+  println!("        --include <GLOB>     Only analyze files matching GLOB, e.g. 'src/**/*.rs' (repeatable)");
+  println!("        --exclude <GLOB>     Leave out files and directories matching GLOB, e.g. '*.pb.go' (repeatable)");
+  println!("        --no-ignore          Don't honor .gitignore, .ignore and .keyword-analyzer-ignore files");
+  println!(
+    "        --include-generated  Also analyze files with a generated-code header (`DO NOT EDIT`)"
+  );
+  println!("        --encoding <ENCODING>  Decode files as utf8, lossy (invalid UTF-8 replaced) or detect (UTF-16 BOM, Latin-1) [default: utf8]");
+  println!("        --naming <CONVENTION>  Only count `identifiers` following snake_case, camelCase, PascalCase or SCREAMING_SNAKE_CASE");
+  println!(
+    "        --top <N>            Identifiers listed per `identifiers` section [default: 20]"
+  );
   println!("    -h, --help               Print help information");
   println!();
   println!("EXAMPLES:");
+  println!("    keyword-analyzer");
   println!("    keyword-analyzer --language rust");
   println!("    keyword-analyzer --language js src/");
   println!("    keyword-analyzer --language ruby lib/");
@@ -115,9 +605,34 @@ fn print_help() {
   println!("    keyword-analyzer -l py github.com/psf/requests");
   println!("    keyword-analyzer -l rs gitlab.com/gitlab-org/gitlab");
   println!("    keyword-analyzer -l go https://github.com/golang/go");
+  println!("    keyword-analyzer -l go --go-directives cmd/");
+  println!("    keyword-analyzer -l rust --per-file --sort-by unsafe src/");
+  println!("    keyword-analyzer --tree .");
+  println!("    keyword-analyzer --lines .");
+  println!("    keyword-analyzer -l rust --comments src/");
+  println!("    keyword-analyzer find unsafe -l rust -C 2 src/");
+  println!("    keyword-analyzer -l rust --category concurrency src/");
+  println!("    keyword-analyzer -l rust --ngram 3 --window 1 src/");
+  println!("    keyword-analyzer --max-complexity 50 src/");
+  println!("    keyword-analyzer --encoding detect legacy/");
+  println!("    keyword-analyzer -j 8 --per-file .");
+  println!("    keyword-analyzer -l go --exclude '*_test.go' --exclude 'internal/mocks/' .");
+  println!("    keyword-analyzer identifiers -l go --top 50 .");
+  println!("    keyword-analyzer identifiers --naming camelCase -f json src/");
+  println!("    keyword-analyzer unsafe -f json . > unsafe-audit.json");
   println!("    keyword-analyzer --format json --language python https://github.com/django/django");
   println!("    keyword-analyzer --format json --output results.json --language rust src/");
   println!("    keyword-analyzer --format html --output analysis.html --language js");
   println!("    keyword-analyzer --format graph --output chart.svg --language rust");
   println!("    keyword-analyzer -f csv -o data.csv -l python");
+  println!("    keyword-analyzer --language-def mydsl.toml scripts/");
+  println!("    keyword-analyzer --format html --language auto https://github.com/rails/rails");
+  println!();
+  println!("EXIT CODES:");
+  println!("    1   --max-complexity exceeded        7   file is not UTF-8");
+  println!("    2   invalid argument                 8   other I/O error");
+  println!("    3   unsupported language             9   repository not found");
+  println!("    4   invalid language definition      10  git clone failed");
+  println!("    5   path not found                   11  nothing to visualize");
+  println!("    6   permission denied                12  output could not be written");
 }
@@ -0,0 +1,299 @@
+use serde::{Deserialize, Serialize};
+
+use crate::lexer::{Source, Token, TokenKind};
+use crate::spec::LanguageSpec;
+
+/// Words that mark a comment as a note to come back to.
+pub const MARKERS: [&str; 4] = ["TODO", "FIXME", "HACK", "XXX"];
+
+/// A `TODO`-style marker found in a comment.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Marker {
+  /// One of [`MARKERS`].
+  pub kind: String,
+  /// 1-based line number of the marker.
+  pub line: usize,
+  /// 1-based column, in characters.
+  pub column: usize,
+  /// The name in parentheses after the marker, as in `TODO(alice)`.
+  pub owner: Option<String>,
+  /// The rest of the comment line.
+  pub text: String,
+}
+
+/// How many documentable items (functions, classes, ...) have a doc comment
+/// or docstring.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocCoverage {
+  pub documented: usize,
+  pub items: usize,
+}
+
+impl DocCoverage {
+  pub fn add(&mut self, other: &DocCoverage) {
+    self.documented += other.documented;
+    self.items += other.items;
+  }
+
+  /// Share of documented items from 0 to 100, `None` without items.
+  pub fn percentage(&self) -> Option<f64> {
+    (self.items > 0).then(|| self.documented as f64 * 100.0 / self.items as f64)
+  }
+}
+
+/// What the comment analysis found in one file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommentScan {
+  pub markers: Vec<Marker>,
+  pub docs: DocCoverage,
+}
+
+/// Looks through the comments of `content`, already lexed into `tokens`, for
+/// markers and measures how many of its items are documented.
+pub fn scan(spec: &dyn LanguageSpec, content: &str, tokens: &[Token]) -> CommentScan {
+  let source = Source::new(content);
+  let mut scan = CommentScan::default();
+
+  for (index, token) in tokens.iter().enumerate() {
+    if token.kind == TokenKind::Comment {
+      scan
+        .markers
+        .extend(find_markers(token.text, token.offset, &source));
+    } else if is_documentable(spec, content, tokens, index, &source) {
+      scan.docs.items += 1;
+      let documented = if spec.definition().docstrings {
+        has_docstring(tokens, index)
+      } else {
+        has_doc_comment(spec, tokens, index, &source)
+      };
+      if documented {
+        scan.docs.documented += 1;
+      }
+    }
+  }
+
+  scan
+}
+
+fn find_markers(comment: &str, offset: usize, source: &Source) -> Vec<Marker> {
+  // A backtick quotes the word in prose, as in "`TODO` comments"
+  let is_word_char = |c: char| c.is_alphanumeric() || c == '_' || c == '`';
+  let mut markers = Vec::new();
+
+  for (i, _) in comment.char_indices() {
+    let Some(kind) = MARKERS.iter().find(|kind| comment[i..].starts_with(**kind)) else {
+      continue;
+    };
+    let rest = &comment[i + kind.len()..];
+    if comment[..i].chars().next_back().is_some_and(is_word_char)
+      || rest.chars().next().is_some_and(is_word_char)
+    {
+      continue;
+    }
+
+    let rest = rest.lines().next().unwrap_or("");
+    let (owner, rest) = match rest.strip_prefix('(').and_then(|r| r.split_once(')')) {
+      Some((owner, rest)) => (Some(owner.trim()).filter(|o| !o.is_empty()), rest),
+      None => (None, rest),
+    };
+    let text = rest.trim_start_matches(':').trim();
+    let text = text.strip_suffix("*/").unwrap_or(text).trim_end();
+
+    let position = offset + i;
+    markers.push(Marker {
+      kind: kind.to_string(),
+      line: source.line_of(position) + 1,
+      column: source.before(position).chars().count() + 1,
+      owner: owner.map(str::to_string),
+      text: text.to_string(),
+    });
+  }
+
+  markers
+}
+
+/// Whether `tokens[index]` introduces an item that should be documented: a
+/// documented keyword followed by a name, or at the start of its line (Go
+/// methods start with `func (r *T)`).
+fn is_documentable(
+  spec: &dyn LanguageSpec,
+  content: &str,
+  tokens: &[Token],
+  index: usize,
+  source: &Source,
+) -> bool {
+  let token = &tokens[index];
+  token.kind == TokenKind::Word
+    && spec
+      .definition()
+      .documented_keywords
+      .iter()
+      .any(|keyword| keyword == token.text)
+    && spec.is_keyword_token(content, tokens, index)
+    && (tokens
+      .get(index + 1)
+      .is_some_and(|next| next.kind == TokenKind::Word)
+      || source.before(token.offset).trim().is_empty())
+}
+
+/// Whether a doc comment comes right before the item at `tokens[index]`,
+/// looking past modifiers on the same line (`pub async fn`) and attribute or
+/// decorator lines (`#[derive(Debug)]`, `@override`).
+fn has_doc_comment(
+  spec: &dyn LanguageSpec,
+  tokens: &[Token],
+  index: usize,
+  source: &Source,
+) -> bool {
+  let mut line = source.line_of(tokens[index].offset);
+  let mut j = index;
+
+  loop {
+    while j > 0
+      && tokens[j - 1].kind != TokenKind::Comment
+      && source.line_of(tokens[j - 1].offset) == line
+    {
+      j -= 1;
+    }
+    let Some(token) = j.checked_sub(1).map(|previous| &tokens[previous]) else {
+      return false;
+    };
+
+    if token.kind == TokenKind::Comment {
+      return source.before(token.offset).trim().is_empty()
+        && spec
+          .definition()
+          .doc_comments
+          .iter()
+          .any(|prefix| token.text.starts_with(prefix.as_str()));
+    }
+
+    line = source.line_of(token.offset);
+    let text = source.line_text(line).trim_start();
+    if !text.starts_with("#[") && !text.starts_with('@') {
+      return false;
+    }
+  }
+}
+
+/// Whether the body of the `def` or `class` at `tokens[index]` starts with a
+/// string: the token after the first `:` outside brackets.
+fn has_docstring(tokens: &[Token], index: usize) -> bool {
+  let mut depth = 0usize;
+  for (j, token) in tokens.iter().enumerate().skip(index + 1) {
+    if token.kind != TokenKind::Punct {
+      continue;
+    }
+    match token.text {
+      "(" | "[" | "{" => depth += 1,
+      ")" | "]" | "}" => depth = depth.saturating_sub(1),
+      ":" if depth == 0 => {
+        return tokens
+          .get(j + 1)
+          .is_some_and(|next| next.kind == TokenKind::Str);
+      }
+      _ => {}
+    }
+  }
+  false
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Language;
+
+  fn scan_source(language: Language, content: &str) -> CommentScan {
+    let spec = language.spec();
+    let tokens = spec.tokenize(content);
+    scan(spec.as_ref(), content, &tokens)
+  }
+
+  #[test]
+  fn test_markers() {
+    let source = "\
+// TODO(alice): split this up
+fn main() {} /* FIXME leaks memory */
+// TODOS, XXXL and `FIXME` are not markers, HACK is
+";
+    let markers: Vec<_> = scan_source(Language::Rust, source)
+      .markers
+      .into_iter()
+      .map(|m| (m.kind, m.line, m.column, m.owner, m.text))
+      .collect();
+    assert_eq!(
+      markers,
+      [
+        (
+          "TODO".to_string(),
+          1,
+          4,
+          Some("alice".to_string()),
+          "split this up".to_string()
+        ),
+        ("FIXME".to_string(), 2, 17, None, "leaks memory".to_string()),
+        ("HACK".to_string(), 3, 45, None, "is".to_string()),
+      ]
+    );
+
+    // Markers in strings are not comments
+    assert!(scan_source(Language::Python, "x = 'TODO: later'\n")
+      .markers
+      .is_empty());
+  }
+
+  #[test]
+  fn test_rust_doc_coverage() {
+    let source = "\
+/// Documented.
+#[derive(Debug)]
+pub struct A;
+
+// Plain comment.
+fn b() {}
+
+/** Documented. */
+pub async fn c(f: fn(u8) -> u8) {}
+
+let x = 1; /// Trailing
+enum D {}
+";
+    assert_eq!(
+      scan_source(Language::Rust, source).docs,
+      DocCoverage {
+        documented: 2,
+        items: 4,
+      }
+    );
+  }
+
+  #[test]
+  fn test_docstrings_and_go_methods() {
+    let python = "\
+class A:
+    \"\"\"Documented.\"\"\"
+
+    def f(self, x: int = {'a': 1}) -> str:
+        '''Documented.'''
+
+    @property
+    def g(self):
+        return 1
+";
+    let docs = scan_source(Language::Python, python).docs;
+    assert_eq!((docs.documented, docs.items), (2, 3));
+    assert_eq!(docs.percentage().map(|p| p.round()), Some(67.0));
+
+    let go = "\
+// Run starts the server.
+func (s *Server) Run() {
+\tgo func() {}()
+}
+
+func helper() {}
+";
+    let docs = scan_source(Language::Golang, go).docs;
+    assert_eq!((docs.documented, docs.items), (1, 2));
+    assert_eq!(DocCoverage::default().percentage(), None);
+  }
+}
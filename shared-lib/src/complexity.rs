@@ -0,0 +1,412 @@
+use serde::{Deserialize, Serialize};
+
+use crate::lexer::{Source, Token, TokenKind};
+use crate::spec::LanguageSpec;
+
+/// Approximate cyclomatic complexity of a function: one plus its decision
+/// points, not counting those of functions nested inside it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionComplexity {
+  /// The function's name, or `<anonymous>`.
+  pub name: String,
+  /// 1-based line of the function keyword.
+  pub line: usize,
+  pub complexity: usize,
+}
+
+/// Approximate complexity of a file and of the functions found in it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileComplexity {
+  /// Every decision point in the file plus one per function, so the sum of
+  /// the function complexities and the decisions outside any function.
+  pub complexity: usize,
+  pub functions: Vec<FunctionComplexity>,
+}
+
+impl FileComplexity {
+  /// The function with the highest complexity, the first one on ties.
+  pub fn most_complex(&self) -> Option<&FunctionComplexity> {
+    self
+      .functions
+      .iter()
+      .rev()
+      .max_by_key(|function| function.complexity)
+  }
+}
+
+/// Estimates the complexity of `content`, already lexed into `tokens`.
+///
+/// Decision points are the language's `decision_keywords` (`if`, `while`,
+/// `case`, `except`, ...) and `decision_operators` (`&&`, `||`, ...).
+/// Functions start at one of its `function_keywords` and span a brace
+/// delimited body, or for `indentation_blocks` languages the lines indented
+/// deeper than the keyword's line. Function keywords without a body, such as
+/// Rust's `fn(u8) -> u8` types or trait method declarations, are skipped.
+pub fn measure(spec: &dyn LanguageSpec, content: &str, tokens: &[Token]) -> FileComplexity {
+  let definition = spec.definition();
+  let source = Source::new(content);
+  let is_keyword = |index: usize, keywords: &[String]| {
+    let token = &tokens[index];
+    token.kind == TokenKind::Word
+      && keywords.iter().any(|keyword| keyword == token.text)
+      && spec.is_keyword_token(content, tokens, index)
+  };
+
+  // (keyword index, last body index, name, line)
+  let mut functions = Vec::new();
+  for index in 0..tokens.len() {
+    if !is_keyword(index, &definition.function_keywords) {
+      continue;
+    }
+    let end = if definition.indentation_blocks {
+      Some(indented_body_end(tokens, index, &source))
+    } else {
+      brace_body_end(tokens, index)
+    };
+    if let Some(end) = end {
+      let line = source.line_of(tokens[index].offset) + 1;
+      functions.push((index, end, function_name(tokens, index), line));
+    }
+  }
+
+  // Ruby lexes a lone `?` as a word, since `?` may end method names
+  let is_symbol = |token: &Token| {
+    token.kind == TokenKind::Punct
+      || (token.kind == TokenKind::Word
+        && !token
+          .text
+          .contains(|c: char| c.is_alphanumeric() || c == '_'))
+  };
+  let mut decisions = Vec::new();
+  let mut index = 0;
+  while index < tokens.len() {
+    let token = &tokens[index];
+    if is_symbol(token) {
+      // Operators are runs of adjacent punctuation, e.g. `&&` or `)?;`
+      let mut end = index + 1;
+      while tokens.get(end).is_some_and(|next| {
+        let previous = &tokens[end - 1];
+        is_symbol(next) && next.offset == previous.offset + previous.text.len()
+      }) {
+        end += 1;
+      }
+      let last = &tokens[end - 1];
+      let run = &content[token.offset..last.offset + last.text.len()];
+      let count = count_operators(run, &definition.decision_operators);
+      decisions.extend(std::iter::repeat_n(index, count));
+      index = end;
+      continue;
+    }
+    if is_keyword(index, &definition.decision_keywords) {
+      decisions.push(index);
+    }
+    index += 1;
+  }
+
+  // Both lists are in source order and spans nest, so a single sweep with a
+  // stack of the spans open at each decision finds its innermost function
+  let mut complexities = vec![1; functions.len()];
+  let mut open: Vec<usize> = Vec::new();
+  let mut next = 0;
+  for &decision in &decisions {
+    while functions
+      .get(next)
+      .is_some_and(|(start, _, _, _)| *start <= decision)
+    {
+      open.push(next);
+      next += 1;
+    }
+    while open
+      .last()
+      .is_some_and(|&owner| functions[owner].1 < decision)
+    {
+      open.pop();
+    }
+    if let Some(&owner) = open.last() {
+      complexities[owner] += 1;
+    }
+  }
+
+  FileComplexity {
+    complexity: decisions.len() + functions.len(),
+    functions: functions
+      .into_iter()
+      .zip(complexities)
+      .map(|((_, _, name, line), complexity)| FunctionComplexity {
+        name,
+        line,
+        complexity,
+      })
+      .collect(),
+  }
+}
+
+/// Occurrences of `operators` in a run of punctuation, longest first and
+/// without overlaps.
+fn count_operators(run: &str, operators: &[String]) -> usize {
+  let mut count = 0;
+  let mut rest = run;
+  while let Some(c) = rest.chars().next() {
+    match operators
+      .iter()
+      .filter(|operator| !operator.is_empty() && rest.starts_with(operator.as_str()))
+      .max_by_key(|operator| operator.len())
+    {
+      Some(operator) => {
+        count += 1;
+        rest = &rest[operator.len()..];
+      }
+      None => rest = &rest[c.len_utf8()..],
+    }
+  }
+  count
+}
+
+/// Index of the `}` closing the body of the function keyword at `start`, or
+/// `None` when a `;`, an unmatched closing bracket or the end of input comes
+/// before the body's `{`.
+fn brace_body_end(tokens: &[Token], start: usize) -> Option<usize> {
+  let mut depth = 0usize;
+  for (index, token) in tokens.iter().enumerate().skip(start + 1) {
+    if token.kind != TokenKind::Punct {
+      continue;
+    }
+    match token.text {
+      "(" | "[" => depth += 1,
+      ")" | "]" => depth = depth.checked_sub(1)?,
+      "{" if depth == 0 => return Some(matching_brace(tokens, index)),
+      ";" | "}" if depth == 0 => return None,
+      _ => {}
+    }
+  }
+  None
+}
+
+fn matching_brace(tokens: &[Token], open: usize) -> usize {
+  let mut depth = 0usize;
+  for (index, token) in tokens.iter().enumerate().skip(open) {
+    if token.kind != TokenKind::Punct {
+      continue;
+    }
+    match token.text {
+      "{" => depth += 1,
+      "}" => {
+        depth -= 1;
+        if depth == 0 {
+          return index;
+        }
+      }
+      _ => {}
+    }
+  }
+  tokens.len() - 1
+}
+
+/// Index of the last token of an indentation delimited function body: the
+/// header up to the first line break outside brackets, then every line
+/// indented deeper than the keyword's line.
+fn indented_body_end(tokens: &[Token], start: usize, source: &Source) -> usize {
+  let indentation =
+    |line: usize| source.line_text(line).len() - source.line_text(line).trim_start().len();
+  let keyword_line = source.line_of(tokens[start].offset);
+  let function_indentation = indentation(keyword_line);
+
+  let mut header_line = keyword_line;
+  let mut in_header = true;
+  let mut depth = 0usize;
+  let mut end = start;
+  for (index, token) in tokens.iter().enumerate().skip(start + 1) {
+    if token.kind == TokenKind::Comment {
+      continue;
+    }
+    let line = source.line_of(token.offset);
+    if in_header && (line == header_line || depth > 0) {
+      header_line = line;
+      match token.text {
+        "(" | "[" | "{" if token.kind == TokenKind::Punct => depth += 1,
+        ")" | "]" | "}" if token.kind == TokenKind::Punct => depth = depth.saturating_sub(1),
+        _ => {}
+      }
+      end = index;
+      continue;
+    }
+    in_header = false;
+
+    let starts_line = source.before(token.offset).trim().is_empty();
+    if starts_line && indentation(line) <= function_indentation {
+      break;
+    }
+    end = index;
+  }
+  end
+}
+
+/// The name after the function keyword at `start`. A parenthesized group
+/// first (a Go receiver) is skipped if a name follows; `self.name` in Ruby
+/// keeps its receiver.
+fn function_name(tokens: &[Token], start: usize) -> String {
+  let mut index = start + 1;
+  // JavaScript generators: `function* name`
+  if tokens.get(index).is_some_and(|token| token.text == "*") {
+    index += 1;
+  }
+
+  match tokens.get(index) {
+    Some(token) if token.kind == TokenKind::Word => {
+      let is_punct = |index: usize, text: &str| {
+        tokens
+          .get(index)
+          .is_some_and(|token| token.kind == TokenKind::Punct && token.text == text)
+      };
+      match tokens.get(index + 2) {
+        Some(method) if is_punct(index + 1, ".") && method.kind == TokenKind::Word => {
+          format!("{}.{}", token.text, method.text)
+        }
+        _ => token.text.to_string(),
+      }
+    }
+    Some(token) if token.text == "(" => {
+      let mut depth = 0usize;
+      for (after, token) in tokens.iter().enumerate().skip(index) {
+        match token.text {
+          "(" => depth += 1,
+          ")" => {
+            depth -= 1;
+            if depth == 0 {
+              let name = tokens.get(after + 1).filter(|name| {
+                name.kind == TokenKind::Word
+                  && tokens
+                    .get(after + 2)
+                    .is_some_and(|next| next.text == "(" || next.text == "[")
+              });
+              return name.map_or(ANONYMOUS.to_string(), |name| name.text.to_string());
+            }
+          }
+          _ => {}
+        }
+      }
+      ANONYMOUS.to_string()
+    }
+    _ => ANONYMOUS.to_string(),
+  }
+}
+
+const ANONYMOUS: &str = "<anonymous>";
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Language;
+
+  fn measure_source(language: Language, content: &str) -> FileComplexity {
+    let spec = language.spec();
+    let tokens = spec.tokenize(content);
+    measure(spec.as_ref(), content, &tokens)
+  }
+
+  fn functions(complexity: &FileComplexity) -> Vec<(&str, usize, usize)> {
+    complexity
+      .functions
+      .iter()
+      .map(|function| (function.name.as_str(), function.line, function.complexity))
+      .collect()
+  }
+
+  #[test]
+  fn test_rust_complexity() {
+    let source = "\
+trait T { fn declared(&self); }
+
+fn run(f: fn(u8) -> u8) -> Result<(), E> {
+    // if in a comment
+    if a && b || \"if\" == c {
+        for x in xs { check(x)?; }
+    }
+    match y { Some(_) => 1, None => 2 }
+}
+";
+    let complexity = measure_source(Language::Rust, source);
+    // if, &&, ||, for, ?, and two match arms
+    assert_eq!(functions(&complexity), [("run", 3, 8)]);
+    assert_eq!(complexity.complexity, 8);
+  }
+
+  #[test]
+  fn test_nested_and_anonymous_functions() {
+    let go = "\
+func (s *Server) Run() error {
+\tgo func() {
+\t\tif ok { return }
+\t}()
+\tif err != nil && s.strict { return err }
+\treturn nil
+}
+
+var handlers = 1
+";
+    let complexity = measure_source(Language::Golang, go);
+    assert_eq!(
+      functions(&complexity),
+      [("Run", 1, 3), ("<anonymous>", 2, 2)]
+    );
+    assert_eq!(complexity.complexity, 5);
+    assert_eq!(complexity.most_complex().unwrap().name, "Run");
+  }
+
+  #[test]
+  fn test_indentation_blocks() {
+    let python = "\
+def first(a,
+          b):
+    if a or b:
+        return [x for x in a if x]
+    try:
+        pass
+    except ValueError:
+        pass
+
+if __name__ == '__main__':
+    first(1, 2)
+";
+    let complexity = measure_source(Language::Python, python);
+    // if, or, for, if, except
+    assert_eq!(functions(&complexity), [("first", 1, 6)]);
+    assert_eq!(complexity.complexity, 7);
+
+    let ruby = "\
+class A
+  def self.build(x)
+    return nil unless x
+    x > 0 ? x : -x
+  end
+
+  def empty?
+    true
+  end
+end
+";
+    let complexity = measure_source(Language::Ruby, ruby);
+    assert_eq!(
+      functions(&complexity),
+      [("self.build", 2, 3), ("empty?", 7, 1)]
+    );
+  }
+
+  #[test]
+  fn test_many_functions() {
+    let mut source = String::new();
+    for i in 0..20_000 {
+      source.push_str(&format!(
+        "fn f{i}() {{\n    if a {{ fn inner() {{ while b {{}} }} }}\n}}\n"
+      ));
+    }
+    source.push_str("const X: bool = a && b;\n");
+    let complexity = measure_source(Language::Rust, &source);
+    assert_eq!(complexity.functions.len(), 40_000);
+    assert!(complexity
+      .functions
+      .iter()
+      .all(|function| function.complexity == 2));
+    assert_eq!(complexity.complexity, 40_000 * 2 + 1);
+  }
+}
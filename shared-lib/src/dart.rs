@@ -1,285 +1,223 @@
 use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
-
-pub const DART_KEYWORDS: &[&str] = &[
-  // Reserved words (cannot be used as identifiers)
-  "abstract",
-  "as",
-  "assert",
-  "async",
-  "await",
-  "base",
-  "break",
-  "case",
-  "catch",
-  "class",
-  "const",
-  "continue",
-  "covariant",
-  "default",
-  "deferred",
-  "do",
-  "dynamic",
-  "else",
-  "enum",
-  "export",
-  "extends",
-  "extension",
-  "external",
-  "factory",
-  "false",
-  "final",
-  "finally",
-  "for",
-  "Function",
-  "get",
-  "hide",
-  "if",
-  "implements",
-  "import",
-  "in",
-  "interface",
-  "is",
-  "late",
-  "library",
-  "mixin",
-  "new",
-  "null",
-  "of",
-  "on",
-  "operator",
-  "part",
-  "required",
-  "rethrow",
-  "return",
-  "sealed",
-  "set",
-  "show",
-  "static",
-  "super",
-  "switch",
-  "sync",
-  "this",
-  "throw",
-  "true",
-  "try",
-  "type",
-  "typedef",
-  "var",
-  "void",
-  "when",
-  "with",
-  "while",
-  "yield",
-  // Built-in types and commonly used classes
-  "int",
-  "double",
-  "num",
-  "bool",
-  "String",
-  "List",
-  "Map",
-  "Set",
-  "Object",
-  "Null",
-];
-
-pub fn analyze_directory(
-  path: &str,
-  total_counts: &mut HashMap<String, usize>,
-  file_count: &mut usize,
-) -> Result<(), Box<dyn std::error::Error>> {
-  let path = Path::new(path);
-
-  if path.is_file() && is_dart_file(path) {
-    eprintln!("Analyzing file: {}", path.display());
-    let content = fs::read_to_string(path)?;
-    let file_counts = count_keywords(&content);
+use std::sync::{Arc, OnceLock};
 
-    for (keyword, count) in file_counts {
-      *total_counts.entry(keyword).or_insert(0) += count;
-    }
-    *file_count += 1;
-    eprintln!("Files processed: {}", *file_count);
-  } else if path.is_dir() {
-    let entries = fs::read_dir(path)?;
-    for entry in entries {
-      let entry = entry?;
-      let entry_path = entry.path();
-
-      if entry_path.is_dir() {
-        if should_skip_dir(&entry_path) {
-          continue;
-        }
-        eprintln!("Entering directory: {}", entry_path.display());
-        analyze_directory(entry_path.to_str().unwrap(), total_counts, file_count)?;
-      } else if is_dart_file(&entry_path) {
-        eprintln!("Analyzing file: {}", entry_path.display());
-        let content = fs::read_to_string(&entry_path)?;
-        let file_counts = count_keywords(&content);
-
-        for (keyword, count) in file_counts {
-          *total_counts.entry(keyword).or_insert(0) += count;
-        }
-        *file_count += 1;
-        eprintln!("Files processed: {}", *file_count);
-      }
+use crate::lexer::{scan_block_comment, Token, TokenKind};
+use crate::spec::{LanguageDefinition, LanguageSpec};
+
+const DEFINITION: &str = include_str!("../languages/dart.toml");
+
+pub struct DartSpec {
+  definition: LanguageDefinition,
+}
+
+impl DartSpec {
+  pub fn new() -> Self {
+    Self {
+      definition: LanguageDefinition::from_toml_str(DEFINITION)
+        .expect("bundled Dart definition should be valid"),
     }
   }
-
-  Ok(())
 }
 
-fn is_dart_file(path: &Path) -> bool {
-  if let Some(extension) = path.extension() {
-    let ext = extension.to_string_lossy().to_lowercase();
-    ext == "dart"
-  } else {
-    false
+impl Default for DartSpec {
+  fn default() -> Self {
+    Self::new()
   }
 }
 
-fn should_skip_dir(path: &Path) -> bool {
-  if let Some(dir_name) = path.file_name() {
-    let name = dir_name.to_string_lossy();
-    matches!(
-      name.as_ref(),
-      ".dart_tool" | "build" | ".pub" | ".git" | "target" | "node_modules" | ".idea" | ".vscode"
-    )
-  } else {
-    false
+impl LanguageSpec for DartSpec {
+  fn definition(&self) -> &LanguageDefinition {
+    &self.definition
+  }
+
+  fn tokenize<'a>(&self, content: &'a str) -> Vec<Token<'a>> {
+    tokenize(content)
   }
 }
 
-pub fn count_keywords(content: &str) -> HashMap<String, usize> {
-  let mut counts = HashMap::new();
-  let mut chars = content.chars().peekable();
-  let mut current_token = String::new();
-  let mut in_string = false;
-  let mut in_comment = false;
-  let mut in_multi_line_comment = false;
-  let mut string_char = '\0';
-
-  while let Some(c) = chars.next() {
-    match c {
-      // Handle multi-line comments (/* ... */)
-      '/' if !in_string && !in_comment && !in_multi_line_comment => {
-        if chars.peek() == Some(&'*') {
-          chars.next(); // consume '*'
-          in_multi_line_comment = true;
-          if !current_token.is_empty() {
-            check_and_count_token(&current_token, &mut counts);
-            current_token.clear();
-          }
-          continue;
-        } else if chars.peek() == Some(&'/') {
-          chars.next(); // consume second '/'
-          in_comment = true;
-          if !current_token.is_empty() {
-            check_and_count_token(&current_token, &mut counts);
-            current_token.clear();
-          }
-          continue;
-        }
-      }
-      // End multi-line comment
-      '*' if in_multi_line_comment && !in_string => {
-        if chars.peek() == Some(&'/') {
-          chars.next(); // consume '/'
-          in_multi_line_comment = false;
-        }
-        continue;
-      }
-      // End single-line comment
-      '\n' if in_comment => {
-        in_comment = false;
-        continue;
-      }
-      // Handle strings
-      '"' | '\'' if !in_comment && !in_multi_line_comment => {
-        if !in_string {
-          in_string = true;
-          string_char = c;
-          if !current_token.is_empty() {
-            check_and_count_token(&current_token, &mut counts);
-            current_token.clear();
+/// Splits Dart source into tokens.
+///
+/// Handles nested block comments, raw strings (`r'...'`) and triple-quoted
+/// strings. The identifier in `$name` and the expression in `${...}` are
+/// lexed as code, with the literal text around them emitted as `Str` tokens.
+/// Unterminated single-quoted strings end at the end of the line.
+pub fn tokenize(content: &str) -> Vec<Token<'_>> {
+  let mut lexer = Lexer {
+    content,
+    pos: 0,
+    tokens: Vec::new(),
+  };
+  lexer.lex_code(false);
+  lexer.tokens
+}
+
+struct Lexer<'a> {
+  content: &'a str,
+  pos: usize,
+  tokens: Vec<Token<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+  fn rest(&self) -> &'a str {
+    &self.content[self.pos..]
+  }
+
+  fn emit(&mut self, kind: TokenKind, start: usize, end: usize) {
+    self
+      .tokens
+      .push(Token::new(kind, &self.content[start..end], start));
+    self.pos = end;
+  }
+
+  /// Lexes code until the end of input or, when `nested`, until the `}`
+  /// closing the enclosing `${`. Returns whether that `}` was found.
+  fn lex_code(&mut self, nested: bool) -> bool {
+    let mut depth: usize = 0;
+
+    while let Some(c) = self.rest().chars().next() {
+      let rest = self.rest();
+      let start = self.pos;
+
+      if c.is_whitespace() {
+        self.pos += c.len_utf8();
+      } else if rest.starts_with("//") {
+        let end = rest.find('\n').map_or(self.content.len(), |i| start + i);
+        self.emit(TokenKind::Comment, start, end);
+      } else if rest.starts_with("/*") {
+        let end = scan_block_comment(self.content, start, "/*", "*/", true);
+        self.emit(TokenKind::Comment, start, end);
+      } else if let Some((raw, quote)) = string_start(rest) {
+        self.lex_string(raw, quote);
+      } else if is_identifier_char(c) {
+        let end = identifier_end(self.content, start, true);
+        self.emit(TokenKind::Word, start, end);
+      } else {
+        if c == '{' {
+          depth += 1;
+        } else if c == '}' {
+          if nested && depth == 0 {
+            return true;
           }
-        } else if c == string_char {
-          in_string = false;
-          string_char = '\0';
+          depth = depth.saturating_sub(1);
         }
-        continue;
-      }
-      // Handle escape sequences in strings
-      '\\' if in_string => {
-        // Skip the next character if we're in a string (escape sequence)
-        chars.next();
-        continue;
-      }
-      // Skip content inside strings and comments
-      _ if in_string || in_comment || in_multi_line_comment => {
-        continue;
+        self.emit(TokenKind::Punct, start, start + c.len_utf8());
       }
-      // Handle regular tokens
-      _ => {
-        if c.is_alphanumeric() || c == '_' {
-          current_token.push(c);
-        } else if !current_token.is_empty() {
-          check_and_count_token(&current_token, &mut counts);
-          current_token.clear();
+    }
+
+    !nested
+  }
+
+  /// Lexes a string literal at `self.pos`, with an `r` prefix when `raw`.
+  fn lex_string(&mut self, raw: bool, quote: &str) {
+    let single_line = quote.len() == 1;
+    let mut chunk_start = self.pos;
+    self.pos += usize::from(raw) + quote.len();
+
+    while let Some(c) = self.rest().chars().next() {
+      let rest = self.rest();
+      if rest.starts_with(quote) {
+        self.pos += quote.len();
+        break;
+      } else if c == '\n' && single_line {
+        break;
+      } else if c == '\\' && !raw {
+        self.pos += 1;
+        self.pos += self.rest().chars().next().map_or(0, char::len_utf8);
+      } else if !raw && rest.starts_with("${") {
+        self.emit(TokenKind::Str, chunk_start, self.pos + 2);
+        if !self.lex_code(true) {
+          return;
         }
+        // The closing `}` starts the next chunk of literal text
+        chunk_start = self.pos;
+        self.pos += 1;
+      } else if !raw && c == '$' && rest[1..].starts_with(is_identifier_start) {
+        // An interpolated `$name` cannot itself contain `$`
+        self.emit(TokenKind::Str, chunk_start, self.pos + 1);
+        let end = identifier_end(self.content, self.pos, false);
+        self.emit(TokenKind::Word, self.pos, end);
+        chunk_start = self.pos;
+      } else {
+        self.pos += c.len_utf8();
       }
     }
-  }
 
-  // Check final token
-  if !current_token.is_empty() {
-    check_and_count_token(&current_token, &mut counts);
+    self.emit(TokenKind::Str, chunk_start, self.pos);
   }
+}
 
-  counts
+fn is_identifier_start(c: char) -> bool {
+  c.is_alphabetic() || c == '_'
 }
 
-fn check_and_count_token(token: &str, counts: &mut HashMap<String, usize>) {
-  if DART_KEYWORDS.contains(&token) {
-    *counts.entry(token.to_string()).or_insert(0) += 1;
-  }
+fn is_identifier_char(c: char) -> bool {
+  c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+fn identifier_end(content: &str, pos: usize, allow_dollar: bool) -> usize {
+  content[pos..]
+    .char_indices()
+    .find(|&(_, c)| !(c.is_alphanumeric() || c == '_' || (allow_dollar && c == '$')))
+    .map_or(content.len(), |(i, _)| pos + i)
+}
+
+/// Whether a string literal starts `rest`: returns whether it is raw and its
+/// quote.
+fn string_start(rest: &str) -> Option<(bool, &'static str)> {
+  let (raw, body) = match rest.strip_prefix('r') {
+    Some(body) => (true, body),
+    None => (false, rest),
+  };
+
+  ["\"\"\"", "'''", "\"", "'"]
+    .into_iter()
+    .find(|quote| body.starts_with(quote))
+    .map(|quote| (raw, quote))
+}
+
+pub fn spec() -> Arc<DartSpec> {
+  static SPEC: OnceLock<Arc<DartSpec>> = OnceLock::new();
+  SPEC.get_or_init(|| Arc::new(DartSpec::new())).clone()
+}
+
+pub fn count_keywords(content: &str) -> HashMap<String, usize> {
+  spec().count_keywords(content)
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use std::path::Path;
 
   #[test]
   fn test_is_dart_file() {
-    assert!(is_dart_file(Path::new("main.dart")));
-    assert!(is_dart_file(Path::new("app.dart")));
-    assert!(is_dart_file(Path::new("lib/widget.dart")));
+    assert!(spec().is_source_file(Path::new("main.dart")));
+    assert!(spec().is_source_file(Path::new("app.dart")));
+    assert!(spec().is_source_file(Path::new("lib/widget.dart")));
 
-    assert!(!is_dart_file(Path::new("test.rs")));
-    assert!(!is_dart_file(Path::new("test.js")));
-    assert!(!is_dart_file(Path::new("README.md")));
+    assert!(!spec().is_source_file(Path::new("test.rs")));
+    assert!(!spec().is_source_file(Path::new("test.js")));
+    assert!(!spec().is_source_file(Path::new("README.md")));
   }
 
   #[test]
   fn test_should_skip_dir() {
-    assert!(should_skip_dir(Path::new(".dart_tool")));
-    assert!(should_skip_dir(Path::new("build")));
-    assert!(should_skip_dir(Path::new(".pub")));
-    assert!(should_skip_dir(Path::new(".git")));
-    assert!(should_skip_dir(Path::new("node_modules")));
-
-    assert!(!should_skip_dir(Path::new("lib")));
-    assert!(!should_skip_dir(Path::new("test")));
-    assert!(!should_skip_dir(Path::new("bin")));
+    assert!(spec().should_skip_dir(Path::new(".dart_tool")));
+    assert!(spec().should_skip_dir(Path::new("build")));
+    assert!(spec().should_skip_dir(Path::new(".pub")));
+    assert!(spec().should_skip_dir(Path::new(".git")));
+    assert!(spec().should_skip_dir(Path::new("node_modules")));
+
+    assert!(!spec().should_skip_dir(Path::new("lib")));
+    assert!(!spec().should_skip_dir(Path::new("test")));
+    assert!(!spec().should_skip_dir(Path::new("bin")));
   }
 
   #[test]
   fn test_dart_keywords_count() {
     // Verify we have the correct number of Dart keywords
-    assert!(DART_KEYWORDS.len() >= 73); // Should have at least 73 keywords
-    assert!(DART_KEYWORDS.len() <= 85); // But not too many
+    assert!(spec().keywords().len() >= 73); // Should have at least 73 keywords
+    assert!(spec().keywords().len() <= 85); // But not too many
   }
 
   #[test]
@@ -513,9 +451,92 @@ void documentedFunction() {
     let mut file_count = 0;
 
     // Test that the function works with valid directory
-    let result = analyze_directory(".", &mut counts, &mut file_count);
+    let result = crate::analyze_directory(
+      Path::new("."),
+      spec().as_ref(),
+      &mut counts,
+      &mut file_count,
+    );
 
     // The function should succeed even if no Dart files are found
     assert!(result.is_ok());
   }
+
+  fn texts<'a>(tokens: &[Token<'a>]) -> Vec<(TokenKind, &'a str)> {
+    tokens
+      .iter()
+      .map(|token| (token.kind, token.text))
+      .collect()
+  }
+
+  #[test]
+  fn test_tokenize_interpolation() {
+    let tokens = tokenize(r"'Hi $name, ${user.isAdmin ? 'admin' : 'user'}! \$5 $this$count'");
+    assert_eq!(
+      texts(&tokens),
+      vec![
+        (TokenKind::Str, "'Hi $"),
+        (TokenKind::Word, "name"),
+        (TokenKind::Str, ", ${"),
+        (TokenKind::Word, "user"),
+        (TokenKind::Punct, "."),
+        (TokenKind::Word, "isAdmin"),
+        (TokenKind::Punct, "?"),
+        (TokenKind::Str, "'admin'"),
+        (TokenKind::Punct, ":"),
+        (TokenKind::Str, "'user'"),
+        (TokenKind::Str, r"}! \$5 $"),
+        (TokenKind::Word, "this"),
+        (TokenKind::Str, "$"),
+        (TokenKind::Word, "count"),
+        (TokenKind::Str, "'"),
+      ]
+    );
+
+    let counts =
+      count_keywords(r#"print("${items.map((i) { return i; }).join()} for ${x is int}");"#);
+    assert_eq!(counts.get("return"), Some(&1));
+    assert_eq!(counts.get("is"), Some(&1));
+    assert_eq!(counts.get("for"), None);
+  }
+
+  #[test]
+  fn test_tokenize_raw_and_multiline_strings() {
+    let content = r#"final a = r'no $interpolation \' class; final b = r"""
+raw ${if} """;
+final c = '''
+multi ${value} line with 'quotes' and return
+''';
+var d = $e;"#;
+    let strings: Vec<_> = tokenize(content)
+      .into_iter()
+      .filter(|token| token.kind == TokenKind::Str)
+      .map(|token| token.text)
+      .collect();
+    assert_eq!(
+      strings,
+      vec![
+        r"r'no $interpolation \'",
+        "r\"\"\"\nraw ${if} \"\"\"",
+        "'''\nmulti ${",
+        "} line with 'quotes' and return\n'''",
+      ]
+    );
+
+    let counts = count_keywords(content);
+    assert_eq!(counts.get("final"), Some(&3));
+    assert_eq!(counts.get("class"), Some(&1));
+    assert_eq!(counts.get("if"), None);
+    assert_eq!(counts.get("return"), None);
+    assert_eq!(counts.get("var"), Some(&1));
+  }
+
+  #[test]
+  fn test_nested_comments_and_unterminated_strings() {
+    let counts =
+      count_keywords("/* outer /* inner */ class */ void main() {}\nvar s = 'oops\nreturn;");
+    assert_eq!(counts.get("class"), None);
+    assert_eq!(counts.get("void"), Some(&1));
+    assert_eq!(counts.get("return"), Some(&1));
+  }
 }
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+/// How the bytes of a source file are turned into text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+  /// UTF-8 only; other files are skipped.
+  #[default]
+  Utf8,
+  /// UTF-8, replacing invalid sequences with `U+FFFD`.
+  Lossy,
+  /// UTF-16 when the file starts with a byte order mark, otherwise UTF-8,
+  /// falling back to Latin-1.
+  Detect,
+}
+
+impl Encoding {
+  /// Parses an `--encoding` value: `utf8`, `lossy` or `detect`.
+  pub fn parse(name: &str) -> Option<Self> {
+    match name.to_lowercase().replace('-', "").as_str() {
+      "utf8" | "strict" => Some(Encoding::Utf8),
+      "lossy" => Some(Encoding::Lossy),
+      "detect" | "auto" => Some(Encoding::Detect),
+      _ => None,
+    }
+  }
+}
+
+/// Decodes `bytes`, or `None` when they are not valid in `encoding`. A
+/// UTF-8 byte order mark is dropped.
+pub fn decode(bytes: Vec<u8>, encoding: Encoding) -> Option<String> {
+  let bytes = match bytes.strip_prefix(b"\xEF\xBB\xBF") {
+    Some(rest) => rest.to_vec(),
+    None => bytes,
+  };
+  match encoding {
+    Encoding::Utf8 => String::from_utf8(bytes).ok(),
+    Encoding::Lossy => Some(String::from_utf8_lossy(&bytes).into_owned()),
+    Encoding::Detect => {
+      if let Some(rest) = bytes.strip_prefix(b"\xFF\xFE") {
+        return decode_utf16(rest, u16::from_le_bytes);
+      }
+      if let Some(rest) = bytes.strip_prefix(b"\xFE\xFF") {
+        return decode_utf16(rest, u16::from_be_bytes);
+      }
+      // Every byte is a valid Latin-1 character, with the same code point
+      Some(
+        String::from_utf8(bytes)
+          .unwrap_or_else(|e| e.into_bytes().into_iter().map(char::from).collect()),
+      )
+    }
+  }
+}
+
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> Option<String> {
+  if !bytes.len().is_multiple_of(2) {
+    return None;
+  }
+  let units = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+  char::decode_utf16(units).collect::<Result<_, _>>().ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_decode() {
+    let latin1 = b"caf\xE9 fn".to_vec();
+    assert_eq!(decode(latin1.clone(), Encoding::Utf8), None);
+    assert_eq!(
+      decode(latin1.clone(), Encoding::Lossy).as_deref(),
+      Some("caf\u{FFFD} fn")
+    );
+    assert_eq!(decode(latin1, Encoding::Detect).as_deref(), Some("café fn"));
+
+    let utf16: Vec<u8> = b"\xFF\xFE"
+      .iter()
+      .copied()
+      .chain("fn é".encode_utf16().flat_map(u16::to_le_bytes))
+      .collect();
+    assert_eq!(decode(utf16, Encoding::Detect).as_deref(), Some("fn é"));
+    assert_eq!(
+      decode(b"\xEF\xBB\xBFfn".to_vec(), Encoding::Utf8).as_deref(),
+      Some("fn")
+    );
+    assert_eq!(Encoding::parse("UTF-8"), Some(Encoding::Utf8));
+    assert_eq!(Encoding::parse("latin2"), None);
+  }
+}
@@ -0,0 +1,159 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Why an analysis, a language lookup or a report failed.
+#[derive(Debug)]
+pub enum AnalyzerError {
+  /// The path to analyze, or a file below it, does not exist.
+  PathNotFound(PathBuf),
+  /// A file or directory could not be read for lack of permissions.
+  PermissionDenied(PathBuf),
+  /// A source file is not valid UTF-8.
+  NonUtf8File(PathBuf),
+  /// Any other failure to read a file or directory.
+  Io { path: PathBuf, source: io::Error },
+  /// The repository does not exist or is private.
+  RepositoryNotFound(String),
+  /// `git clone` failed or could not be started.
+  CloneFailed { url: String, message: String },
+  /// No language has this id or alias.
+  UnsupportedLanguage(String),
+  /// A language definition is malformed or invalid; `path` is `None` for
+  /// definitions that did not come from a file.
+  InvalidLanguageDefinition {
+    path: Option<PathBuf>,
+    message: String,
+  },
+  /// A category, keyword, naming convention or similar argument is unknown.
+  InvalidArgument(String),
+  /// A chart was requested for a report without any keywords.
+  NothingToVisualize,
+  /// A report could not be written.
+  OutputWriteFailed { path: PathBuf, source: io::Error },
+}
+
+impl AnalyzerError {
+  /// Classifies an I/O error on `path`. `fs::read_to_string` reports
+  /// invalid UTF-8 as [`io::ErrorKind::InvalidData`].
+  pub fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+    let path = path.into();
+    match source.kind() {
+      io::ErrorKind::NotFound => AnalyzerError::PathNotFound(path),
+      io::ErrorKind::PermissionDenied => AnalyzerError::PermissionDenied(path),
+      io::ErrorKind::InvalidData => AnalyzerError::NonUtf8File(path),
+      _ => AnalyzerError::Io { path, source },
+    }
+  }
+
+  /// The file or directory the error is about, if any.
+  pub fn path(&self) -> Option<&Path> {
+    match self {
+      AnalyzerError::PathNotFound(path)
+      | AnalyzerError::PermissionDenied(path)
+      | AnalyzerError::NonUtf8File(path)
+      | AnalyzerError::Io { path, .. }
+      | AnalyzerError::OutputWriteFailed { path, .. } => Some(path),
+      AnalyzerError::InvalidLanguageDefinition { path, .. } => path.as_deref(),
+      _ => None,
+    }
+  }
+
+  /// Attaches the definition file to an [`AnalyzerError::InvalidLanguageDefinition`].
+  pub(crate) fn in_definition(self, file: &Path) -> Self {
+    match self {
+      AnalyzerError::InvalidLanguageDefinition {
+        path: None,
+        message,
+      } => AnalyzerError::InvalidLanguageDefinition {
+        path: Some(file.to_path_buf()),
+        message,
+      },
+      other => other,
+    }
+  }
+
+  pub(crate) fn invalid_definition(message: impl fmt::Display) -> Self {
+    AnalyzerError::InvalidLanguageDefinition {
+      path: None,
+      message: message.to_string(),
+    }
+  }
+}
+
+impl fmt::Display for AnalyzerError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      AnalyzerError::PathNotFound(path) => write!(f, "Path not found: {}", path.display()),
+      AnalyzerError::PermissionDenied(path) => {
+        write!(f, "Permission denied: {}", path.display())
+      }
+      AnalyzerError::NonUtf8File(path) => write!(f, "Not a UTF-8 file: {}", path.display()),
+      AnalyzerError::Io { path, source } => {
+        write!(f, "Failed to read {}: {source}", path.display())
+      }
+      AnalyzerError::RepositoryNotFound(url) => {
+        write!(f, "Repository not found or not public: {url}")
+      }
+      AnalyzerError::CloneFailed { url, message } => {
+        write!(f, "Failed to clone repository {url}: {message}")
+      }
+      AnalyzerError::UnsupportedLanguage(name) => write!(f, "Unsupported language `{name}`"),
+      AnalyzerError::InvalidLanguageDefinition {
+        path: Some(path),
+        message,
+      } => write!(
+        f,
+        "Invalid language definition {}: {message}",
+        path.display()
+      ),
+      AnalyzerError::InvalidLanguageDefinition {
+        path: None,
+        message,
+      } => write!(f, "Invalid language definition: {message}"),
+      AnalyzerError::InvalidArgument(message) => f.write_str(message),
+      AnalyzerError::NothingToVisualize => f.write_str("No keywords found to visualize"),
+      AnalyzerError::OutputWriteFailed { path, source } => {
+        write!(f, "Failed to write {}: {source}", path.display())
+      }
+    }
+  }
+}
+
+impl std::error::Error for AnalyzerError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      AnalyzerError::Io { source, .. } | AnalyzerError::OutputWriteFailed { source, .. } => {
+        Some(source)
+      }
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_io_errors_keep_the_path() {
+    let error = AnalyzerError::io("src/a.rs", io::Error::from(io::ErrorKind::NotFound));
+    assert!(matches!(error, AnalyzerError::PathNotFound(_)));
+    assert_eq!(error.to_string(), "Path not found: src/a.rs");
+
+    let error = AnalyzerError::io("b.rs", io::Error::from(io::ErrorKind::InvalidData));
+    assert!(matches!(error, AnalyzerError::NonUtf8File(_)));
+    assert_eq!(error.path(), Some(Path::new("b.rs")));
+
+    let error = AnalyzerError::io("c.rs", io::Error::other("disk on fire"));
+    assert_eq!(error.to_string(), "Failed to read c.rs: disk on fire");
+    assert!(std::error::Error::source(&error).is_some());
+
+    let error =
+      AnalyzerError::invalid_definition("missing `id`").in_definition(Path::new("x.toml"));
+    assert_eq!(
+      error.to_string(),
+      "Invalid language definition x.toml: missing `id`"
+    );
+  }
+}
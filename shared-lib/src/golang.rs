@@ -1,252 +1,212 @@
 use std::collections::HashMap;
-use std::fs;
 use std::path::Path;
+use std::sync::{Arc, OnceLock};
 
-pub const GOLANG_KEYWORDS: &[&str] = &[
-  // Go language keywords (25 keywords)
-  "break",
-  "case",
-  "chan",
-  "const",
-  "continue",
-  "default",
-  "defer",
-  "else",
-  "fallthrough",
-  "for",
-  "func",
-  "go",
-  "goto",
-  "if",
-  "import",
-  "interface",
-  "map",
-  "package",
-  "range",
-  "return",
-  "select",
-  "struct",
-  "switch",
-  "type",
-  "var",
-  // Built-in types
-  "bool",
-  "byte",
-  "complex64",
-  "complex128",
-  "error",
-  "float32",
-  "float64",
-  "int",
-  "int8",
-  "int16",
-  "int32",
-  "int64",
-  "rune",
-  "string",
-  "uint",
-  "uint8",
-  "uint16",
-  "uint32",
-  "uint64",
-  "uintptr",
-  // Built-in constants
-  "false",
-  "true",
-  "iota",
-  "nil",
-  // Built-in functions
-  "append",
-  "cap",
-  "close",
-  "complex",
-  "copy",
-  "delete",
-  "imag",
-  "len",
-  "make",
-  "new",
-  "panic",
-  "print",
-  "println",
-  "real",
-  "recover",
-];
-
-pub fn analyze_directory(
-  path: &str,
-  total_counts: &mut HashMap<String, usize>,
-  file_count: &mut usize,
-) -> Result<(), Box<dyn std::error::Error>> {
-  let path = Path::new(path);
-
-  if path.is_file() && is_go_file(path) {
-    eprintln!("Analyzing file: {}", path.display());
-    analyze_file(path, total_counts)?;
-    *file_count += 1;
-    eprintln!("Files processed: {file_count}");
-  } else if path.is_dir() {
-    for entry in fs::read_dir(path)? {
-      let entry = entry?;
-      let entry_path = entry.path();
-
-      if entry_path.is_dir() && !should_skip_dir(&entry_path) {
-        eprintln!("Entering directory: {}", entry_path.display());
-        analyze_directory(entry_path.to_str().unwrap(), total_counts, file_count)?;
-      } else if is_go_file(&entry_path) {
-        eprintln!("Analyzing file: {}", entry_path.display());
-        analyze_file(&entry_path, total_counts)?;
-        *file_count += 1;
-        eprintln!("Files processed: {file_count}");
-      }
+use serde::Serialize;
+
+use crate::error::AnalyzerError;
+use crate::{relative_path, AnalysisOptions};
+
+use crate::lexer::{scan_block_comment, Token, TokenKind};
+use crate::spec::{LanguageDefinition, LanguageSpec};
+
+const DEFINITION: &str = include_str!("../languages/go.toml");
+
+pub struct GolangSpec {
+  definition: LanguageDefinition,
+}
+
+impl GolangSpec {
+  pub fn new() -> Self {
+    Self {
+      definition: LanguageDefinition::from_toml_str(DEFINITION)
+        .expect("bundled Go definition should be valid"),
     }
   }
-
-  Ok(())
 }
 
-fn is_go_file(path: &Path) -> bool {
-  if let Some(extension) = path.extension() {
-    return extension == "go";
+impl Default for GolangSpec {
+  fn default() -> Self {
+    Self::new()
   }
-  false
 }
 
-fn should_skip_dir(path: &Path) -> bool {
-  if let Some(dir_name) = path.file_name() {
-    if let Some(name_str) = dir_name.to_str() {
-      return matches!(
-        name_str,
-        "vendor" | "node_modules" | ".git" | "target" | "bin" | "pkg" | ".vscode" | ".idea"
-      );
-    }
+impl LanguageSpec for GolangSpec {
+  fn definition(&self) -> &LanguageDefinition {
+    &self.definition
+  }
+
+  fn tokenize<'a>(&self, content: &'a str) -> Vec<Token<'a>> {
+    tokenize(content)
   }
-  false
 }
 
-fn analyze_file(
-  path: &Path,
-  total_counts: &mut HashMap<String, usize>,
-) -> Result<(), Box<dyn std::error::Error>> {
-  let content = fs::read_to_string(path)?;
-  let file_counts = count_keywords(&content);
+/// Splits Go source into tokens.
+///
+/// Backtick strings are raw: they may span lines and have no escapes.
+/// Interpreted strings and rune literals honour `\` escapes and end at the
+/// end of the line if unterminated, as Go does not allow them to span lines.
+pub fn tokenize(content: &str) -> Vec<Token<'_>> {
+  let mut tokens = Vec::new();
+  let mut pos = 0;
+
+  while let Some(c) = content[pos..].chars().next() {
+    let rest = &content[pos..];
+
+    if c.is_whitespace() {
+      pos += c.len_utf8();
+      continue;
+    }
 
-  for (keyword, count) in file_counts {
-    *total_counts.entry(keyword).or_insert(0) += count;
+    let (kind, end) = if rest.starts_with("//") {
+      let end = rest.find('\n').map_or(content.len(), |i| pos + i);
+      (TokenKind::Comment, end)
+    } else if rest.starts_with("/*") {
+      (
+        TokenKind::Comment,
+        scan_block_comment(content, pos, "/*", "*/", false),
+      )
+    } else if c == '`' {
+      let end = rest[1..].find('`').map_or(content.len(), |i| pos + i + 2);
+      (TokenKind::Str, end)
+    } else if c == '"' || c == '\'' {
+      (TokenKind::Str, scan_interpreted(content, pos, c))
+    } else if c.is_alphanumeric() || c == '_' {
+      let end = rest
+        .char_indices()
+        .find(|(_, ch)| !(ch.is_alphanumeric() || *ch == '_'))
+        .map_or(content.len(), |(i, _)| pos + i);
+      (TokenKind::Word, end)
+    } else {
+      (TokenKind::Punct, pos + c.len_utf8())
+    };
+
+    tokens.push(Token::new(kind, &content[pos..end], pos));
+    pos = end;
   }
 
-  Ok(())
+  tokens
 }
 
-pub fn count_keywords(content: &str) -> HashMap<String, usize> {
-  let mut counts = HashMap::new();
-  let mut chars = content.chars().peekable();
-  let mut current_token = String::new();
-  let mut in_string = false;
-  let mut in_single_comment = false;
-  let mut in_multi_comment = false;
-  let mut string_char = '\0';
-
-  while let Some(c) = chars.next() {
+/// Returns the end of the string or rune literal opened by `quote` at `pos`.
+fn scan_interpreted(content: &str, pos: usize, quote: char) -> usize {
+  let mut chars = content[pos + 1..].char_indices();
+
+  while let Some((i, c)) = chars.next() {
     match c {
-      // Handle single-line comments
-      '/' if !in_string && !in_multi_comment => {
-        if chars.peek() == Some(&'/') {
-          chars.next(); // consume second '/'
-          in_single_comment = true;
-          if !current_token.is_empty() {
-            check_and_count_token(&current_token, &mut counts);
-            current_token.clear();
-          }
-          continue;
-        } else if chars.peek() == Some(&'*') {
-          chars.next(); // consume '*'
-          in_multi_comment = true;
-          if !current_token.is_empty() {
-            check_and_count_token(&current_token, &mut counts);
-            current_token.clear();
-          }
-          continue;
-        }
-      }
-      // End multi-line comment
-      '*' if in_multi_comment => {
-        if chars.peek() == Some(&'/') {
-          chars.next(); // consume '/'
-          in_multi_comment = false;
-          continue;
-        }
-      }
-      // End single-line comment
-      '\n' if in_single_comment => {
-        in_single_comment = false;
-        continue;
-      }
-      // Handle strings (double quotes, single quotes, and backticks for raw strings)
-      '"' | '\'' | '`' if !in_single_comment && !in_multi_comment => {
-        if !in_string {
-          in_string = true;
-          string_char = c;
-          if !current_token.is_empty() {
-            check_and_count_token(&current_token, &mut counts);
-            current_token.clear();
-          }
-        } else if c == string_char {
-          in_string = false;
-          string_char = '\0';
-        }
-        continue;
-      }
-      // Handle escape sequences in strings (but not in raw strings with backticks)
-      '\\' if in_string && string_char != '`' => {
-        // Skip the next character if we're in a string (escape sequence)
+      '\\' => {
         chars.next();
-        continue;
-      }
-      // Skip content inside strings and comments
-      _ if in_string || in_single_comment || in_multi_comment => {
-        continue;
-      }
-      // Handle regular tokens
-      _ => {
-        if c.is_alphanumeric() || c == '_' {
-          current_token.push(c);
-        } else if !current_token.is_empty() {
-          check_and_count_token(&current_token, &mut counts);
-          current_token.clear();
-        }
       }
+      '\n' => return pos + 1 + i,
+      c if c == quote => return pos + 1 + i + 1,
+      _ => {}
     }
   }
 
-  // Check final token
-  if !current_token.is_empty() {
-    check_and_count_token(&current_token, &mut counts);
-  }
+  content.len()
+}
 
-  counts
+/// A `//go:build` or `//go:generate` directive.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct GoDirective {
+  pub file: String,
+  /// 1-based line number.
+  pub line: usize,
+  /// `build` or `generate`.
+  pub name: String,
+  /// Build constraint or generator command.
+  pub argument: String,
 }
 
-fn check_and_count_token(token: &str, counts: &mut HashMap<String, usize>) {
-  if GOLANG_KEYWORDS.contains(&token) {
-    *counts.entry(token.to_string()).or_insert(0) += 1;
+/// Finds the `//go:build` and `//go:generate` directives in `content`. Like
+/// the Go tool, only line comments starting at the beginning of a line count.
+pub fn find_directives(file: &str, content: &str) -> Vec<GoDirective> {
+  tokenize(content)
+    .into_iter()
+    .filter(|token| token.kind == TokenKind::Comment)
+    .filter(|token| token.offset == 0 || content[..token.offset].ends_with('\n'))
+    .filter_map(|token| {
+      let directive = token.text.strip_prefix("//go:")?;
+      let (name, argument) = directive.split_once(char::is_whitespace)?;
+      matches!(name, "build" | "generate").then(|| GoDirective {
+        file: file.to_string(),
+        line: content[..token.offset].matches('\n').count() + 1,
+        name: name.to_string(),
+        argument: argument.trim().to_string(),
+      })
+    })
+    .collect()
+}
+
+/// Collects the directives of every Go file below `path`, which may also be a
+/// single file. The files are those an analysis with `options` would walk,
+/// in the same order, plus generated files, which often carry build
+/// constraints of their own; those that cannot be read are skipped with a
+/// warning.
+pub fn collect_directives(
+  path: &Path,
+  options: &AnalysisOptions,
+) -> Result<Vec<GoDirective>, AnalyzerError> {
+  if !path.exists() {
+    return Err(AnalyzerError::PathNotFound(path.to_path_buf()));
+  }
+  let mut options = options.clone();
+  options.filter.include_generated = true;
+  let language = spec();
+  let findings = crate::walk_sources(path, Some(language.as_ref()), &[], &options);
+  let outcomes = crate::visit_files(
+    &findings.files,
+    path,
+    &[language.as_ref()],
+    &options,
+    |file, _, content| find_directives(&relative_path(file, path), content),
+  );
+  Ok(outcomes.into_iter().flatten().flatten().collect())
+}
+
+/// Plain text listing of `directives`, one per line.
+pub fn format_directives(directives: &[GoDirective]) -> String {
+  let mut output = String::from("\n=== Go Directives ===\n");
+  if directives.is_empty() {
+    output.push_str("No //go:build or //go:generate directives found\n");
+  }
+  for directive in directives {
+    output.push_str(&format!(
+      "{}:{}: //go:{} {}\n",
+      directive.file, directive.line, directive.name, directive.argument
+    ));
   }
+  output
+}
+
+pub fn spec() -> Arc<GolangSpec> {
+  static SPEC: OnceLock<Arc<GolangSpec>> = OnceLock::new();
+  SPEC.get_or_init(|| Arc::new(GolangSpec::new())).clone()
+}
+
+pub fn count_keywords(content: &str) -> HashMap<String, usize> {
+  spec().count_keywords(content)
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::test_dir::TestDir;
+  use crate::Encoding;
   use std::collections::HashMap;
+  use std::path::Path;
+
+  type AnalysisOutcome = Result<(), AnalyzerError>;
 
   #[test]
   fn test_golang_keywords_count() {
-    assert_eq!(GOLANG_KEYWORDS.len(), 64);
+    assert_eq!(spec().keywords().len(), 64);
   }
 
   #[test]
   fn test_golang_keywords_completeness() {
     // Test that all major Go categories are included
-    let keywords_str = GOLANG_KEYWORDS.join(" ");
+    let keywords_str = spec().keywords().join(" ");
 
     // Language keywords
     assert!(keywords_str.contains("func"));
@@ -308,43 +268,41 @@ mod tests {
 
   #[test]
   fn test_is_go_file() {
-    assert!(is_go_file(Path::new("main.go")));
-    assert!(is_go_file(Path::new("src/lib.go")));
-    assert!(is_go_file(Path::new("/path/to/file.go")));
-
-    assert!(!is_go_file(Path::new("main.rs")));
-    assert!(!is_go_file(Path::new("file.js")));
-    assert!(!is_go_file(Path::new("README.md")));
-    assert!(!is_go_file(Path::new("Makefile")));
-    assert!(!is_go_file(Path::new("go.mod")));
-    assert!(!is_go_file(Path::new("go.sum")));
+    assert!(spec().is_source_file(Path::new("main.go")));
+    assert!(spec().is_source_file(Path::new("src/lib.go")));
+    assert!(spec().is_source_file(Path::new("/path/to/file.go")));
+
+    assert!(!spec().is_source_file(Path::new("main.rs")));
+    assert!(!spec().is_source_file(Path::new("file.js")));
+    assert!(!spec().is_source_file(Path::new("README.md")));
+    assert!(!spec().is_source_file(Path::new("Makefile")));
+    assert!(!spec().is_source_file(Path::new("go.mod")));
+    assert!(!spec().is_source_file(Path::new("go.sum")));
   }
 
   #[test]
   fn test_should_skip_dir() {
-    assert!(should_skip_dir(Path::new("vendor")));
-    assert!(should_skip_dir(Path::new("node_modules")));
-    assert!(should_skip_dir(Path::new(".git")));
-    assert!(should_skip_dir(Path::new("target")));
-    assert!(should_skip_dir(Path::new("bin")));
-    assert!(should_skip_dir(Path::new("pkg")));
-    assert!(should_skip_dir(Path::new(".vscode")));
-    assert!(should_skip_dir(Path::new(".idea")));
-
-    assert!(!should_skip_dir(Path::new("src")));
-    assert!(!should_skip_dir(Path::new("cmd")));
-    assert!(!should_skip_dir(Path::new("internal")));
-    assert!(!should_skip_dir(Path::new("pkg_valid")));
-    assert!(!should_skip_dir(Path::new("test")));
+    assert!(spec().should_skip_dir(Path::new("vendor")));
+    assert!(spec().should_skip_dir(Path::new("node_modules")));
+    assert!(spec().should_skip_dir(Path::new(".git")));
+    assert!(spec().should_skip_dir(Path::new("target")));
+    assert!(spec().should_skip_dir(Path::new("bin")));
+    assert!(spec().should_skip_dir(Path::new("pkg")));
+    assert!(spec().should_skip_dir(Path::new(".vscode")));
+    assert!(spec().should_skip_dir(Path::new(".idea")));
+
+    assert!(!spec().should_skip_dir(Path::new("src")));
+    assert!(!spec().should_skip_dir(Path::new("cmd")));
+    assert!(!spec().should_skip_dir(Path::new("internal")));
+    assert!(!spec().should_skip_dir(Path::new("pkg_valid")));
+    assert!(!spec().should_skip_dir(Path::new("test")));
   }
 
   #[test]
   fn test_analyze_file_mock() {
     // Mock test for analyze_file function signature
-    fn _test_signature() {
-      let _: fn(&Path, &mut HashMap<String, usize>) -> Result<(), Box<dyn std::error::Error>> =
-        analyze_file;
-    }
+    let _: fn(&Path, &dyn LanguageSpec, &mut HashMap<String, usize>) -> AnalysisOutcome =
+      crate::analyze_file;
 
     // Test that analyze_file can handle HashMap correctly
     let mut counts = HashMap::new();
@@ -362,13 +320,12 @@ mod tests {
   #[test]
   fn test_analyze_directory_mock() {
     // Mock test for analyze_directory function signature
-    fn _test_signature() {
-      let _: fn(
-        &str,
-        &mut HashMap<String, usize>,
-        &mut usize,
-      ) -> Result<(), Box<dyn std::error::Error>> = analyze_directory;
-    }
+    let _: fn(
+      &Path,
+      &dyn LanguageSpec,
+      &mut HashMap<String, usize>,
+      &mut usize,
+    ) -> AnalysisOutcome = crate::analyze_directory;
 
     // Test that the function can handle the correct parameter types
     let mut counts = HashMap::new();
@@ -556,4 +513,93 @@ func someFunction() error {
     assert!(counts.get("string").unwrap_or(&0) >= &2);
     assert!(counts.get("int").unwrap_or(&0) >= &2);
   }
+
+  #[test]
+  fn test_tokenize_raw_strings_and_runes() {
+    let content = "q := `SELECT * FROM t\nWHERE type = 'x' \\` + '\\'' + '`' + \"for\\\"\"";
+    let tokens: Vec<_> = tokenize(content)
+      .into_iter()
+      .map(|token| (token.kind, token.text))
+      .collect();
+    assert_eq!(
+      tokens,
+      vec![
+        (TokenKind::Word, "q"),
+        (TokenKind::Punct, ":"),
+        (TokenKind::Punct, "="),
+        (TokenKind::Str, "`SELECT * FROM t\nWHERE type = 'x' \\`"),
+        (TokenKind::Punct, "+"),
+        (TokenKind::Str, "'\\''"),
+        (TokenKind::Punct, "+"),
+        (TokenKind::Str, "'`'"),
+        (TokenKind::Punct, "+"),
+        (TokenKind::Str, "\"for\\\"\""),
+      ]
+    );
+
+    // An unterminated interpreted string stops at the end of its line
+    let counts = count_keywords("s := \"oops\nfunc main() {}");
+    assert_eq!(counts.get("func"), Some(&1));
+
+    let query = "const query = `\n  select id from users\n  where type = 'admin'\n`\nfunc run() {}";
+    let counts = count_keywords(query);
+    assert_eq!(counts.get("const"), Some(&1));
+    assert_eq!(counts.get("func"), Some(&1));
+    assert_eq!(counts.get("type"), None);
+  }
+
+  #[test]
+  fn test_find_directives() {
+    let content = "//go:build linux && amd64\n\npackage main\n\n//go:generate stringer -type=Pill\n  //go:generate indented\n// go:generate spaced\n//go:noinline\nvar s = `\n//go:build ignore\n`\n";
+    let directives = find_directives("main.go", content);
+    assert_eq!(
+      directives,
+      vec![
+        GoDirective {
+          file: "main.go".to_string(),
+          line: 1,
+          name: "build".to_string(),
+          argument: "linux && amd64".to_string(),
+        },
+        GoDirective {
+          file: "main.go".to_string(),
+          line: 5,
+          name: "generate".to_string(),
+          argument: "stringer -type=Pill".to_string(),
+        },
+      ]
+    );
+    assert!(format_directives(&directives).contains("main.go:5: //go:generate stringer -type=Pill"));
+  }
+
+  #[test]
+  fn test_collect_directives_skips_ignored_and_unreadable_files() {
+    let root = TestDir::new(&[
+      (".gitignore", "*.pb.go\n"),
+      ("main.go", "//go:build linux\npackage main\n"),
+      ("api/user.pb.go", "//go:build ignore\npackage api\n"),
+      (
+        "api/mock.go",
+        "// Code generated by mockgen. DO NOT EDIT.\n\n//go:generate mockgen\npackage api\n",
+      ),
+    ]);
+    root.write("latin1.go", b"// caf\xE9\n//go:generate go run gen.go\n");
+
+    let files = |encoding| {
+      let options = AnalysisOptions {
+        encoding,
+        ..AnalysisOptions::default()
+      };
+      let directives = collect_directives(root.path(), &options).unwrap();
+      directives
+        .into_iter()
+        .map(|directive| directive.file)
+        .collect::<Vec<_>>()
+    };
+    let strict = files(Encoding::Utf8);
+    let detect = files(Encoding::Detect);
+
+    assert_eq!(strict, ["api/mock.go", "main.go"]);
+    assert_eq!(detect, ["api/mock.go", "latin1.go", "main.go"]);
+  }
 }
@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::error::AnalyzerError;
+use crate::lexer::{Token, TokenKind};
+use crate::spec::LanguageSpec;
+
+/// How the words of an identifier are separated and capitalized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NamingConvention {
+  #[serde(rename = "snake_case")]
+  SnakeCase,
+  #[serde(rename = "camelCase")]
+  CamelCase,
+  PascalCase,
+  #[serde(rename = "SCREAMING_SNAKE_CASE")]
+  ScreamingSnakeCase,
+}
+
+impl NamingConvention {
+  pub const ALL: [NamingConvention; 4] = [
+    NamingConvention::SnakeCase,
+    NamingConvention::CamelCase,
+    NamingConvention::PascalCase,
+    NamingConvention::ScreamingSnakeCase,
+  ];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      NamingConvention::SnakeCase => "snake_case",
+      NamingConvention::CamelCase => "camelCase",
+      NamingConvention::PascalCase => "PascalCase",
+      NamingConvention::ScreamingSnakeCase => "SCREAMING_SNAKE_CASE",
+    }
+  }
+
+  /// Looks a convention up by name, ignoring case, `_` and `-`, so `snake`,
+  /// `camel-case` and `SCREAMING` are all accepted.
+  pub fn parse(name: &str) -> Result<Self, AnalyzerError> {
+    let normalized = name.to_lowercase().replace(['_', '-'], "");
+    let normalized = normalized.strip_suffix("case").unwrap_or(&normalized);
+    match normalized {
+      "snake" => Ok(NamingConvention::SnakeCase),
+      "camel" | "lowercamel" => Ok(NamingConvention::CamelCase),
+      "pascal" | "uppercamel" => Ok(NamingConvention::PascalCase),
+      "screaming" | "screamingsnake" | "constant" => Ok(NamingConvention::ScreamingSnakeCase),
+      _ => {
+        let available: Vec<&str> = Self::ALL.iter().map(|c| c.name()).collect();
+        Err(AnalyzerError::InvalidArgument(format!(
+          "Unknown naming convention `{name}` (available: {})",
+          available.join(", ")
+        )))
+      }
+    }
+  }
+
+  /// Whether `identifier` follows the convention. Leading and trailing `_`
+  /// (`_unused`, `__init__`) and characters other than letters, digits and
+  /// `_` (`$`, Ruby's `?`) are ignored, so a single lowercase word is both
+  /// snake_case and camelCase.
+  pub fn matches(self, identifier: &str) -> bool {
+    let core = identifier.trim_matches(|c: char| !c.is_alphanumeric());
+    let has_upper = core.chars().any(char::is_uppercase);
+    let has_lower = core.chars().any(char::is_lowercase);
+    let has_underscore = core.contains('_');
+    let first_letter = core.chars().find(|c| c.is_alphabetic());
+
+    match self {
+      NamingConvention::SnakeCase => !has_upper,
+      NamingConvention::CamelCase => {
+        first_letter.is_some_and(char::is_lowercase) && !has_underscore
+      }
+      NamingConvention::PascalCase => {
+        first_letter.is_some_and(char::is_uppercase) && !has_underscore
+      }
+      NamingConvention::ScreamingSnakeCase => !has_lower,
+    }
+  }
+}
+
+/// Counts the identifiers among `tokens`, already lexed from `content`:
+/// words not used as keywords, leaving out numbers and lifetimes. Rust raw
+/// identifiers are counted without their `r#`.
+pub fn count_identifiers(
+  spec: &dyn LanguageSpec,
+  content: &str,
+  tokens: &[Token],
+) -> HashMap<String, usize> {
+  let mut counts = HashMap::new();
+
+  for (index, token) in tokens.iter().enumerate() {
+    if token.kind != TokenKind::Word || spec.is_keyword_token(content, tokens, index) {
+      continue;
+    }
+    let name = token.text.strip_prefix("r#").unwrap_or(token.text);
+    let starts_like_identifier = name
+      .chars()
+      .next()
+      .is_some_and(|c| !c.is_ascii_digit() && c != '\'');
+    if starts_like_identifier && name.chars().any(char::is_alphabetic) {
+      *counts.entry(name.to_string()).or_insert(0) += 1;
+    }
+  }
+
+  counts
+}
+
+/// Identifier use in one file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FileVocabulary {
+  /// Path relative to the analyzed directory.
+  pub path: String,
+  /// Identifiers used, counting repeats.
+  pub identifiers: usize,
+  /// Distinct identifiers used.
+  pub vocabulary: usize,
+}
+
+/// Identifier counts of one language.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IdentifierReport {
+  pub language: String,
+  pub language_name: String,
+  /// The conventions the language's identifiers are expected to follow;
+  /// empty when the definition does not declare any.
+  pub conventions: Vec<NamingConvention>,
+  pub counts: HashMap<String, usize>,
+  pub files: Vec<FileVocabulary>,
+}
+
+impl IdentifierReport {
+  pub fn new(spec: &dyn LanguageSpec) -> Self {
+    Self {
+      language: spec.id().to_string(),
+      language_name: spec.name().to_string(),
+      conventions: spec.definition().naming_conventions.clone(),
+      counts: HashMap::new(),
+      files: Vec::new(),
+    }
+  }
+
+  pub fn add_file(&mut self, path: String, counts: HashMap<String, usize>) {
+    self.files.push(FileVocabulary {
+      path,
+      identifiers: counts.values().sum(),
+      vocabulary: counts.len(),
+    });
+    for (identifier, count) in counts {
+      *self.counts.entry(identifier).or_insert(0) += count;
+    }
+  }
+
+  /// Identifiers used, counting repeats.
+  pub fn total(&self) -> usize {
+    self.counts.values().sum()
+  }
+
+  /// Distinct identifiers used.
+  pub fn vocabulary(&self) -> usize {
+    self.counts.len()
+  }
+
+  /// The `limit` most used identifiers, ties ordered by name.
+  pub fn most_common(&self, limit: usize) -> Vec<(&str, usize)> {
+    let mut identifiers = sorted(self.counts.iter());
+    identifiers.truncate(limit);
+    identifiers
+  }
+
+  /// Identifiers following none of the language's conventions, most used
+  /// first.
+  pub fn violations(&self) -> Vec<(&str, usize)> {
+    if self.conventions.is_empty() {
+      return Vec::new();
+    }
+    sorted(self.counts.iter().filter(|(identifier, _)| {
+      !self
+        .conventions
+        .iter()
+        .any(|convention| convention.matches(identifier))
+    }))
+  }
+
+  /// Files with the largest vocabulary first, ties ordered by path.
+  pub fn files_by_vocabulary(&self) -> Vec<&FileVocabulary> {
+    let mut files: Vec<_> = self.files.iter().collect();
+    files.sort_by(|a, b| {
+      b.vocabulary
+        .cmp(&a.vocabulary)
+        .then_with(|| a.path.cmp(&b.path))
+    });
+    files
+  }
+}
+
+fn sorted<'a>(counts: impl Iterator<Item = (&'a String, &'a usize)>) -> Vec<(&'a str, usize)> {
+  let mut counts: Vec<(&str, usize)> = counts
+    .map(|(identifier, count)| (identifier.as_str(), *count))
+    .collect();
+  counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+  counts
+}
+
+/// Most common identifiers, convention violations and per-file vocabulary
+/// of each language, listing at most `limit` identifiers per section.
+pub fn format_identifiers(reports: &[IdentifierReport], limit: usize) -> String {
+  let mut output = String::new();
+
+  for report in reports {
+    output.push_str(&format!(
+      "\n=== {} Identifiers ===\nIdentifiers: {} ({} distinct)\n",
+      report.language_name,
+      report.total(),
+      report.vocabulary()
+    ));
+
+    output.push_str("\nMost common identifiers:\n");
+    for (identifier, count) in report.most_common(limit) {
+      output.push_str(&format!("  {identifier:24} : {count}\n"));
+    }
+
+    if !report.conventions.is_empty() {
+      let conventions: Vec<&str> = report.conventions.iter().map(|c| c.name()).collect();
+      let violations = report.violations();
+      output.push_str(&format!(
+        "\nNaming convention violations ({}): {}\n",
+        conventions.join(", "),
+        violations.len()
+      ));
+      for (identifier, count) in violations.into_iter().take(limit) {
+        output.push_str(&format!("  {identifier:24} : {count}\n"));
+      }
+    }
+
+    output.push_str(&format!(
+      "\n{:<40} {:>12} {:>12}\n",
+      "File", "Identifiers", "Vocabulary"
+    ));
+    for file in report.files_by_vocabulary() {
+      output.push_str(&format!(
+        "{:<40} {:>12} {:>12}\n",
+        file.path, file.identifiers, file.vocabulary
+      ));
+    }
+  }
+
+  output
+}
+
+/// A JSON report per language, with at most `limit` identifiers in the
+/// `identifiers` and `violations` lists.
+pub fn generate_identifiers_json(reports: &[IdentifierReport], limit: usize) -> String {
+  let counts = |counts: Vec<(&str, usize)>| -> Vec<Value> {
+    counts
+      .into_iter()
+      .map(|(name, count)| json!({ "name": name, "count": count }))
+      .collect()
+  };
+  let languages: Vec<Value> = reports
+    .iter()
+    .map(|report| {
+      let violations = report.violations();
+      json!({
+        "language": report.language,
+        "language_name": report.language_name,
+        "total": report.total(),
+        "vocabulary": report.vocabulary(),
+        "conventions": report.conventions,
+        "identifiers": counts(report.most_common(limit)),
+        "violation_count": violations.len(),
+        "violations": counts(violations.into_iter().take(limit).collect()),
+        "files": report.files_by_vocabulary(),
+      })
+    })
+    .collect();
+  serde_json::to_string_pretty(&json!({ "languages": languages })).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Language;
+
+  #[test]
+  fn test_naming_conventions() {
+    use NamingConvention::*;
+
+    let conventions = |identifier: &str| -> Vec<NamingConvention> {
+      NamingConvention::ALL
+        .into_iter()
+        .filter(|convention| convention.matches(identifier))
+        .collect()
+    };
+    assert_eq!(conventions("parse_args"), [SnakeCase]);
+    assert_eq!(conventions("value"), [SnakeCase, CamelCase]);
+    assert_eq!(conventions("__init__"), [SnakeCase, CamelCase]);
+    assert_eq!(conventions("parseArgs"), [CamelCase]);
+    assert_eq!(conventions("$el"), [SnakeCase, CamelCase]);
+    assert_eq!(conventions("HttpServer"), [PascalCase]);
+    assert_eq!(conventions("MAX_SIZE"), [ScreamingSnakeCase]);
+    assert_eq!(conventions("T"), [PascalCase, ScreamingSnakeCase]);
+    assert!(conventions("Mixed_Case").is_empty());
+
+    assert_eq!(NamingConvention::parse("Camel-Case").unwrap(), CamelCase);
+    assert_eq!(
+      NamingConvention::parse("screaming").unwrap(),
+      ScreamingSnakeCase
+    );
+    assert!(NamingConvention::parse("kebab")
+      .unwrap_err()
+      .to_string()
+      .contains("snake_case, camelCase"));
+  }
+
+  #[test]
+  fn test_identifier_report() {
+    let source = "\
+// fn ignored_in_comment
+fn parse_args<'a>(argCount: usize) -> &'a str {
+    let r#match = \"not_an_identifier\";
+    parse_args(argCount + 1)
+}
+";
+    let spec = Language::Rust.spec();
+    let tokens = spec.tokenize(source);
+    let counts = count_identifiers(spec.as_ref(), source, &tokens);
+    assert_eq!(counts.len(), 3);
+    assert_eq!(counts["parse_args"], 2);
+    assert_eq!(counts["argCount"], 2);
+    assert_eq!(counts["match"], 1);
+
+    let mut report = IdentifierReport::new(spec.as_ref());
+    report.add_file("src/main.rs".to_string(), counts);
+    assert_eq!((report.total(), report.vocabulary()), (5, 3));
+    assert_eq!(report.most_common(2), [("argCount", 2), ("parse_args", 2)]);
+    assert_eq!(report.violations(), [("argCount", 2)]);
+
+    let plain = format_identifiers(std::slice::from_ref(&report), 10);
+    assert!(plain.contains("Identifiers: 5 (3 distinct)\n"));
+    assert!(plain.contains(
+      "Naming convention violations (snake_case, PascalCase, SCREAMING_SNAKE_CASE): 1\n  argCount"
+    ));
+
+    let json: Value = serde_json::from_str(&generate_identifiers_json(&[report], 1)).unwrap();
+    assert_eq!(json["languages"][0]["identifiers"][0]["name"], "argCount");
+    assert_eq!(
+      json["languages"][0]["identifiers"]
+        .as_array()
+        .unwrap()
+        .len(),
+      1
+    );
+    assert_eq!(json["languages"][0]["files"][0]["vocabulary"], 3);
+  }
+}
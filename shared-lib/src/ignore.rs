@@ -0,0 +1,331 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Ignore files read in every directory of a walk, later ones taking
+/// precedence.
+pub const IGNORE_FILES: [&str; 3] = [".gitignore", ".ignore", ".keyword-analyzer-ignore"];
+
+/// Lines at the top of a file searched for a generated-code header.
+const GENERATED_HEADER_LINES: usize = 10;
+
+/// A `.gitignore`-style pattern such as `*.pb.go`, `src/generated/` or
+/// `lib/**/*.g.dart`. Patterns without a `/` match the file name at any
+/// depth, others the whole path relative to the analyzed directory; a
+/// trailing `/` only matches directories.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Glob {
+  pattern: Vec<char>,
+  anchored: bool,
+  dir_only: bool,
+}
+
+impl Glob {
+  pub fn new(pattern: &str) -> Self {
+    let dir_only = pattern.ends_with('/');
+    let pattern = pattern.trim_end_matches('/');
+    let anchored = pattern.contains('/');
+    let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+    Self {
+      pattern: pattern.chars().collect(),
+      anchored,
+      dir_only,
+    }
+  }
+
+  /// Whether `path`, relative and `/`-separated, matches.
+  pub fn matches(&self, path: &str, is_dir: bool) -> bool {
+    if self.dir_only && !is_dir {
+      return false;
+    }
+    let text = if self.anchored {
+      path
+    } else {
+      path.rsplit('/').next().unwrap_or(path)
+    };
+    glob_match(&self.pattern, &text.chars().collect::<Vec<_>>())
+  }
+
+  /// Whether `path` or one of the directories containing it matches.
+  fn matches_path_or_parent(&self, path: &str, is_dir: bool) -> bool {
+    self.matches(path, is_dir)
+      || path
+        .match_indices('/')
+        .any(|(end, _)| self.matches(&path[..end], true))
+  }
+}
+
+/// Which files and directories a walk visits, on top of each language's
+/// `skip_dirs`.
+#[derive(Clone, Debug, Default)]
+pub struct FileFilter {
+  /// Only files matching one of these, or inside a matching directory, are
+  /// analyzed when any are given.
+  pub include: Vec<Glob>,
+  /// Files and directories matching any of these are left out.
+  pub exclude: Vec<Glob>,
+  /// Don't read the [`IGNORE_FILES`].
+  pub no_ignore_files: bool,
+  /// Also analyze files with a generated-code header.
+  pub include_generated: bool,
+}
+
+impl FileFilter {
+  /// `parent` plus the rules of the ignore files in `dir`.
+  pub(crate) fn rules_in(&self, dir: &Path, root: &Path, parent: &IgnoreRules) -> IgnoreRules {
+    if self.no_ignore_files {
+      return parent.clone();
+    }
+    let base = dir
+      .strip_prefix(root)
+      .map(|base| base.display().to_string())
+      .unwrap_or_default();
+    let mut rules = parent.clone();
+    for name in IGNORE_FILES {
+      if let Ok(content) = fs::read_to_string(dir.join(name)) {
+        let layer = parse_rules(&content, &base);
+        if !layer.is_empty() {
+          rules.layers.push(Arc::new(layer));
+        }
+      }
+    }
+    rules
+  }
+
+  /// Whether the walk should enter the directory or analyze the file at
+  /// `relative`.
+  pub(crate) fn is_walked(&self, rules: &IgnoreRules, relative: &str, is_dir: bool) -> bool {
+    if rules.is_ignored(relative, is_dir)
+      || self
+        .exclude
+        .iter()
+        .any(|glob| glob.matches(relative, is_dir))
+    {
+      return false;
+    }
+    is_dir
+      || self.include.is_empty()
+      || self
+        .include
+        .iter()
+        .any(|glob| glob.matches_path_or_parent(relative, false))
+  }
+}
+
+struct IgnoreRule {
+  glob: Glob,
+  negated: bool,
+  /// Directory of the ignore file, relative to the analyzed directory.
+  base: String,
+}
+
+/// The ignore rules in effect in a directory: those of its ignore files and
+/// of its parents' up to the analyzed directory.
+#[derive(Clone, Default)]
+pub(crate) struct IgnoreRules {
+  layers: Vec<Arc<Vec<IgnoreRule>>>,
+}
+
+impl IgnoreRules {
+  /// Whether the last rule matching `relative` ignores it.
+  fn is_ignored(&self, relative: &str, is_dir: bool) -> bool {
+    let mut ignored = false;
+    for rule in self.layers.iter().flat_map(|layer| layer.iter()) {
+      let path = if rule.base.is_empty() {
+        Some(relative)
+      } else {
+        relative
+          .strip_prefix(rule.base.as_str())
+          .and_then(|rest| rest.strip_prefix('/'))
+      };
+      if path.is_some_and(|path| rule.glob.matches(path, is_dir)) {
+        ignored = !rule.negated;
+      }
+    }
+    ignored
+  }
+}
+
+fn parse_rules(content: &str, base: &str) -> Vec<IgnoreRule> {
+  content
+    .lines()
+    .map(str::trim_end)
+    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    .map(|line| {
+      let (negated, pattern) = match line.strip_prefix('!') {
+        Some(pattern) => (true, pattern),
+        None => (false, line),
+      };
+      // `\#` and `\!` start patterns with a literal `#` or `!`
+      let pattern = pattern
+        .strip_prefix('\\')
+        .filter(|rest| rest.starts_with(['#', '!']))
+        .unwrap_or(pattern);
+      IgnoreRule {
+        glob: Glob::new(pattern),
+        negated,
+        base: base.to_string(),
+      }
+    })
+    .collect()
+}
+
+/// Matches `text` against a glob: `*` and `?` stay within a path segment,
+/// `**` spans segments and `[a-z]`/`[!a-z]` are character classes.
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+  match pattern.first() {
+    None => text.is_empty(),
+    Some('*') if pattern.get(1) == Some(&'*') => match pattern.get(2) {
+      None => true,
+      // `**/` matches zero or more whole directories
+      Some('/') => (0..=text.len())
+        .filter(|&start| start == 0 || text[start - 1] == '/')
+        .any(|start| glob_match(&pattern[3..], &text[start..])),
+      Some(_) => glob_match(&pattern[1..], text),
+    },
+    Some('*') => {
+      for start in 0..=text.len() {
+        if glob_match(&pattern[1..], &text[start..]) {
+          return true;
+        }
+        if text.get(start) == Some(&'/') {
+          break;
+        }
+      }
+      false
+    }
+    Some('?') => text.first().is_some_and(|&c| c != '/') && glob_match(&pattern[1..], &text[1..]),
+    Some('[') => match (text.first(), class_end(pattern)) {
+      (Some(&c), Some(end)) => {
+        c != '/'
+          && class_matches(&pattern[1..end], c)
+          && glob_match(&pattern[end + 1..], &text[1..])
+      }
+      (Some(&c), None) => c == '[' && glob_match(&pattern[1..], &text[1..]),
+      (None, _) => false,
+    },
+    Some('\\') if pattern.len() > 1 => {
+      text.first() == Some(&pattern[1]) && glob_match(&pattern[2..], &text[1..])
+    }
+    Some(&c) => text.first() == Some(&c) && glob_match(&pattern[1..], &text[1..]),
+  }
+}
+
+/// Index of the `]` closing the class that starts `pattern`; a `]` right
+/// after the `[` or `[!` is part of the class.
+fn class_end(pattern: &[char]) -> Option<usize> {
+  let mut start = 1;
+  if matches!(pattern.get(start), Some('!' | '^')) {
+    start += 1;
+  }
+  (start + 1..pattern.len()).find(|&i| pattern[i] == ']')
+}
+
+fn class_matches(class: &[char], c: char) -> bool {
+  let (negated, class) = match class.first() {
+    Some('!' | '^') => (true, &class[1..]),
+    _ => (false, class),
+  };
+  let mut found = false;
+  let mut i = 0;
+  while i < class.len() {
+    if class.get(i + 1) == Some(&'-') && i + 2 < class.len() {
+      found |= (class[i]..=class[i + 2]).contains(&c);
+      i += 3;
+    } else {
+      found |= class[i] == c;
+      i += 1;
+    }
+  }
+  found != negated
+}
+
+/// Whether `content` starts with a generated-code header, such as Go's
+/// `// Code generated by protoc-gen-go. DO NOT EDIT.`, Dart's
+/// `// GENERATED CODE - DO NOT MODIFY BY HAND` or an `@generated` tag.
+pub fn is_generated(content: &str) -> bool {
+  content
+    .lines()
+    .take(GENERATED_HEADER_LINES)
+    .map(str::to_lowercase)
+    .any(|line| {
+      line.contains("@generated")
+        || (line.contains("generated")
+          && (line.contains("do not edit") || line.contains("do not modify")))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn rules(content: &str, base: &str) -> IgnoreRules {
+    IgnoreRules {
+      layers: vec![Arc::new(parse_rules(content, base))],
+    }
+  }
+
+  #[test]
+  fn test_glob() {
+    let glob = Glob::new("*.pb.go");
+    assert!(glob.matches("api/v1/user.pb.go", false));
+    assert!(!glob.matches("api/v1/user.go", false));
+
+    let glob = Glob::new("lib/**/*.g.dart");
+    assert!(glob.matches("lib/model.g.dart", false));
+    assert!(glob.matches("lib/a/b/model.g.dart", false));
+    assert!(!glob.matches("test/model.g.dart", false));
+
+    let glob = Glob::new("src/generated/");
+    assert!(glob.matches("src/generated", true));
+    assert!(!glob.matches("src/generated", false));
+    assert!(!glob.matches("app/src/generated", true));
+
+    assert!(Glob::new("v[0-9].rs").matches("v1.rs", false));
+    assert!(!Glob::new("v[!0-9].rs").matches("v1.rs", false));
+    assert!(Glob::new("src/*").matches("src/a.rs", false));
+    assert!(!Glob::new("src/*").matches("src/a/b.rs", false));
+    assert!(Glob::new("src").matches_path_or_parent("src/a/b.rs", false));
+  }
+
+  #[test]
+  fn test_ignore_rules() {
+    let root = rules(
+      "# build output\ntarget/\n*.log\n!keep.log\n/config.rs\n",
+      "",
+    );
+    assert!(root.is_ignored("target", true));
+    assert!(root.is_ignored("crates/a/target", true));
+    assert!(root.is_ignored("logs/x.log", false));
+    assert!(!root.is_ignored("logs/keep.log", false));
+    assert!(root.is_ignored("config.rs", false));
+    assert!(!root.is_ignored("src/config.rs", false));
+
+    let nested = rules("gen/\n", "crates/a");
+    assert!(nested.is_ignored("crates/a/gen", true));
+    assert!(!nested.is_ignored("crates/b/gen", true));
+
+    let filter = FileFilter {
+      include: vec![Glob::new("src")],
+      exclude: vec![Glob::new("*_test.go")],
+      ..FileFilter::default()
+    };
+    let none = IgnoreRules::default();
+    assert!(filter.is_walked(&none, "src/a/main.go", false));
+    assert!(!filter.is_walked(&none, "src/a/main_test.go", false));
+    assert!(!filter.is_walked(&none, "cmd/main.go", false));
+    assert!(filter.is_walked(&none, "cmd", true));
+  }
+
+  #[test]
+  fn test_is_generated() {
+    assert!(is_generated(
+      "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage api\n"
+    ));
+    assert!(is_generated(
+      "// coverage:ignore-file\n// GENERATED CODE - DO NOT MODIFY BY HAND\n"
+    ));
+    assert!(is_generated("/**\n * @generated\n */\n"));
+    assert!(!is_generated("// Parses generated code.\nfn main() {}\n"));
+  }
+}
@@ -1,497 +1,500 @@
-use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
-
-pub const JAVASCRIPT_KEYWORDS: &[&str] = &[
-  // JavaScript/TypeScript keywords
-  "abstract",
-  "any",
-  "as",
-  "asserts",
-  "async",
-  "await",
-  "boolean",
-  "break",
-  "case",
-  "catch",
-  "class",
-  "const",
-  "constructor",
-  "continue",
-  "debugger",
-  "declare",
-  "default",
-  "delete",
-  "do",
-  "else",
-  "enum",
-  "export",
-  "extends",
-  "false",
-  "finally",
-  "for",
-  "from",
-  "function",
-  "get",
-  "if",
-  "implements",
-  "import",
-  "in",
-  "infer",
-  "instanceof",
-  "interface",
-  "is",
-  "keyof",
-  "let",
-  "namespace",
-  "never",
-  "new",
-  "null",
-  "number",
-  "object",
-  "of",
-  "override",
-  "package",
-  "private",
-  "protected",
-  "public",
-  "readonly",
-  "require",
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, OnceLock};
+
+use crate::lexer::{scan_block_comment, scan_string, Token, TokenKind};
+use crate::spec::{LanguageDefinition, LanguageSpec};
+
+const DEFINITION: &str = include_str!("../languages/javascript.toml");
+
+pub struct JavaScriptSpec {
+  definition: LanguageDefinition,
+}
+
+impl JavaScriptSpec {
+  pub fn new() -> Self {
+    Self {
+      definition: LanguageDefinition::from_toml_str(DEFINITION)
+        .expect("bundled JavaScript definition should be valid"),
+    }
+  }
+}
+
+impl Default for JavaScriptSpec {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl LanguageSpec for JavaScriptSpec {
+  fn definition(&self) -> &LanguageDefinition {
+    &self.definition
+  }
+
+  fn tokenize<'a>(&self, content: &'a str) -> Vec<Token<'a>> {
+    tokenize(content)
+  }
+}
+
+/// Keywords after which a `/` starts a regex and a `<` may start JSX.
+const EXPRESSION_KEYWORDS: [&str; 14] = [
   "return",
-  "set",
-  "static",
-  "string",
-  "super",
-  "switch",
-  "symbol",
-  "this",
-  "throw",
-  "true",
-  "try",
-  "type",
   "typeof",
-  "undefined",
-  "unique",
-  "unknown",
-  "var",
+  "instanceof",
+  "in",
+  "of",
+  "new",
+  "delete",
   "void",
-  "while",
-  "with",
+  "throw",
+  "case",
+  "do",
+  "else",
   "yield",
-  // TypeScript-specific types
-  "bigint",
-  "intrinsic",
-  "global",
-  "module",
-  "satisfies",
-  "out",
+  "await",
 ];
 
-pub fn analyze_directory(
-  path: &str,
-  total_counts: &mut HashMap<String, usize>,
-  file_count: &mut usize,
-) -> Result<(), Box<dyn std::error::Error>> {
-  let path = Path::new(path);
-
-  if path.is_file() && is_javascript_file(path) {
-    eprintln!("Analyzing file: {}", path.display());
-    analyze_file(path, total_counts)?;
-    *file_count += 1;
-    eprintln!("Files processed: {file_count}");
-  } else if path.is_dir() {
-    for entry in fs::read_dir(path)? {
-      let entry = entry?;
-      let entry_path = entry.path();
-
-      if entry_path.is_dir() && !should_skip_dir(&entry_path) {
-        eprintln!("Entering directory: {}", entry_path.display());
-        analyze_directory(entry_path.to_str().unwrap(), total_counts, file_count)?;
-      } else if is_javascript_file(&entry_path) {
-        eprintln!("Analyzing file: {}", entry_path.display());
-        analyze_file(&entry_path, total_counts)?;
-        *file_count += 1;
-        eprintln!("Files processed: {file_count}");
-      }
-    }
-  }
+/// Splits JavaScript/TypeScript source into tokens.
+///
+/// Template literal text and regex literals become `Str` tokens while the code
+/// inside `${}` is lexed normally. JSX markup (tag and attribute names, text
+/// children) is emitted as `Str` tokens as well, with `{}` expressions lexed
+/// as code. A `<` that does not parse as a complete JSX element, such as a
+/// TypeScript type assertion, is treated as punctuation.
+pub fn tokenize(content: &str) -> Vec<Token<'_>> {
+  let mut lexer = Lexer {
+    content,
+    pos: 0,
+    tokens: Vec::new(),
+    failed_jsx: HashSet::new(),
+    braces: Vec::new(),
+    object_ends: BTreeSet::new(),
+  };
+  lexer.lex_code(false);
+  lexer.tokens
+}
 
-  Ok(())
+struct Lexer<'a> {
+  content: &'a str,
+  pos: usize,
+  tokens: Vec<Token<'a>>,
+  /// Offsets of `<` known not to start a JSX element.
+  failed_jsx: HashSet<usize>,
+  /// Whether each open `{` of the code started an object literal.
+  braces: Vec<bool>,
+  /// Offsets of the `}` closing object literals.
+  object_ends: BTreeSet<usize>,
 }
 
-pub fn should_skip_dir(path: &Path) -> bool {
-  if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-    matches!(name, "target" | ".git" | "node_modules" | "dist" | "build")
-  } else {
-    false
+impl<'a> Lexer<'a> {
+  fn rest(&self) -> &'a str {
+    &self.content[self.pos..]
   }
-}
 
-pub fn is_javascript_file(path: &Path) -> bool {
-  if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-    matches!(ext, "ts" | "tsx" | "js" | "jsx")
-  } else {
-    false
+  fn emit(&mut self, kind: TokenKind, start: usize, end: usize) {
+    self
+      .tokens
+      .push(Token::new(kind, &self.content[start..end], start));
+    self.pos = end;
   }
-}
 
-pub fn analyze_file(
-  path: &Path,
-  total_counts: &mut HashMap<String, usize>,
-) -> Result<(), Box<dyn std::error::Error>> {
-  let content = match fs::read_to_string(path) {
-    Ok(content) => content,
-    Err(e) => {
-      eprintln!("Warning: Skipping file {} due to encoding error: {}", path.display(), e);
-      return Ok(());
+  /// Emits pending markup from `start` up to the current position.
+  fn emit_markup(&mut self, start: usize) {
+    if self.pos > start {
+      self.emit(TokenKind::Str, start, self.pos);
     }
-  };
-  let file_counts = count_keywords(&content);
+  }
 
-  for (keyword, count) in file_counts {
-    *total_counts.entry(keyword).or_insert(0) += count;
+  fn skip_whitespace(&mut self) {
+    let trimmed = self.rest().trim_start();
+    self.pos = self.content.len() - trimmed.len();
   }
 
-  Ok(())
-}
+  /// Whether the previous token leaves the lexer where an expression may
+  /// start, which decides between regex and division, or JSX and less-than.
+  fn expression_expected(&self) -> bool {
+    let mut tokens = self.code_tokens();
+    let Some(previous) = tokens.next() else {
+      return true;
+    };
+
+    match previous.kind {
+      TokenKind::Word => EXPRESSION_KEYWORDS.contains(&previous.text),
+      TokenKind::Str => previous.text.ends_with("${"),
+      TokenKind::Punct => match previous.text {
+        ")" | "]" => false,
+        // `++` and `--` end an operand, as in `i++ / n`
+        "+" | "-" => !tokens.next().is_some_and(|before| {
+          before.text == previous.text && before.offset + 1 == previous.offset
+        }),
+        "}" => !self.object_ends.contains(&previous.offset),
+        _ => true,
+      },
+      TokenKind::Comment => unreachable!(),
+    }
+  }
 
-pub fn count_keywords(content: &str) -> HashMap<String, usize> {
-  let mut counts = HashMap::new();
-  let mut chars = content.chars().peekable();
-  let mut current_token = String::new();
-  let mut in_string = false;
-  let mut in_single_comment = false;
-  let mut in_multi_comment = false;
-  let mut string_char = '\0';
-
-  while let Some(c) = chars.next() {
-    match c {
-      // Handle single-line comments
-      '/' if !in_string && !in_multi_comment => {
-        if chars.peek() == Some(&'/') {
-          chars.next(); // consume second '/'
-          in_single_comment = true;
-          if !current_token.is_empty() {
-            check_and_count_token(&current_token, &mut counts);
-            current_token.clear();
+  /// Whether a `{` at the current position starts an object literal rather
+  /// than a block: an expression is expected, but not a statement or an
+  /// arrow function body.
+  fn opens_object_literal(&self) -> bool {
+    let mut tokens = self.code_tokens();
+    let Some(previous) = tokens.next() else {
+      return false;
+    };
+
+    match previous.kind {
+      TokenKind::Word => matches!(
+        previous.text,
+        "return" | "typeof" | "in" | "of" | "void" | "throw" | "yield" | "await"
+      ),
+      TokenKind::Punct => match previous.text {
+        ";" | "{" | "}" | ")" | "]" => false,
+        ">" => !tokens
+          .next()
+          .is_some_and(|before| before.text == "=" && before.offset + 1 == previous.offset),
+        _ => true,
+      },
+      TokenKind::Str | TokenKind::Comment => false,
+    }
+  }
+
+  /// The tokens lexed so far, last first, without comments.
+  fn code_tokens(&self) -> impl Iterator<Item = &Token<'a>> {
+    self
+      .tokens
+      .iter()
+      .rev()
+      .filter(|token| token.kind != TokenKind::Comment)
+  }
+
+  /// Lexes code until the end of input or, when `nested`, until the `}`
+  /// closing the enclosing `${` or JSX `{`. Returns whether that `}` was found.
+  fn lex_code(&mut self, nested: bool) -> bool {
+    let mut depth: usize = 0;
+
+    while let Some(c) = self.rest().chars().next() {
+      let rest = self.rest();
+      let start = self.pos;
+
+      if c.is_whitespace() {
+        self.pos += c.len_utf8();
+      } else if rest.starts_with("//") {
+        let end = rest.find('\n').map_or(self.content.len(), |i| start + i);
+        self.emit(TokenKind::Comment, start, end);
+      } else if rest.starts_with("/*") {
+        let end = scan_block_comment(self.content, start, "/*", "*/", false);
+        self.emit(TokenKind::Comment, start, end);
+      } else if c == '"' || c == '\'' {
+        let quote = if c == '"' { "\"" } else { "'" };
+        let end = scan_string(self.content, start + 1, quote, Some('\\'));
+        self.emit(TokenKind::Str, start, end);
+      } else if c == '`' {
+        self.lex_template();
+      } else if let Some(end) = (c == '/' && self.expression_expected())
+        .then(|| scan_regex(self.content, start))
+        .flatten()
+      {
+        self.emit(TokenKind::Str, start, end);
+      } else if c == '<' && self.expression_expected() && self.try_jsx() {
+        continue;
+      } else if is_identifier_char(c) {
+        self.emit(TokenKind::Word, start, identifier_end(self.content, start));
+      } else {
+        if c == '{' {
+          depth += 1;
+          let object = self.opens_object_literal();
+          self.braces.push(object);
+        } else if c == '}' {
+          if nested && depth == 0 {
+            return true;
           }
-          continue;
-        } else if chars.peek() == Some(&'*') {
-          chars.next(); // consume '*'
-          in_multi_comment = true;
-          if !current_token.is_empty() {
-            check_and_count_token(&current_token, &mut counts);
-            current_token.clear();
+          depth = depth.saturating_sub(1);
+          if self.braces.pop() == Some(true) {
+            self.object_ends.insert(start);
           }
-          continue;
         }
+        self.emit(TokenKind::Punct, start, start + c.len_utf8());
+      }
+    }
+
+    !nested
+  }
+
+  /// Lexes a template literal starting at the opening backtick.
+  fn lex_template(&mut self) {
+    let mut start = self.pos;
+    self.pos += 1;
+
+    loop {
+      let rest = self.rest();
+      if rest.is_empty() {
+        self.emit(TokenKind::Str, start, self.pos);
+        return;
       }
-      // End multi-line comment
-      '*' if in_multi_comment => {
-        if chars.peek() == Some(&'/') {
-          chars.next(); // consume '/'
-          in_multi_comment = false;
-          continue;
+
+      if rest.starts_with('`') {
+        self.emit(TokenKind::Str, start, self.pos + 1);
+        return;
+      } else if rest.starts_with("${") {
+        self.emit(TokenKind::Str, start, self.pos + 2);
+        if !self.lex_code(true) {
+          return;
+        }
+        // The closing `}` starts the next chunk of template text
+        start = self.pos;
+        self.pos += 1;
+      } else {
+        let mut chars = rest.chars();
+        let c = chars.next().unwrap();
+        self.pos += c.len_utf8();
+        if c == '\\' {
+          self.pos += chars.next().map_or(0, char::len_utf8);
         }
       }
-      // End single-line comment
-      '\n' if in_single_comment => {
-        in_single_comment = false;
-        continue;
+    }
+  }
+
+  /// Tries to lex a JSX element at the current `<`, restoring the lexer state
+  /// if it turns out not to be one.
+  fn try_jsx(&mut self) -> bool {
+    let start = self.pos;
+    let next = self.rest()[1..].chars().next();
+    let starts_tag =
+      next.is_some_and(|c| c == '>' || (is_identifier_char(c) && !c.is_ascii_digit()));
+    if !starts_tag || self.failed_jsx.contains(&start) {
+      return false;
+    }
+
+    let token_count = self.tokens.len();
+    let brace_count = self.braces.len();
+    if self.lex_jsx_element() {
+      return true;
+    }
+
+    self.tokens.truncate(token_count);
+    self.braces.truncate(brace_count);
+    self.object_ends.split_off(&start);
+    self.pos = start;
+    false
+  }
+
+  /// Lexes a JSX element and its children. When it fails, the offsets of the
+  /// element and of every nested element still open are added to
+  /// `failed_jsx`, so a `<T>` guess costs one scan however many follow it.
+  fn lex_jsx_element(&mut self) -> bool {
+    let mut markup = self.pos;
+    let start = self.pos;
+    let name = match self.lex_jsx_tag(&mut markup) {
+      Some((_, true)) => return true,
+      Some((name, false)) => name,
+      None => {
+        self.failed_jsx.insert(start);
+        return false;
       }
-      // Handle strings
-      '"' | '\'' | '`' if !in_single_comment && !in_multi_comment => {
-        if !in_string {
-          in_string = true;
-          string_char = c;
-          if !current_token.is_empty() {
-            check_and_count_token(&current_token, &mut counts);
-            current_token.clear();
+    };
+
+    // Elements whose closing tag is pending, innermost last
+    let mut open = vec![(start, name)];
+    loop {
+      let rest = self.rest();
+      if rest.starts_with("</") {
+        self.pos += 2;
+        self.skip_whitespace();
+        let closing = self.jsx_name();
+        self.skip_whitespace();
+        if open.last().is_some_and(|(_, name)| *name != closing) || !self.rest().starts_with('>') {
+          break;
+        }
+        self.pos += 1;
+        self.emit_markup(markup);
+        markup = self.pos;
+        open.pop();
+        if open.is_empty() {
+          return true;
+        }
+      } else if rest.starts_with('<') {
+        let start = self.pos;
+        if self.failed_jsx.contains(&start) {
+          break;
+        }
+        self.emit_markup(markup);
+        markup = self.pos;
+        match self.lex_jsx_tag(&mut markup) {
+          Some((_, true)) => {}
+          Some((name, false)) => open.push((start, name)),
+          None => {
+            self.failed_jsx.insert(start);
+            break;
           }
-        } else if c == string_char {
-          in_string = false;
-          string_char = '\0';
         }
+      } else if rest.starts_with('{') {
+        self.emit_markup(markup);
+        if !self.lex_jsx_expression() {
+          break;
+        }
+        markup = self.pos;
+      } else if let Some(c) = rest.chars().next() {
+        self.pos += c.len_utf8();
+      } else {
+        break;
+      }
+    }
+
+    self.failed_jsx.extend(open.iter().map(|(start, _)| *start));
+    false
+  }
+
+  /// Lexes an opening tag up to its `>` or `/>`, returning its name and
+  /// whether it closes itself, or `None` if it is not a tag. `markup` is the
+  /// start of the markup not yet emitted.
+  fn lex_jsx_tag(&mut self, markup: &mut usize) -> Option<(&'a str, bool)> {
+    self.pos += 1;
+    let name = self.jsx_name();
+
+    loop {
+      self.skip_whitespace();
+      let rest = self.rest();
+      if rest.starts_with("/>") {
+        self.pos += 2;
+        self.emit_markup(*markup);
+        *markup = self.pos;
+        return Some((name, true));
+      } else if rest.starts_with('>') {
+        self.pos += 1;
+        return Some((name, false));
+      } else if rest.starts_with('{') {
+        // Spread attributes such as `{...props}`
+        self.emit_markup(*markup);
+        if !self.lex_jsx_expression() {
+          return None;
+        }
+        *markup = self.pos;
         continue;
       }
-      // Skip content inside strings and comments
-      _ if in_string || in_single_comment || in_multi_comment => {
+
+      if self.jsx_name().is_empty() {
+        return None;
+      }
+      self.skip_whitespace();
+      if !self.rest().starts_with('=') {
         continue;
       }
-      // Handle regular tokens
-      _ => {
-        if c.is_alphanumeric() || c == '_' || c == '$' {
-          current_token.push(c);
-        } else if !current_token.is_empty() {
-          check_and_count_token(&current_token, &mut counts);
-          current_token.clear();
+      self.pos += 1;
+      self.skip_whitespace();
+
+      match self.rest().chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+          let quote = if quote == '"' { "\"" } else { "'" };
+          self.pos = scan_string(self.content, self.pos + 1, quote, None);
         }
+        Some('{') => {
+          self.emit_markup(*markup);
+          if !self.lex_jsx_expression() {
+            return None;
+          }
+          *markup = self.pos;
+        }
+        Some('<') => {
+          self.emit_markup(*markup);
+          if !self.lex_jsx_element() {
+            return None;
+          }
+          *markup = self.pos;
+        }
+        _ => return None,
       }
     }
   }
 
-  // Check final token
-  if !current_token.is_empty() {
-    check_and_count_token(&current_token, &mut counts);
+  /// Lexes a `{...}` expression inside JSX.
+  fn lex_jsx_expression(&mut self) -> bool {
+    self.emit(TokenKind::Punct, self.pos, self.pos + 1);
+    if !self.lex_code(true) {
+      return false;
+    }
+    self.emit(TokenKind::Punct, self.pos, self.pos + 1);
+    true
   }
 
-  counts
-}
-
-fn check_and_count_token(token: &str, counts: &mut HashMap<String, usize>) {
-  if JAVASCRIPT_KEYWORDS.contains(&token) {
-    *counts.entry(token.to_string()).or_insert(0) += 1;
+  /// Consumes a JSX tag or attribute name such as `Foo.Bar`, `data-id` or
+  /// `xlink:href`.
+  fn jsx_name(&mut self) -> &'a str {
+    let start = self.pos;
+    let rest = self.rest();
+    let len = rest
+      .char_indices()
+      .find(|(_, c)| !(is_identifier_char(*c) || matches!(c, '.' | ':' | '-')))
+      .map_or(rest.len(), |(i, _)| i);
+    self.pos += len;
+    &self.content[start..self.pos]
   }
 }
 
-#[cfg(test)]
-mod tests {
-  use super::*;
+fn is_identifier_char(c: char) -> bool {
+  c.is_alphanumeric() || c == '_' || c == '$'
+}
 
-  #[test]
-  fn test_count_keywords() {
-    // Import/Export and Module System
-    let imports_exports = r#"
-            import { Component } from 'react';
-            export default class App extends Component {
-                const fs = require('fs');
-                
-                declare module 'external-lib' {
-                    export function method(): void;
-                }
-                
-                declare package 'my-package' {
-                    export const version: string;
-                }
-            }
-        "#;
-
-    // Classes and Object-Oriented Programming
-    let classes_oop = r#"
-            abstract class BaseEntity {
-                private readonly id: number;
-                protected name: string;
-                public static count: number = 0;
-                
-                constructor(name: string) {
-                    this.name = name;
-                    this.id = BaseEntity.count++;
-                }
-                
-                abstract process(): void;
-                
-                get getName(): string {
-                    return this.name;
-                }
-                
-                set setName(value: string) {
-                    this.name = value;
-                }
-            }
-            
-            class UserService implements Service {
-                override async fetchUser(id: bigint): Promise<User | null> {
-                    const result = await super.fetchUser(id);
-                    return result;
-                }
-            }
-        "#;
-
-    // Interface and Type Definitions
-    let interfaces_types = r#"
-            interface User {
-                name: string;
-                age: number;
-                isActive: boolean;
-                data: any;
-                tags: symbol[];
-                metadata: object;
-                id: bigint;
-            }
-            
-            type Status = 'active' | 'inactive';
-            type UserKey = keyof User;
-            type StringOrNumber = string | number;
-            type ReadonlyUser = Readonly<User>;
-            
-            enum Color {
-                Red,
-                Green,
-                Blue
-            }
-        "#;
-
-    // Advanced TypeScript Types
-    let advanced_types = r#"
-            type NonNullable<T> = T extends null | undefined ? never : T;
-            type ReturnType<T> = T extends (...args: any[]) => infer R ? R : never;
-            type Contravariant<out T> = {
-                consume: (value: T) => void;
-            };
-            type Uppercase<S extends string> = intrinsic;
-            
-            const config = {
-                apiUrl: 'https://api.example.com',
-                timeout: 5000
-            } satisfies Config;
-        "#;
-
-    // Control Flow and Logic
-    let control_flow = r#"
-            function processUsers(): void {
-                for (const user of users) {
-                    if (user.isActive) {
-                        switch (user.status) {
-                            case 'active':
-                                break;
-                            case 'inactive':
-                                break;
-                            default:
-                                debugger;
-                                continue;
-                        }
-                    } else {
-                        do {
-                            user.retryCount++;
-                        } while (user.retryCount < 3);
-                    }
-                }
-            }
-        "#;
-
-    // Async/Await and Error Handling
-    let async_error_handling = r#"
-            async function fetchUser(id: bigint): Promise<User | null> {
-                try {
-                    const response = await fetch(`/api/user/${id}`);
-                    if (!response.ok) {
-                        throw new Error('Failed to fetch user');
-                    }
-                    return await response.json() as User;
-                } catch (error) {
-                    console.error('Error fetching user:', error);
-                    return null;
-                } finally {
-                    console.log('Fetch attempt completed');
-                }
-            }
-        "#;
-
-    // Functions and Generators
-    let functions_generators = r#"
-            function isUser(obj: any): obj is User {
-                return obj && typeof obj.name === 'string';
-            }
-            
-            function assertUser(obj: unknown): asserts obj is User {
-                if (!isUser(obj)) {
-                    throw new Error('Not a user');
-                }
-            }
-            
-            function* generateUsers(): Generator<User, void, unknown> {
-                for (let i = 0; i < users.length; i++) {
-                    yield users[i];
-                }
-            }
-            
-            function logMessage(message: string): void {
-                console.log(message);
-            }
-        "#;
-
-    // Variables and Operators
-    let variables_operators = r#"
-            const API_URL: string = 'https://api.example.com';
-            let currentUser: User | null = null;
-            var globalConfig: any = {};
-            
-            const isReady: boolean = true;
-            const isDisabled: boolean = false;
-            const emptyValue: null = null;
-            const notSet: undefined = undefined;
-            const dynamicValue: unknown = 'could be anything';
-            const neverValue: never = (() => { throw new Error(); })();
-            const uniqueSymbol: unique symbol = Symbol('unique');
-            
-            if (user instanceof UserModel && typeof user.name === 'string') {
-                return user.name in validNames && user.age > 0;
-            }
-            
-            delete globalConfig.temporaryProperty;
-        "#;
-
-    // Namespace and Global Declarations
-    let namespaces_globals = r#"
-            namespace Utils {
-                export function helper(): void {}
-            }
-            
-            declare global {
-                interface Window {
-                    customProperty: unknown;
-                }
-            }
-            
-            var oldVar = 'legacy';
-            with (config) {
-                // Legacy code example
-            }
-        "#;
-
-    // Combine all sections
-    let comprehensive_content = format!(
-      "{imports_exports}\n{classes_oop}\n{interfaces_types}\n{advanced_types}\n{control_flow}\n{async_error_handling}\n{functions_generators}\n{variables_operators}\n{namespaces_globals}"
-    );
+fn identifier_end(content: &str, pos: usize) -> usize {
+  content[pos..]
+    .char_indices()
+    .find(|(_, c)| !is_identifier_char(*c))
+    .map_or(content.len(), |(i, _)| pos + i)
+}
 
-    let counts = count_keywords(&comprehensive_content);
+/// Returns the end of the regex literal starting at the `/` at `pos`,
+/// including its flags, or `None` if the line ends first.
+fn scan_regex(content: &str, pos: usize) -> Option<usize> {
+  let mut in_class = false;
+  let mut chars = content[pos + 1..].char_indices();
 
-    let mut missing_keywords = Vec::new();
-    for &keyword in JAVASCRIPT_KEYWORDS {
-      if !counts.contains_key(keyword) {
-        missing_keywords.push(keyword);
+  while let Some((i, c)) = chars.next() {
+    match c {
+      '\n' => return None,
+      '\\' => {
+        chars.next();
       }
+      '[' => in_class = true,
+      ']' => in_class = false,
+      '/' if !in_class => return Some(identifier_end(content, pos + 1 + i + 1)),
+      _ => {}
     }
+  }
 
-    if !missing_keywords.is_empty() {
-      panic!(
-        "The following keywords are missing from the comprehensive test: {missing_keywords:?}"
-      );
-    }
+  None
+}
 
-    for &keyword in JAVASCRIPT_KEYWORDS {
-      assert!(
-        counts.get(keyword).unwrap_or(&0) >= &1,
-        "Keyword '{keyword}' should appear at least once in the comprehensive example"
-      );
-    }
+pub fn spec() -> Arc<JavaScriptSpec> {
+  static SPEC: OnceLock<Arc<JavaScriptSpec>> = OnceLock::new();
+  SPEC.get_or_init(|| Arc::new(JavaScriptSpec::new())).clone()
+}
 
-    println!(
-      "✅ All {} JavaScript/TypeScript keywords are properly tested!",
-      JAVASCRIPT_KEYWORDS.len()
-    );
+pub fn count_keywords(content: &str) -> HashMap<String, usize> {
+  spec().count_keywords(content)
+}
 
-    let no_keywords_content = "hello world 123 test";
-    let no_keywords_counts = count_keywords(no_keywords_content);
-    assert!(no_keywords_counts.is_empty());
-  }
+#[cfg(test)]
+mod tests {
+  use super::*;
 
   #[test]
   fn test_is_javascript_file() {
     use std::path::PathBuf;
 
-    // JavaScript/TypeScript files should be detected
-    assert!(is_javascript_file(&PathBuf::from("app.ts")));
-    assert!(is_javascript_file(&PathBuf::from("component.tsx")));
-    assert!(is_javascript_file(&PathBuf::from("script.js")));
-    assert!(is_javascript_file(&PathBuf::from("component.jsx")));
-    assert!(is_javascript_file(&PathBuf::from("path/to/file.ts")));
+    // JavaScript files should be detected
+    assert!(spec().is_source_file(&PathBuf::from("script.js")));
+    assert!(spec().is_source_file(&PathBuf::from("component.jsx")));
+    assert!(spec().is_source_file(&PathBuf::from("module.mjs")));
+    assert!(spec().is_source_file(&PathBuf::from("path/to/config.cjs")));
+
+    // TypeScript files belong to the TypeScript spec
+    assert!(!spec().is_source_file(&PathBuf::from("app.ts")));
+    assert!(!spec().is_source_file(&PathBuf::from("component.tsx")));
 
     // Non-JavaScript files should not be detected
-    assert!(!is_javascript_file(&PathBuf::from("file.rs")));
-    assert!(!is_javascript_file(&PathBuf::from("file.py")));
-    assert!(!is_javascript_file(&PathBuf::from("file.txt")));
-    assert!(!is_javascript_file(&PathBuf::from("file")));
+    assert!(!spec().is_source_file(&PathBuf::from("file.rs")));
+    assert!(!spec().is_source_file(&PathBuf::from("file.py")));
+    assert!(!spec().is_source_file(&PathBuf::from("file.txt")));
+    assert!(!spec().is_source_file(&PathBuf::from("file")));
   }
 
   #[test]
@@ -499,65 +502,66 @@ mod tests {
     use std::path::PathBuf;
 
     // Directories that should be skipped
-    assert!(should_skip_dir(&PathBuf::from("node_modules")));
-    assert!(should_skip_dir(&PathBuf::from("dist")));
-    assert!(should_skip_dir(&PathBuf::from("build")));
-    assert!(should_skip_dir(&PathBuf::from("target")));
-    assert!(should_skip_dir(&PathBuf::from(".git")));
+    assert!(spec().should_skip_dir(&PathBuf::from("node_modules")));
+    assert!(spec().should_skip_dir(&PathBuf::from("dist")));
+    assert!(spec().should_skip_dir(&PathBuf::from("build")));
+    assert!(spec().should_skip_dir(&PathBuf::from("target")));
+    assert!(spec().should_skip_dir(&PathBuf::from(".git")));
 
     // Directories that should not be skipped
-    assert!(!should_skip_dir(&PathBuf::from("src")));
-    assert!(!should_skip_dir(&PathBuf::from("lib")));
-    assert!(!should_skip_dir(&PathBuf::from("components")));
-    assert!(!should_skip_dir(&PathBuf::from("utils")));
-  }
-
-  #[test]
-  fn test_typescript_specific_keywords() {
-    // Test TypeScript-only keywords
-    let content = "abstract class Base { abstract method(): void; }";
-    let counts = count_keywords(content);
-    assert_eq!(counts.get("abstract"), Some(&2));
-    assert_eq!(counts.get("class"), Some(&1));
-    assert_eq!(counts.get("void"), Some(&1));
-
-    // Test that keywords in strings are not counted
-    let content =
-      "type Result<T> = T | undefined; const x: unknown = null; const y: string = 'type';";
-    let counts = count_keywords(content);
-    assert_eq!(counts.get("type"), Some(&1)); // Only the actual type keyword, not the string 'type'
-    assert_eq!(counts.get("undefined"), Some(&1));
-    assert_eq!(counts.get("const"), Some(&2)); // Two const declarations
-    assert_eq!(counts.get("unknown"), Some(&1));
-    assert_eq!(counts.get("null"), Some(&1));
-    assert_eq!(counts.get("string"), Some(&1));
+    assert!(!spec().should_skip_dir(&PathBuf::from("src")));
+    assert!(!spec().should_skip_dir(&PathBuf::from("lib")));
+    assert!(!spec().should_skip_dir(&PathBuf::from("components")));
+    assert!(!spec().should_skip_dir(&PathBuf::from("utils")));
   }
 
   #[test]
   fn test_all_javascript_keywords_recognized() {
-    // Test that all keywords in JAVASCRIPT_KEYWORDS are properly recognized
-    for keyword in JAVASCRIPT_KEYWORDS {
+    // Test that all keywords of the language are properly recognized
+    for keyword in spec().keywords() {
       let content = format!("{keyword} ");
       let counts = count_keywords(&content);
       assert_eq!(
-        counts.get(*keyword),
+        counts.get(keyword),
         Some(&1),
         "Keyword '{keyword}' was not properly counted"
       );
     }
   }
 
+  #[test]
+  fn test_typescript_keywords_not_counted() {
+    let content =
+      "interface Props { readonly id: number } type Keys = keyof Props; let x = y satisfies T;";
+    let counts = count_keywords(content);
+    assert_eq!(counts.get("let"), Some(&1));
+    for keyword in [
+      "interface",
+      "readonly",
+      "number",
+      "type",
+      "keyof",
+      "satisfies",
+    ] {
+      assert_eq!(counts.get(keyword), None, "{keyword} is TypeScript-only");
+    }
+  }
+
   #[test]
   fn test_analyze_file_error_cases() {
     use std::path::PathBuf;
     let mut counts = HashMap::new();
 
     // Test with non-existent file
-    let result = analyze_file(&PathBuf::from("non_existent_file.ts"), &mut counts);
+    let result = crate::analyze_file(
+      &PathBuf::from("non_existent_file.js"),
+      spec().as_ref(),
+      &mut counts,
+    );
     assert!(result.is_err());
 
     // Test with directory instead of file (should error)
-    let result = analyze_file(&PathBuf::from("/"), &mut counts);
+    let result = crate::analyze_file(&PathBuf::from("/"), spec().as_ref(), &mut counts);
     assert!(result.is_err());
   }
 
@@ -581,7 +585,7 @@ mod tests {
     let content = "const element = <div className=\"test\">Hello</div>;";
     let counts = count_keywords(content);
     assert_eq!(counts.get("const"), Some(&1));
-    assert_eq!(counts.get("div"), None); // HTML tags are not TypeScript keywords
+    assert_eq!(counts.get("div"), None); // HTML tags are not JavaScript keywords
   }
 
   #[test]
@@ -644,4 +648,120 @@ mod tests {
     assert_eq!(counts.get("if"), None); // ifCondition should not count as 'if'
     assert_eq!(counts.get("for"), None); // forLoop should not count as 'for'
   }
+
+  fn texts<'a>(tokens: &[Token<'a>]) -> Vec<(TokenKind, &'a str)> {
+    tokens
+      .iter()
+      .map(|token| (token.kind, token.text))
+      .collect()
+  }
+
+  #[test]
+  fn test_tokenize_template_literals() {
+    let tokens = tokenize("`if ${cond ? `else ${x}` : y} for` while");
+    assert_eq!(
+      texts(&tokens),
+      vec![
+        (TokenKind::Str, "`if ${"),
+        (TokenKind::Word, "cond"),
+        (TokenKind::Punct, "?"),
+        (TokenKind::Str, "`else ${"),
+        (TokenKind::Word, "x"),
+        (TokenKind::Str, "}`"),
+        (TokenKind::Punct, ":"),
+        (TokenKind::Word, "y"),
+        (TokenKind::Str, "} for`"),
+        (TokenKind::Word, "while"),
+      ]
+    );
+
+    // Braces inside an interpolation do not end it
+    let counts = count_keywords("`${items.map(i => { return `<li>${i}</li>`; }).join('')} class`");
+    assert_eq!(counts.get("return"), Some(&1));
+    assert_eq!(counts.get("class"), None);
+  }
+
+  #[test]
+  fn test_tokenize_regex_and_division() {
+    let tokens = tokenize("const re = /if|else[/]/gi; x = a / b / c; return /for/.test(s)");
+    let strings: Vec<_> = tokens
+      .iter()
+      .filter(|token| token.kind == TokenKind::Str)
+      .map(|token| token.text)
+      .collect();
+    assert_eq!(strings, ["/if|else[/]/gi", "/for/"]);
+
+    let counts = count_keywords("let half = (total) / 2; const ratio = items.length / count;");
+    assert_eq!(counts.get("let"), Some(&1));
+    assert_eq!(counts.get("const"), Some(&1));
+
+    // Postfix operators and object literals end an operand
+    for source in [
+      "a++ / 2; /* if */ if (x) {}",
+      "i++ / n; /* if */ if (x) {}",
+      "i-- / n; /* if */ if (x) {}",
+      "const o = { a: 1 } / 2; /* if */ if (x) {}",
+    ] {
+      assert_eq!(count_keywords(source).get("if"), Some(&1), "{source}");
+    }
+    let counts = count_keywords("if (x) {} /for/.test(s); x = -/while/.source; f = () => {}\n/do/");
+    assert_eq!(counts.get("for"), None);
+    assert_eq!(counts.get("while"), None);
+    assert_eq!(counts.get("do"), None);
+  }
+
+  #[test]
+  fn test_tokenize_jsx() {
+    let source = r#"
+      function TodoList({ todos }) {
+        return (
+          <ul className="todos" {...rest}>
+            {todos.length === 0 && <li>Nothing to do if you're done</li>}
+            {todos.map((todo) => (
+              <TodoItem key={todo.id} done={todo.done ? true : false} />
+            ))}
+            <>for while</>
+          </ul>
+        );
+      }
+    "#;
+    let counts = count_keywords(source);
+    assert_eq!(counts.get("function"), Some(&1));
+    assert_eq!(counts.get("return"), Some(&1));
+    assert_eq!(counts.get("true"), Some(&1));
+    assert_eq!(counts.get("false"), Some(&1));
+    assert_eq!(counts.get("if"), None); // JSX text
+    assert_eq!(counts.get("for"), None);
+    assert_eq!(counts.get("while"), None);
+    assert_eq!(counts.get("done"), None);
+
+    // TypeScript generics and type assertions are not JSX
+    let counts = crate::typescript::count_keywords(
+      "const id = <T,>(x: T): T => x; let n = <number>value; if (a < b) {}",
+    );
+    assert_eq!(counts.get("const"), Some(&1));
+    assert_eq!(counts.get("number"), Some(&1));
+    assert_eq!(counts.get("let"), Some(&1));
+    assert_eq!(counts.get("if"), Some(&1));
+  }
+
+  #[test]
+  fn test_failed_jsx_is_not_rescanned() {
+    // Each `<T>` would scan to the end of the file if failures were retried
+    let source = "const f = <T>(x: T): Array<T> => [x];\n".repeat(5000);
+    let start = std::time::Instant::now();
+    let counts = crate::typescript::count_keywords(&source);
+    assert_eq!(counts.get("const"), Some(&5000));
+    assert_eq!(tokenize(&"<a>\n".repeat(20000)).len(), 60000);
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+  }
+
+  #[test]
+  fn test_tokenize_non_ascii_punctuation() {
+    assert_eq!(texts(&tokenize("×")), vec![(TokenKind::Punct, "×")]);
+
+    let counts = count_keywords("const s = a × 2; let arrow = x → y … z;");
+    assert_eq!(counts.get("const"), Some(&1));
+    assert_eq!(counts.get("let"), Some(&1));
+  }
 }
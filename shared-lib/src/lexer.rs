@@ -0,0 +1,249 @@
+use crate::spec::LanguageDefinition;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+  /// An identifier or keyword.
+  Word,
+  Comment,
+  Str,
+  /// Any other single non-whitespace character.
+  Punct,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Token<'a> {
+  pub kind: TokenKind,
+  pub text: &'a str,
+  /// Byte offset of the token in the source.
+  pub offset: usize,
+}
+
+impl<'a> Token<'a> {
+  pub fn new(kind: TokenKind, text: &'a str, offset: usize) -> Self {
+    Self { kind, text, offset }
+  }
+}
+
+/// Splits `content` into tokens using the comment and string syntax of
+/// `definition`. Unterminated comments and strings run to the end of input.
+pub fn tokenize<'a>(definition: &LanguageDefinition, content: &'a str) -> Vec<Token<'a>> {
+  let mut tokens = Vec::new();
+  let mut pos = 0;
+
+  while let Some(c) = content[pos..].chars().next() {
+    let rest = &content[pos..];
+
+    if c.is_whitespace() {
+      pos += c.len_utf8();
+      continue;
+    }
+
+    if definition
+      .line_comments
+      .iter()
+      .any(|marker| rest.starts_with(marker.as_str()))
+    {
+      let end = rest.find('\n').map_or(content.len(), |i| pos + i);
+      tokens.push(Token::new(TokenKind::Comment, &content[pos..end], pos));
+      pos = end;
+      continue;
+    }
+
+    if let Some(comment) = definition
+      .block_comments
+      .iter()
+      .find(|comment| rest.starts_with(comment.start.as_str()))
+    {
+      let end = scan_block_comment(content, pos, &comment.start, &comment.end, comment.nested);
+      tokens.push(Token::new(TokenKind::Comment, &content[pos..end], pos));
+      pos = end;
+      continue;
+    }
+
+    // Prefer the longest opening delimiter so `"""` wins over `"`.
+    if let Some(delimiter) = definition
+      .strings
+      .iter()
+      .filter(|delimiter| rest.starts_with(delimiter.start.as_str()))
+      .max_by_key(|delimiter| delimiter.start.len())
+    {
+      let body_start = pos + delimiter.start.len();
+      let end = scan_string(content, body_start, &delimiter.end, delimiter.escape);
+      tokens.push(Token::new(TokenKind::Str, &content[pos..end], pos));
+      pos = end;
+      continue;
+    }
+
+    if is_identifier_char(definition, c) {
+      let end = rest
+        .char_indices()
+        .find(|(_, ch)| !is_identifier_char(definition, *ch))
+        .map_or(content.len(), |(i, _)| pos + i);
+      tokens.push(Token::new(TokenKind::Word, &content[pos..end], pos));
+      pos = end;
+      continue;
+    }
+
+    let end = pos + c.len_utf8();
+    tokens.push(Token::new(TokenKind::Punct, &content[pos..end], pos));
+    pos = end;
+  }
+
+  tokens
+}
+
+/// Line lookups for byte offsets of a source file.
+pub(crate) struct Source<'a> {
+  content: &'a str,
+  line_starts: Vec<usize>,
+}
+
+impl<'a> Source<'a> {
+  pub(crate) fn new(content: &'a str) -> Self {
+    Self {
+      content,
+      line_starts: std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect(),
+    }
+  }
+
+  /// 0-based line of `offset`.
+  pub(crate) fn line_of(&self, offset: usize) -> usize {
+    self.line_starts.partition_point(|&start| start <= offset) - 1
+  }
+
+  /// The text of `line` up to `offset`.
+  pub(crate) fn before(&self, offset: usize) -> &'a str {
+    &self.content[self.line_starts[self.line_of(offset)]..offset]
+  }
+
+  pub(crate) fn line_text(&self, line: usize) -> &'a str {
+    let end = self
+      .line_starts
+      .get(line + 1)
+      .map_or(self.content.len(), |next| next - 1);
+    &self.content[self.line_starts[line]..end]
+  }
+}
+
+pub fn is_identifier_char(definition: &LanguageDefinition, c: char) -> bool {
+  c.is_alphanumeric() || c == '_' || definition.identifier_chars.contains(c)
+}
+
+/// Returns the byte offset just past the block comment starting at `pos`.
+pub(crate) fn scan_block_comment(
+  content: &str,
+  pos: usize,
+  start: &str,
+  end: &str,
+  nested: bool,
+) -> usize {
+  let mut depth = 0;
+  let mut i = pos;
+
+  while i < content.len() {
+    let rest = &content[i..];
+    if rest.starts_with(start) && (nested || depth == 0) {
+      depth += 1;
+      i += start.len();
+    } else if rest.starts_with(end) {
+      depth -= 1;
+      i += end.len();
+      if depth == 0 {
+        return i;
+      }
+    } else {
+      i += rest.chars().next().map_or(1, char::len_utf8);
+    }
+  }
+
+  content.len()
+}
+
+/// Returns the byte offset just past the closing `end` delimiter of a string
+/// whose body starts at `pos`.
+pub(crate) fn scan_string(content: &str, pos: usize, end: &str, escape: Option<char>) -> usize {
+  let mut i = pos;
+
+  while i < content.len() {
+    let rest = &content[i..];
+    if rest.starts_with(end) {
+      return i + end.len();
+    }
+
+    let c = rest.chars().next().unwrap();
+    i += c.len_utf8();
+    if Some(c) == escape {
+      // Skip the escaped character
+      if let Some(next) = content[i..].chars().next() {
+        i += next.len_utf8();
+      }
+    }
+  }
+
+  content.len()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::spec::{to_strings, BlockComment, StringDelimiter};
+
+  fn c_like() -> LanguageDefinition {
+    LanguageDefinition {
+      line_comments: to_strings(&["//"]),
+      block_comments: vec![BlockComment::new("/*", "*/")],
+      strings: vec![
+        StringDelimiter::new("\"", "\"", Some('\\')),
+        StringDelimiter::new("\"\"\"", "\"\"\"", None),
+      ],
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_tokenize_kinds() {
+    let tokens = tokenize(&c_like(), "let x = \"a\\\"b\"; // done\n/* c */ y");
+    let kinds: Vec<_> = tokens.iter().map(|t| (t.kind, t.text)).collect();
+    assert_eq!(
+      kinds,
+      vec![
+        (TokenKind::Word, "let"),
+        (TokenKind::Word, "x"),
+        (TokenKind::Punct, "="),
+        (TokenKind::Str, "\"a\\\"b\""),
+        (TokenKind::Punct, ";"),
+        (TokenKind::Comment, "// done"),
+        (TokenKind::Comment, "/* c */"),
+        (TokenKind::Word, "y"),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_longest_string_delimiter_wins() {
+    let tokens = tokenize(&c_like(), "\"\"\"a \" b\"\"\" c");
+    assert_eq!(tokens[0].text, "\"\"\"a \" b\"\"\"");
+    assert_eq!(tokens[1].text, "c");
+  }
+
+  #[test]
+  fn test_nested_block_comments() {
+    let mut definition = c_like();
+    let tokens = tokenize(&definition, "/* a /* b */ c */ d");
+    assert_eq!(tokens[0].text, "/* a /* b */");
+    definition.block_comments[0].nested = true;
+    let tokens = tokenize(&definition, "/* a /* b */ c */ d");
+    assert_eq!(tokens[0].text, "/* a /* b */ c */");
+    assert_eq!(tokens[1].text, "d");
+  }
+
+  #[test]
+  fn test_unterminated_and_offsets() {
+    let tokens = tokenize(&c_like(), "é x \"open");
+    assert_eq!(tokens[0], Token::new(TokenKind::Word, "é", 0));
+    assert_eq!(tokens[1], Token::new(TokenKind::Word, "x", 3));
+    assert_eq!(tokens[2], Token::new(TokenKind::Str, "\"open", 5));
+  }
+}
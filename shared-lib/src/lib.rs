@@ -1,17 +1,70 @@
 use std::collections::HashMap;
 use std::fs;
-use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
 
+pub mod comments;
+pub mod complexity;
 pub mod dart;
+pub mod encoding;
+pub mod error;
 pub mod golang;
+pub mod identifiers;
+pub mod ignore;
 pub mod javascript;
+pub mod lexer;
+pub mod metrics;
+pub mod occurrence;
+pub mod output;
 pub mod python;
+pub mod registry;
 pub mod ruby;
 pub mod rust;
+pub mod sequences;
+pub mod spec;
+pub mod tree;
+pub mod typescript;
+pub mod unsafe_audit;
+
+#[cfg(test)]
+mod test_dir;
 
 use serde::{Deserialize, Serialize};
 
+use ignore::IgnoreRules;
+
+pub use comments::{CommentScan, DocCoverage, Marker};
+pub use complexity::{FileComplexity, FunctionComplexity};
+pub use encoding::Encoding;
+pub use error::AnalyzerError;
+pub use identifiers::{
+  format_identifiers, generate_identifiers_json, FileVocabulary, IdentifierReport, NamingConvention,
+};
+pub use ignore::{FileFilter, Glob};
+pub use metrics::{Density, Distribution, LineCounts};
+pub use occurrence::{format_occurrences, generate_occurrences_json, Occurrence};
+pub use output::{
+  format_comments_plain, format_lines_plain, format_per_file_plain, format_plain,
+  format_repository_comments_plain, format_repository_lines_plain,
+  format_repository_per_file_plain, format_repository_plain, format_repository_tree_plain,
+  format_tree_plain, generate_csv_content, generate_html_content, generate_json_content,
+  generate_repository_csv_content, generate_repository_html_content,
+  generate_repository_json_content, generate_repository_svg_content, generate_svg_content,
+};
+pub use registry::{find_language, register_language, shebang_interpreter, LanguageRegistry};
+pub use sequences::{CooccurrenceMatrix, SequenceCounts, SequenceOptions};
+pub use spec::{
+  BlockComment, KeywordCategory, LanguageDefinition, LanguageSpec, StringDelimiter, UNCATEGORIZED,
+};
+pub use tree::{DirectoryEntry, DirectoryNode, Package};
+pub use unsafe_audit::{
+  format_unsafe_audit, generate_unsafe_audit_json, CrateUnsafe, UnsafeAudit, UnsafeKind,
+  UnsafeTotals, UnsafeUse,
+};
+
 #[derive(Clone, Copy, Serialize, Deserialize)]
 pub enum OutputFormat {
   Plain,
@@ -25,92 +78,656 @@ pub enum OutputFormat {
 pub enum Language {
   Rust,
   JavaScript,
+  TypeScript,
   Ruby,
   Golang,
   Python,
   Dart,
 }
 
+impl Language {
+  pub const ALL: [Language; 7] = [
+    Language::Rust,
+    Language::JavaScript,
+    Language::TypeScript,
+    Language::Ruby,
+    Language::Golang,
+    Language::Python,
+    Language::Dart,
+  ];
+
+  pub fn spec(self) -> Arc<dyn LanguageSpec> {
+    match self {
+      Language::Rust => rust::spec(),
+      Language::JavaScript => javascript::spec(),
+      Language::TypeScript => typescript::spec(),
+      Language::Ruby => ruby::spec(),
+      Language::Golang => golang::spec(),
+      Language::Python => python::spec(),
+      Language::Dart => dart::spec(),
+    }
+  }
+}
+
+/// Keyword counts for a single source file.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FileAnalysis {
+  /// Path relative to the analyzed directory.
+  pub path: String,
+  pub line_count: usize,
+  /// Code, comment and blank lines; `lines.total` equals `line_count`.
+  #[serde(default)]
+  pub lines: LineCounts,
+  pub total_keywords: usize,
+  pub keyword_counts: HashMap<String, usize>,
+  /// `TODO`-style markers found in the file's comments.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub markers: Vec<Marker>,
+  #[serde(default)]
+  pub docs: DocCoverage,
+  /// Keyword pairs and sequences, see [`SequenceCounts`].
+  #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+  pub keyword_pairs: HashMap<String, usize>,
+  #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+  pub keyword_sequences: HashMap<String, usize>,
+  #[serde(default)]
+  pub complexity: FileComplexity,
+}
+
+impl FileAnalysis {
+  /// A file whose lines are all taken to be code; see
+  /// [`FileAnalysis::with_lines`].
+  pub fn new(path: String, line_count: usize, keyword_counts: HashMap<String, usize>) -> Self {
+    Self {
+      path,
+      line_count,
+      lines: LineCounts {
+        total: line_count,
+        code: line_count,
+        ..Default::default()
+      },
+      total_keywords: keyword_counts.values().sum(),
+      keyword_counts,
+      markers: Vec::new(),
+      docs: DocCoverage::default(),
+      keyword_pairs: HashMap::new(),
+      keyword_sequences: HashMap::new(),
+      complexity: FileComplexity::default(),
+    }
+  }
+
+  pub fn with_lines(mut self, lines: LineCounts) -> Self {
+    self.line_count = lines.total;
+    self.lines = lines;
+    self
+  }
+
+  /// Adds the markers and doc coverage found by [`comments::scan`].
+  pub fn with_comments(mut self, scan: CommentScan) -> Self {
+    self.markers = scan.markers;
+    self.docs = scan.docs;
+    self
+  }
+
+  pub fn with_sequences(mut self, counts: SequenceCounts) -> Self {
+    self.keyword_pairs = counts.pairs;
+    self.keyword_sequences = counts.sequences;
+    self
+  }
+
+  pub fn with_complexity(mut self, complexity: FileComplexity) -> Self {
+    self.complexity = complexity;
+    self
+  }
+
+  /// Keywords per 1000 lines of code.
+  pub fn keywords_per_kloc(&self) -> f64 {
+    metrics::per_kloc(self.total_keywords, self.lines.code)
+  }
+
+  /// How often `keyword` occurs in the file.
+  pub fn count(&self, keyword: &str) -> usize {
+    self.keyword_counts.get(keyword).copied().unwrap_or(0)
+  }
+}
+
+/// A file or directory left out of an analysis because it could not be read
+/// or is generated.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedFile {
+  /// Path relative to the analyzed directory.
+  pub path: String,
+  /// Why it was skipped, e.g. `not valid UTF-8` or `generated`.
+  pub reason: String,
+}
+
+impl SkippedFile {
+  fn new(path: &Path, root: &Path, error: &AnalyzerError) -> Self {
+    let reason = match error {
+      AnalyzerError::PathNotFound(_) => "not found (broken symlink?)".to_string(),
+      AnalyzerError::PermissionDenied(_) => "permission denied".to_string(),
+      AnalyzerError::NonUtf8File(_) => "not valid UTF-8".to_string(),
+      AnalyzerError::Io { source, .. } => source.to_string(),
+      other => other.to_string(),
+    };
+    Self {
+      path: relative_path(path, root),
+      reason,
+    }
+  }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct AnalysisResult {
-  pub language: Language,
+  /// Id of the analyzed language, e.g. `go`.
+  pub language: String,
+  /// Display name of the analyzed language, e.g. `Go`.
+  pub language_name: String,
   pub file_count: usize,
   pub total_keywords: usize,
   pub keyword_counts: HashMap<String, usize>,
   pub files_analyzed: Vec<String>,
+  /// Lines of every analyzed file.
+  #[serde(default)]
+  pub lines: LineCounts,
+  /// Doc coverage of every analyzed file.
+  #[serde(default)]
+  pub docs: DocCoverage,
+  /// Per-file breakdown, in the order the files were analyzed.
+  pub files: Vec<FileAnalysis>,
+  /// Crates and packages found below the analyzed directory.
+  pub packages: Vec<Package>,
+  /// The language's keyword categories, used for the category totals.
+  #[serde(default)]
+  pub categories: Vec<KeywordCategory>,
+  /// Keyword pairs and sequences of every analyzed file.
+  #[serde(default)]
+  pub keyword_pairs: HashMap<String, usize>,
+  #[serde(default)]
+  pub keyword_sequences: HashMap<String, usize>,
+  /// How the sequences were counted.
+  #[serde(default)]
+  pub sequence_options: SequenceOptions,
+  /// Files and directories that could not be read, in the order they were
+  /// found.
+  #[serde(default)]
+  pub skipped: Vec<SkippedFile>,
+}
+
+/// Keywords found in one category.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CategoryTotal {
+  pub name: String,
+  pub count: usize,
+  /// Share of all keywords found, from 0 to 100.
+  pub percentage: f64,
 }
 
 impl AnalysisResult {
-  pub fn new(language: Language) -> Self {
+  pub fn new(spec: &dyn LanguageSpec) -> Self {
     Self {
-      language,
+      language: spec.id().to_string(),
+      language_name: spec.name().to_string(),
       file_count: 0,
       total_keywords: 0,
       keyword_counts: HashMap::new(),
       files_analyzed: Vec::new(),
+      lines: LineCounts::default(),
+      docs: DocCoverage::default(),
+      files: Vec::new(),
+      packages: Vec::new(),
+      categories: spec.definition().categories.clone(),
+      keyword_pairs: HashMap::new(),
+      keyword_sequences: HashMap::new(),
+      sequence_options: SequenceOptions::default(),
+      skipped: Vec::new(),
     }
   }
 
-  pub fn add_file(&mut self, file_path: String, counts: HashMap<String, usize>) {
+  pub fn add_file(&mut self, file: FileAnalysis) {
     self.file_count += 1;
-    self.files_analyzed.push(file_path);
+    self.files_analyzed.push(file.path.clone());
+    self.total_keywords += file.total_keywords;
+    self.lines.add(&file.lines);
+    self.docs.add(&file.docs);
 
-    for (keyword, count) in counts {
-      *self.keyword_counts.entry(keyword).or_insert(0) += count;
-      self.total_keywords += count;
+    for (keyword, count) in &file.keyword_counts {
+      *self.keyword_counts.entry(keyword.clone()).or_insert(0) += count;
+    }
+    for (pair, count) in &file.keyword_pairs {
+      *self.keyword_pairs.entry(pair.clone()).or_insert(0) += count;
     }
+    for (sequence, count) in &file.keyword_sequences {
+      *self.keyword_sequences.entry(sequence.clone()).or_insert(0) += count;
+    }
+    self.files.push(file);
   }
 
+  /// Keyword counts, most frequent first and ties by keyword.
   pub fn get_sorted_counts(&self) -> Vec<(&String, &usize)> {
     let mut sorted_counts: Vec<_> = self.keyword_counts.iter().collect();
-    sorted_counts.sort_by(|a, b| b.1.cmp(a.1));
+    sorted_counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
     sorted_counts
   }
-}
 
-pub struct KeywordAnalyzer;
+  /// Files with the most occurrences of `keyword` first, or the most keywords
+  /// overall when `keyword` is `None`. Ties are ordered by path.
+  pub fn files_sorted_by(&self, keyword: Option<&str>) -> Vec<&FileAnalysis> {
+    let score = |file: &FileAnalysis| keyword.map_or(file.total_keywords, |k| file.count(k));
+    let mut files: Vec<_> = self.files.iter().collect();
+    files.sort_by(|a, b| score(b).cmp(&score(a)).then_with(|| a.path.cmp(&b.path)));
+    files
+  }
 
-impl KeywordAnalyzer {
-  pub fn analyze_path(
-    path: &str,
-    language: Language,
-  ) -> Result<AnalysisResult, Box<dyn std::error::Error>> {
-    let mut result = AnalysisResult::new(language);
-    let mut total_counts: HashMap<String, usize> = HashMap::new();
-    let mut file_count = 0;
+  /// Approximate cyclomatic complexity summed over every file.
+  pub fn complexity(&self) -> usize {
+    self
+      .files
+      .iter()
+      .map(|file| file.complexity.complexity)
+      .sum()
+  }
 
-    let actual_path = if is_git_url(path) {
-      clone_git_repo(path)?
-    } else {
-      path.to_string()
-    };
+  /// The `limit` files with the highest complexity, ties ordered by path.
+  /// Files without decision points or functions are left out.
+  pub fn most_complex_files(&self, limit: usize) -> Vec<&FileAnalysis> {
+    let mut files: Vec<_> = self
+      .files
+      .iter()
+      .filter(|file| file.complexity.complexity > 0)
+      .collect();
+    files.sort_by(|a, b| {
+      b.complexity
+        .complexity
+        .cmp(&a.complexity.complexity)
+        .then_with(|| a.path.cmp(&b.path))
+    });
+    files.truncate(limit);
+    files
+  }
+
+  /// The `limit` most complex functions with the path of their file, ties
+  /// ordered by path and line.
+  pub fn most_complex_functions(&self, limit: usize) -> Vec<(&str, &FunctionComplexity)> {
+    let mut functions: Vec<_> = self
+      .files
+      .iter()
+      .flat_map(|file| {
+        file
+          .complexity
+          .functions
+          .iter()
+          .map(|function| (file.path.as_str(), function))
+      })
+      .collect();
+    functions.sort_by(|a, b| {
+      b.1
+        .complexity
+        .cmp(&a.1.complexity)
+        .then_with(|| (a.0, a.1.line).cmp(&(b.0, b.1.line)))
+    });
+    functions.truncate(limit);
+    functions
+  }
+
+  /// The `limit` most frequent keyword sequences, ties ordered by sequence.
+  pub fn top_sequences(&self, limit: usize) -> Vec<(&str, usize)> {
+    let mut sequences: Vec<(&str, usize)> = self
+      .keyword_sequences
+      .iter()
+      .map(|(sequence, count)| (sequence.as_str(), *count))
+      .collect();
+    sequences.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    sequences.truncate(limit);
+    sequences
+  }
+
+  /// Co-occurrence of the `size` keywords found in the most pairs.
+  pub fn cooccurrence(&self, size: usize) -> CooccurrenceMatrix {
+    CooccurrenceMatrix::from_pairs(&self.keyword_pairs, size)
+  }
+
+  /// The category `keyword` is listed under, or [`UNCATEGORIZED`].
+  pub fn category_of(&self, keyword: &str) -> &str {
+    self
+      .categories
+      .iter()
+      .find(|category| category.keywords.iter().any(|k| k == keyword))
+      .map_or(UNCATEGORIZED, |category| category.name.as_str())
+  }
 
-    let analysis_result = match language {
-      Language::Rust => rust::analyze_directory(&actual_path, &mut total_counts, &mut file_count),
-      Language::JavaScript => {
-        javascript::analyze_directory(&actual_path, &mut total_counts, &mut file_count)
+  /// Keyword totals per category, largest first. Categories without any
+  /// keyword found are left out.
+  pub fn category_totals(&self) -> Vec<CategoryTotal> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (keyword, count) in &self.keyword_counts {
+      if *count > 0 {
+        *counts.entry(self.category_of(keyword)).or_insert(0) += count;
       }
-      Language::Ruby => ruby::analyze_directory(&actual_path, &mut total_counts, &mut file_count),
-      Language::Golang => {
-        golang::analyze_directory(&actual_path, &mut total_counts, &mut file_count)
+    }
+
+    let mut totals: Vec<CategoryTotal> = counts
+      .into_iter()
+      .map(|(name, count)| CategoryTotal {
+        name: name.to_string(),
+        count,
+        percentage: count as f64 * 100.0 / self.total_keywords.max(1) as f64,
+      })
+      .collect();
+    totals.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    totals
+  }
+
+  /// Whether `category` names one of this language's categories, compared
+  /// case-insensitively with `-` and `_` standing for spaces.
+  pub fn has_category(&self, category: &str) -> bool {
+    self.matching_category(category).is_some()
+  }
+
+  fn matching_category(&self, category: &str) -> Option<&str> {
+    self
+      .categories
+      .iter()
+      .map(|category| category.name.as_str())
+      .chain([UNCATEGORIZED])
+      .find(|name| category_name_matches(name, category))
+  }
+
+  /// Drops every keyword outside `category` from the totals and the per-file
+  /// counts.
+  pub fn retain_category(&mut self, category: &str) -> Result<(), AnalyzerError> {
+    let Some(name) = self.matching_category(category).map(str::to_string) else {
+      let available: Vec<&str> = self
+        .categories
+        .iter()
+        .map(|category| category.name.as_str())
+        .collect();
+      return Err(AnalyzerError::InvalidArgument(format!(
+        "Unknown category `{category}` for {} (available: {})",
+        self.language_name,
+        available.join(", ")
+      )));
+    };
+
+    let in_category: Vec<String> = self
+      .keyword_counts
+      .keys()
+      .filter(|keyword| self.category_of(keyword) == name)
+      .cloned()
+      .collect();
+    let sequence_in_category = |sequence: &String| {
+      sequence
+        .split(' ')
+        .all(|keyword| in_category.iter().any(|k| k == keyword))
+    };
+    self
+      .keyword_counts
+      .retain(|keyword, _| in_category.contains(keyword));
+    self.total_keywords = self.keyword_counts.values().sum();
+    self
+      .keyword_pairs
+      .retain(|pair, _| sequence_in_category(pair));
+    self
+      .keyword_sequences
+      .retain(|sequence, _| sequence_in_category(sequence));
+
+    for file in &mut self.files {
+      file
+        .keyword_counts
+        .retain(|keyword, _| in_category.contains(keyword));
+      file.total_keywords = file.keyword_counts.values().sum();
+      file
+        .keyword_pairs
+        .retain(|pair, _| sequence_in_category(pair));
+      file
+        .keyword_sequences
+        .retain(|sequence, _| sequence_in_category(sequence));
+    }
+    Ok(())
+  }
+
+  /// Every marker with the path of its file, ordered by path and line.
+  pub fn markers(&self) -> Vec<(&str, &Marker)> {
+    let mut markers: Vec<_> = self
+      .files
+      .iter()
+      .flat_map(|file| {
+        file
+          .markers
+          .iter()
+          .map(|marker| (file.path.as_str(), marker))
+      })
+      .collect();
+    markers.sort_by_key(|(path, marker)| (*path, marker.line, marker.column));
+    markers
+  }
+
+  /// Keyword density and per-file statistics.
+  pub fn density(&self) -> Density {
+    Density::from_files(
+      self
+        .files
+        .iter()
+        .map(|file| (file.total_keywords, file.lines.code)),
+    )
+  }
+
+  /// Counts rolled up per directory, with package roots marked.
+  pub fn directory_tree(&self) -> DirectoryNode {
+    DirectoryNode::build(&self.files, &self.packages)
+  }
+}
+
+/// Results of a multi-language run: one [`AnalysisResult`] per language that
+/// had at least one file, largest first.
+#[derive(Serialize, Deserialize)]
+pub struct RepositoryAnalysis {
+  pub results: Vec<AnalysisResult>,
+  pub file_count: usize,
+  pub total_keywords: usize,
+  #[serde(default)]
+  pub lines: LineCounts,
+  #[serde(default)]
+  pub docs: DocCoverage,
+  /// Files and directories that could not be read.
+  #[serde(default)]
+  pub skipped: Vec<SkippedFile>,
+}
+
+impl RepositoryAnalysis {
+  pub fn from_results(results: Vec<AnalysisResult>) -> Self {
+    let skipped = results
+      .iter()
+      .flat_map(|result| result.skipped.iter().cloned())
+      .collect();
+    let mut results: Vec<_> = results
+      .into_iter()
+      .filter(|result| result.file_count > 0)
+      .collect();
+    results.sort_by(|a, b| {
+      b.file_count
+        .cmp(&a.file_count)
+        .then_with(|| a.language_name.cmp(&b.language_name))
+    });
+
+    Self {
+      file_count: results.iter().map(|result| result.file_count).sum(),
+      total_keywords: results.iter().map(|result| result.total_keywords).sum(),
+      lines: results
+        .iter()
+        .fold(LineCounts::default(), |mut lines, result| {
+          lines.add(&result.lines);
+          lines
+        }),
+      docs: results
+        .iter()
+        .fold(DocCoverage::default(), |mut docs, result| {
+          docs.add(&result.docs);
+          docs
+        }),
+      skipped,
+      results,
+    }
+  }
+
+  /// Approximate cyclomatic complexity summed over every language.
+  pub fn complexity(&self) -> usize {
+    self.results.iter().map(AnalysisResult::complexity).sum()
+  }
+
+  /// The `limit` most complex files of any language, with their language's
+  /// result.
+  pub fn most_complex_files(&self, limit: usize) -> Vec<(&AnalysisResult, &FileAnalysis)> {
+    let mut files: Vec<_> = self
+      .results
+      .iter()
+      .flat_map(|result| result.files.iter().map(move |file| (result, file)))
+      .filter(|(_, file)| file.complexity.complexity > 0)
+      .collect();
+    files.sort_by(|a, b| {
+      b.1
+        .complexity
+        .complexity
+        .cmp(&a.1.complexity.complexity)
+        .then_with(|| a.1.path.cmp(&b.1.path))
+    });
+    files.truncate(limit);
+    files
+  }
+
+  /// Keyword density and per-file statistics over every language.
+  pub fn density(&self) -> Density {
+    Density::from_files(
+      self
+        .results
+        .iter()
+        .flat_map(|result| &result.files)
+        .map(|file| (file.total_keywords, file.lines.code)),
+    )
+  }
+
+  /// Category totals across languages, merging categories with the same
+  /// name.
+  pub fn category_totals(&self) -> Vec<CategoryTotal> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for result in &self.results {
+      for total in result.category_totals() {
+        *counts.entry(total.name).or_insert(0) += total.count;
       }
-      Language::Python => {
-        python::analyze_directory(&actual_path, &mut total_counts, &mut file_count)
+    }
+
+    let mut totals: Vec<CategoryTotal> = counts
+      .into_iter()
+      .map(|(name, count)| CategoryTotal {
+        name,
+        count,
+        percentage: count as f64 * 100.0 / self.total_keywords.max(1) as f64,
+      })
+      .collect();
+    totals.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    totals
+  }
+
+  /// Applies [`AnalysisResult::retain_category`] to every language that has
+  /// `category`; the others are left with no keywords.
+  pub fn retain_category(&mut self, category: &str) -> Result<(), AnalyzerError> {
+    if !self
+      .results
+      .iter()
+      .any(|result| result.has_category(category))
+    {
+      return Err(AnalyzerError::InvalidArgument(format!(
+        "Unknown category `{category}` for the analyzed languages"
+      )));
+    }
+
+    for result in &mut self.results {
+      if result.has_category(category) {
+        result.retain_category(category)?;
+      } else {
+        result.keyword_counts.clear();
+        result.total_keywords = 0;
+        result.keyword_pairs.clear();
+        result.keyword_sequences.clear();
+        for file in &mut result.files {
+          file.keyword_counts.clear();
+          file.total_keywords = 0;
+          file.keyword_pairs.clear();
+          file.keyword_sequences.clear();
+        }
       }
-      Language::Dart => dart::analyze_directory(&actual_path, &mut total_counts, &mut file_count),
-    };
+    }
+    self.total_keywords = self
+      .results
+      .iter()
+      .map(|result| result.total_keywords)
+      .sum();
+    Ok(())
+  }
 
-    analysis_result?;
+  pub fn find(&self, language: &str) -> Option<&AnalysisResult> {
+    self
+      .results
+      .iter()
+      .find(|result| result.language == language)
+  }
+}
+
+fn category_name_matches(name: &str, query: &str) -> bool {
+  let normalize = |text: &str| text.to_lowercase().replace(['-', '_'], " ");
+  normalize(name) == normalize(query)
+}
 
-    result.file_count = file_count;
-    result.keyword_counts = total_counts;
-    result.total_keywords = result.keyword_counts.values().sum();
+/// Settings for a run of the analyzer.
+#[derive(Clone, Debug, Default)]
+pub struct AnalysisOptions {
+  pub sequences: SequenceOptions,
+  /// How source files are decoded; files that cannot be are skipped.
+  pub encoding: Encoding,
+  /// Which files are analyzed.
+  pub filter: FileFilter,
+  /// Threads analyzing files at once; 0 uses one per available core.
+  pub jobs: usize,
+}
 
-    // Clean up if it was a git repo
-    if is_git_url(path) {
-      let _ = fs::remove_dir_all(&actual_path);
+impl AnalysisOptions {
+  /// [`AnalysisOptions::jobs`], with 0 resolved to the available parallelism.
+  pub fn worker_count(&self) -> usize {
+    match self.jobs {
+      0 => thread::available_parallelism().map_or(1, |cores| cores.get()),
+      jobs => jobs,
     }
+  }
+}
+
+pub struct KeywordAnalyzer;
 
+impl KeywordAnalyzer {
+  pub fn analyze_path(path: &str, language: Language) -> Result<AnalysisResult, AnalyzerError> {
+    Self::analyze_path_with_spec(path, language.spec().as_ref())
+  }
+
+  /// Analyzes `path` with any [`LanguageSpec`], including ones that are not
+  /// built into this crate.
+  pub fn analyze_path_with_spec(
+    path: &str,
+    spec: &dyn LanguageSpec,
+  ) -> Result<AnalysisResult, AnalyzerError> {
+    Self::analyze_path_with_options(path, spec, &AnalysisOptions::default())
+  }
+
+  /// Like [`KeywordAnalyzer::analyze_path_with_spec`], with `options`.
+  pub fn analyze_path_with_options(
+    path: &str,
+    spec: &dyn LanguageSpec,
+    options: &AnalysisOptions,
+  ) -> Result<AnalysisResult, AnalyzerError> {
+    let mut result = AnalysisResult::new(spec);
+    result.sequence_options = options.sequences;
+    with_local_path(path, |root| {
+      analyze_spec_root(root, spec, options, &mut result);
+      Ok(())
+    })?;
     Ok(result)
   }
 
@@ -118,53 +735,624 @@ impl KeywordAnalyzer {
     result: &AnalysisResult,
     format: OutputFormat,
     output_file: Option<String>,
-  ) -> Result<String, Box<dyn std::error::Error>> {
-    let sorted_counts = result.get_sorted_counts();
-
-    match format {
-      OutputFormat::Plain => Ok(format_plain(
-        &sorted_counts,
-        result.file_count,
-        result.language,
-      )),
-      OutputFormat::Json => {
-        let file_path =
-          output_file.unwrap_or_else(|| generate_default_filename(result.language, "json"));
-        write_json_to_file(&sorted_counts, result.file_count, &file_path)?;
-        Ok(format!("JSON results written to: {file_path}"))
+  ) -> Result<String, AnalyzerError> {
+    output::render_output(output::Report::Single(result), format, output_file)
+  }
+
+  /// Analyzes every file below `path` whose language can be detected, using
+  /// all languages in the global registry.
+  pub fn analyze_repository(path: &str) -> Result<RepositoryAnalysis, AnalyzerError> {
+    Self::analyze_repository_with_options(path, &AnalysisOptions::default())
+  }
+
+  /// Like [`KeywordAnalyzer::analyze_repository`], with `options`.
+  pub fn analyze_repository_with_options(
+    path: &str,
+    options: &AnalysisOptions,
+  ) -> Result<RepositoryAnalysis, AnalyzerError> {
+    let languages = registry::global().read().unwrap().languages().to_vec();
+    Self::analyze_languages_with_options(path, &languages, options)
+  }
+
+  /// Like [`KeywordAnalyzer::analyze_repository`], restricted to `languages`.
+  pub fn analyze_repository_with(
+    path: &str,
+    languages: &[Arc<dyn LanguageSpec>],
+  ) -> Result<RepositoryAnalysis, AnalyzerError> {
+    Self::analyze_languages_with_options(path, languages, &AnalysisOptions::default())
+  }
+
+  /// Like [`KeywordAnalyzer::analyze_repository_with`], with `options`.
+  pub fn analyze_languages_with_options(
+    path: &str,
+    languages: &[Arc<dyn LanguageSpec>],
+    options: &AnalysisOptions,
+  ) -> Result<RepositoryAnalysis, AnalyzerError> {
+    let mut results: Vec<AnalysisResult> = languages
+      .iter()
+      .map(|spec| {
+        let mut result = AnalysisResult::new(spec.as_ref());
+        result.sequence_options = options.sequences;
+        result
+      })
+      .collect();
+    let findings = with_local_path(path, |root| {
+      Ok(analyze_languages_root(
+        root,
+        languages,
+        options,
+        &mut results,
+      ))
+    })?;
+
+    for result in &mut results {
+      result.packages = findings.packages.clone();
+    }
+    let mut analysis = RepositoryAnalysis::from_results(results);
+    analysis.skipped = findings.skipped;
+    Ok(analysis)
+  }
+
+  /// Every use of `keyword` below `path`, ordered by file, line and column.
+  /// With `spec` only that language's files are searched, otherwise the
+  /// files of every registered language that has the keyword. `context` is
+  /// the number of lines kept around each occurrence.
+  pub fn find_occurrences(
+    path: &str,
+    keyword: &str,
+    spec: Option<&dyn LanguageSpec>,
+    context: usize,
+    options: &AnalysisOptions,
+  ) -> Result<Vec<Occurrence>, AnalyzerError> {
+    let languages: Vec<Arc<dyn LanguageSpec>> = match spec {
+      Some(spec) if !spec.is_keyword(keyword) => {
+        return Err(AnalyzerError::InvalidArgument(format!(
+          "`{keyword}` is not a {} keyword",
+          spec.name()
+        )));
       }
-      OutputFormat::Csv => {
-        let file_path =
-          output_file.unwrap_or_else(|| generate_default_filename(result.language, "csv"));
-        write_csv_to_file(&sorted_counts, result.file_count, &file_path)?;
-        Ok(format!("CSV results written to: {file_path}"))
+      Some(_) => Vec::new(),
+      None => registry::global()
+        .read()
+        .unwrap()
+        .languages()
+        .iter()
+        .filter(|spec| spec.is_keyword(keyword))
+        .cloned()
+        .collect(),
+    };
+    if spec.is_none() && languages.is_empty() {
+      return Err(AnalyzerError::InvalidArgument(format!(
+        "`{keyword}` is not a keyword of any known language"
+      )));
+    }
+
+    let mut occurrences: Vec<Occurrence> = with_local_path(path, |root| {
+      let (specs, findings) = walk_each(root, spec, &languages, options);
+      let found = visit_files(
+        &findings.files,
+        root,
+        &specs,
+        options,
+        |path, spec, content| {
+          occurrence::find_in_content(spec, &relative_path(path, root), content, keyword, context)
+        },
+      );
+      Ok(found.into_iter().flatten().flatten().collect())
+    })?;
+
+    occurrences.sort_by(|a, b| {
+      (a.path.as_str(), a.line, a.column).cmp(&(b.path.as_str(), b.line, b.column))
+    });
+    Ok(occurrences)
+  }
+
+  /// Counts the identifiers below `path`, per language: only `spec`'s files
+  /// with a language given, otherwise those of every registered language.
+  /// With `naming`, only identifiers following that convention are counted.
+  pub fn analyze_identifiers(
+    path: &str,
+    spec: Option<&dyn LanguageSpec>,
+    naming: Option<NamingConvention>,
+    options: &AnalysisOptions,
+  ) -> Result<Vec<IdentifierReport>, AnalyzerError> {
+    let languages = match spec {
+      Some(_) => Vec::new(),
+      None => registry::global().read().unwrap().languages().to_vec(),
+    };
+
+    let mut reports: Vec<IdentifierReport> = with_local_path(path, |root| {
+      let (specs, findings) = walk_each(root, spec, &languages, options);
+      let found = visit_files(
+        &findings.files,
+        root,
+        &specs,
+        options,
+        |path, spec, content| {
+          let tokens = spec.tokenize(content);
+          let mut counts = identifiers::count_identifiers(spec, content, &tokens);
+          if let Some(naming) = naming {
+            counts.retain(|identifier, _| naming.matches(identifier));
+          }
+          (relative_path(path, root), counts)
+        },
+      );
+
+      // Only languages with at least one readable file get a report
+      let mut reports: Vec<Option<IdentifierReport>> = specs.iter().map(|_| None).collect();
+      for ((_, language), outcome) in findings.files.iter().zip(found) {
+        if let Ok((path, counts)) = outcome {
+          reports[*language]
+            .get_or_insert_with(|| IdentifierReport::new(specs[*language]))
+            .add_file(path, counts);
+        }
       }
-      OutputFormat::Html => {
-        let file_path =
-          output_file.unwrap_or_else(|| generate_default_filename(result.language, "html"));
-        write_html_to_file(
-          &sorted_counts,
-          result.file_count,
-          result.language,
-          &file_path,
-        )?;
-        Ok(format!("HTML results written to: {file_path}"))
+      Ok(reports.into_iter().flatten().collect())
+    })?;
+
+    reports.sort_by(|a, b| {
+      b.files
+        .len()
+        .cmp(&a.files.len())
+        .then_with(|| a.language_name.cmp(&b.language_name))
+    });
+    Ok(reports)
+  }
+
+  /// Lists every `unsafe` block, function, impl and trait in the Rust files
+  /// below `path`, with totals per crate.
+  pub fn audit_unsafe(path: &str, options: &AnalysisOptions) -> Result<UnsafeAudit, AnalyzerError> {
+    let spec = Language::Rust.spec();
+    with_local_path(path, |root| {
+      let findings = walk_sources(root, Some(spec.as_ref()), &[], options);
+      let found = visit_files(
+        &findings.files,
+        root,
+        &[spec.as_ref()],
+        options,
+        |path, _, content| unsafe_audit::find_unsafe(&relative_path(path, root), content),
+      );
+      let uses = found.into_iter().flatten().flatten().collect();
+      Ok(UnsafeAudit::new(uses, &findings.packages))
+    })
+  }
+
+  pub fn format_repository_output(
+    analysis: &RepositoryAnalysis,
+    format: OutputFormat,
+    output_file: Option<String>,
+  ) -> Result<String, AnalyzerError> {
+    output::render_output(output::Report::Repository(analysis), format, output_file)
+  }
+}
+
+/// Runs `f` on `path`, or on a temporary clone when `path` is a Git URL. The
+/// clone is removed afterwards, whether `f` succeeds or not.
+fn with_local_path<T>(
+  path: &str,
+  f: impl FnOnce(&Path) -> Result<T, AnalyzerError>,
+) -> Result<T, AnalyzerError> {
+  if !is_git_url(path) {
+    let path = Path::new(path);
+    if !path.exists() {
+      return Err(AnalyzerError::PathNotFound(path.to_path_buf()));
+    }
+    return f(path);
+  }
+
+  let clone = clone_git_repo(path)?;
+  let outcome = f(Path::new(&clone));
+  let _ = fs::remove_dir_all(&clone);
+  outcome
+}
+
+/// Walks `root` for `spec`'s files, or for those of each of `languages` when
+/// `spec` is `None`, returning the languages the files' indices refer to.
+fn walk_each<'a>(
+  root: &Path,
+  spec: Option<&'a dyn LanguageSpec>,
+  languages: &'a [Arc<dyn LanguageSpec>],
+  options: &AnalysisOptions,
+) -> (Vec<&'a dyn LanguageSpec>, WalkFindings) {
+  let specs = match spec {
+    Some(spec) => vec![spec],
+    None => languages.iter().map(|spec| spec.as_ref()).collect(),
+  };
+  (specs, walk_sources(root, spec, languages, options))
+}
+
+fn read_source(path: &Path, encoding: Encoding) -> Result<String, AnalyzerError> {
+  let bytes = fs::read(path).map_err(|e| AnalyzerError::io(path, e))?;
+  encoding::decode(bytes, encoding).ok_or_else(|| AnalyzerError::NonUtf8File(path.to_path_buf()))
+}
+
+fn read_dir(path: &Path) -> Result<fs::ReadDir, AnalyzerError> {
+  fs::read_dir(path).map_err(|e| AnalyzerError::io(path, e))
+}
+
+/// Recursively analyzes every source file of `spec` below `path`. `path` may
+/// also point at a single file; a missing path is `PathNotFound`.
+pub fn analyze_directory(
+  path: &Path,
+  spec: &dyn LanguageSpec,
+  total_counts: &mut HashMap<String, usize>,
+  file_count: &mut usize,
+) -> Result<(), AnalyzerError> {
+  if !path.exists() {
+    return Err(AnalyzerError::PathNotFound(path.to_path_buf()));
+  }
+  let mut result = AnalysisResult::new(spec);
+  analyze_spec_root(path, spec, &AnalysisOptions::default(), &mut result);
+
+  *file_count += result.file_count;
+  for (keyword, count) in result.keyword_counts {
+    *total_counts.entry(keyword).or_insert(0) += count;
+  }
+  Ok(())
+}
+
+/// Walks `root` for `spec`'s files, analyzes them and adds them to `result`
+/// with the packages and skipped entries found on the way.
+fn analyze_spec_root(
+  root: &Path,
+  spec: &dyn LanguageSpec,
+  options: &AnalysisOptions,
+  result: &mut AnalysisResult,
+) {
+  let mut findings = walk_sources(root, Some(spec), &[], options);
+  analyze_found_files(
+    root,
+    &[spec],
+    options,
+    &mut findings,
+    std::slice::from_mut(result),
+  );
+  result.packages.extend(findings.packages);
+  result.skipped.extend(findings.skipped);
+}
+
+/// Walks `root` once for all of `languages` and adds each file found to the
+/// result of its language in `results`. Returns the packages and skipped
+/// entries found.
+fn analyze_languages_root(
+  root: &Path,
+  languages: &[Arc<dyn LanguageSpec>],
+  options: &AnalysisOptions,
+  results: &mut [AnalysisResult],
+) -> WalkFindings {
+  let mut findings = walk_sources(root, None, languages, options);
+  let specs: Vec<&dyn LanguageSpec> = languages.iter().map(|spec| spec.as_ref()).collect();
+  analyze_found_files(root, &specs, options, &mut findings, results);
+  findings
+}
+
+/// Walks `root` for `spec`'s files, or for those of each of `languages`
+/// when `spec` is `None`, honoring `options.filter`.
+pub(crate) fn walk_sources(
+  root: &Path,
+  spec: Option<&dyn LanguageSpec>,
+  languages: &[Arc<dyn LanguageSpec>],
+  options: &AnalysisOptions,
+) -> WalkFindings {
+  let mut findings = WalkFindings::default();
+  match spec {
+    Some(spec) => walk_spec_directory(
+      root,
+      root,
+      spec,
+      options,
+      &IgnoreRules::default(),
+      &mut findings,
+    ),
+    None => walk_repository_directory(
+      root,
+      root,
+      languages,
+      &DirScope::new(languages.len()),
+      options,
+      &mut findings,
+    ),
+  }
+  findings
+}
+
+/// What a walk finds: the source files to analyze, each with the index of
+/// its language, and the packages and unreadable entries seen on the way.
+#[derive(Default)]
+pub(crate) struct WalkFindings {
+  pub(crate) files: Vec<(PathBuf, usize)>,
+  pub(crate) packages: Vec<Package>,
+  pub(crate) skipped: Vec<SkippedFile>,
+}
+
+/// The entries of the directory `path`, sorted so that every walk visits
+/// files in the same order. Unreadable entries are recorded in `findings`.
+fn sorted_entries(path: &Path, root: &Path, findings: &mut WalkFindings) -> Vec<PathBuf> {
+  let entries = match read_dir(path) {
+    Ok(entries) => entries,
+    Err(e) => {
+      findings.skipped.push(skipped(path, root, e));
+      return Vec::new();
+    }
+  };
+  let mut paths = Vec::new();
+  for entry in entries {
+    match entry {
+      Ok(entry) => paths.push(entry.path()),
+      Err(e) => findings
+        .skipped
+        .push(skipped(path, root, AnalyzerError::io(path, e))),
+    }
+  }
+  paths.sort();
+  paths
+}
+
+/// Collects every source file of `spec` below `path`, with paths relative to
+/// `root`. `rules` are the ignore rules of `path`'s parents.
+fn walk_spec_directory(
+  path: &Path,
+  root: &Path,
+  spec: &dyn LanguageSpec,
+  options: &AnalysisOptions,
+  rules: &IgnoreRules,
+  findings: &mut WalkFindings,
+) {
+  if !path.is_dir() {
+    if spec.is_source_file(path) {
+      findings.files.push((path.to_path_buf(), 0));
+    }
+    return;
+  }
+  findings.packages.extend(tree::detect_package(path, root));
+  let rules = options.filter.rules_in(path, root, rules);
+
+  for entry_path in sorted_entries(path, root, findings) {
+    let relative = relative_path(&entry_path, root);
+    if entry_path.is_dir() {
+      if !spec.should_skip_dir(&entry_path) && options.filter.is_walked(&rules, &relative, true) {
+        eprintln!("Entering directory: {}", entry_path.display());
+        walk_spec_directory(&entry_path, root, spec, options, &rules, findings);
       }
-      OutputFormat::Graph => {
-        let file_path =
-          output_file.unwrap_or_else(|| generate_default_filename(result.language, "svg"));
-        write_graph_to_file(
-          &sorted_counts,
-          result.file_count,
-          result.language,
-          &file_path,
-        )?;
-        Ok(format!("Graph results written to: {file_path}"))
+    } else if spec.is_source_file(&entry_path) && options.filter.is_walked(&rules, &relative, false)
+    {
+      findings.files.push((entry_path, 0));
+    }
+  }
+}
+
+/// What applies below a directory of a multi-language walk: the languages
+/// still looking for files and the ignore rules of the directory's parents.
+struct DirScope {
+  active: Vec<bool>,
+  rules: IgnoreRules,
+}
+
+impl DirScope {
+  fn new(languages: usize) -> Self {
+    Self {
+      active: vec![true; languages],
+      rules: IgnoreRules::default(),
+    }
+  }
+}
+
+/// Walks `path` once for several languages, collecting the files whose
+/// language can be detected. A directory skipped by one language is still
+/// entered for the others.
+fn walk_repository_directory(
+  path: &Path,
+  root: &Path,
+  languages: &[Arc<dyn LanguageSpec>],
+  scope: &DirScope,
+  options: &AnalysisOptions,
+  findings: &mut WalkFindings,
+) {
+  if !path.is_dir() {
+    if let Some(index) = registry::detect_among(languages, &scope.active, path) {
+      findings.files.push((path.to_path_buf(), index));
+    }
+    return;
+  }
+  findings.packages.extend(tree::detect_package(path, root));
+  let rules = options.filter.rules_in(path, root, &scope.rules);
+
+  for entry_path in sorted_entries(path, root, findings) {
+    let is_dir = entry_path.is_dir();
+    if !options
+      .filter
+      .is_walked(&rules, &relative_path(&entry_path, root), is_dir)
+    {
+      continue;
+    }
+    let nested = DirScope {
+      active: languages
+        .iter()
+        .zip(&scope.active)
+        .map(|(spec, active)| *active && !(is_dir && spec.should_skip_dir(&entry_path)))
+        .collect(),
+      rules: rules.clone(),
+    };
+    if is_dir {
+      if !nested.active.contains(&true) {
+        continue;
       }
+      eprintln!("Entering directory: {}", entry_path.display());
+    }
+    walk_repository_directory(&entry_path, root, languages, &nested, options, findings);
+  }
+}
+
+/// Analyzes the files of `findings` and adds them to `results[language]` in
+/// the order they were found; those that cannot be analyzed are added to
+/// `findings.skipped`.
+fn analyze_found_files(
+  root: &Path,
+  specs: &[&dyn LanguageSpec],
+  options: &AnalysisOptions,
+  findings: &mut WalkFindings,
+  results: &mut [AnalysisResult],
+) {
+  let files = std::mem::take(&mut findings.files);
+  let outcomes = visit_files(&files, root, specs, options, |path, spec, content| {
+    analyze_content(path, root, spec, options, content)
+  });
+  for ((_, language), outcome) in files.iter().zip(outcomes) {
+    match outcome {
+      Ok(file) => results[*language].add_file(file),
+      Err(file) => findings.skipped.push(file),
     }
   }
 }
 
+/// Reads the `files` of a walk and runs `visit` on each with its language and
+/// content, on up to [`AnalysisOptions::worker_count`] threads, each taking
+/// the next file from a shared index and keeping its own outcomes. The
+/// outcomes are returned in the order of `files`, whichever thread produced
+/// them.
+pub(crate) fn visit_files<T: Send>(
+  files: &[(PathBuf, usize)],
+  root: &Path,
+  specs: &[&dyn LanguageSpec],
+  options: &AnalysisOptions,
+  visit: impl Fn(&Path, &dyn LanguageSpec, &str) -> T + Sync,
+) -> Vec<Result<T, SkippedFile>> {
+  let analyze = |(path, language): &(PathBuf, usize)| {
+    let spec = specs[*language];
+    read_walked_file(path, root, spec, options).map(|content| visit(path, spec, &content))
+  };
+  let workers = options.worker_count().min(files.len());
+  if workers <= 1 {
+    return files.iter().map(analyze).collect();
+  }
+
+  let next = AtomicUsize::new(0);
+  let mut outcomes: Vec<_> = thread::scope(|scope| {
+    let handles: Vec<_> = (0..workers)
+      .map(|_| {
+        scope.spawn(|| {
+          let mut outcomes = Vec::new();
+          loop {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            let Some(file) = files.get(index) else {
+              return outcomes;
+            };
+            outcomes.push((index, analyze(file)));
+          }
+        })
+      })
+      .collect();
+    handles
+      .into_iter()
+      .flat_map(|handle| handle.join().unwrap())
+      .collect()
+  });
+  outcomes.sort_by_key(|(index, _)| *index);
+  outcomes.into_iter().map(|(_, outcome)| outcome).collect()
+}
+
+/// Reads a file found by a walk. Generated files and files that cannot be
+/// read are returned as skipped instead.
+fn read_walked_file(
+  path: &Path,
+  root: &Path,
+  spec: &dyn LanguageSpec,
+  options: &AnalysisOptions,
+) -> Result<String, SkippedFile> {
+  eprintln!("Analyzing {} file: {}", spec.name(), path.display());
+  let content = read_source(path, options.encoding).map_err(|e| skipped(path, root, e))?;
+  if !options.filter.include_generated && ignore::is_generated(&content) {
+    eprintln!("Skipping generated file: {}", path.display());
+    return Err(SkippedFile {
+      path: relative_path(path, root),
+      reason: "generated".to_string(),
+    });
+  }
+  Ok(content)
+}
+
+/// Reports that `path` is left out because of `error`.
+fn skipped(path: &Path, root: &Path, error: AnalyzerError) -> SkippedFile {
+  let file = SkippedFile::new(path, root, &error);
+  eprintln!("Skipping {}: {}", path.display(), file.reason);
+  file
+}
+
+/// Counts the keywords, keyword sequences and the code, comment and blank
+/// lines of a single file, estimates its complexity and collects its comment
+/// markers and doc coverage. The recorded path is relative to `root` unless `path` is `root`
+/// itself.
+pub fn analyze_source_file(
+  path: &Path,
+  root: &Path,
+  spec: &dyn LanguageSpec,
+) -> Result<FileAnalysis, AnalyzerError> {
+  analyze_source_file_with(path, root, spec, &AnalysisOptions::default())
+}
+
+/// Like [`analyze_source_file`], with `options`.
+pub fn analyze_source_file_with(
+  path: &Path,
+  root: &Path,
+  spec: &dyn LanguageSpec,
+  options: &AnalysisOptions,
+) -> Result<FileAnalysis, AnalyzerError> {
+  let content = read_source(path, options.encoding)?;
+  Ok(analyze_content(path, root, spec, options, &content))
+}
+
+/// [`analyze_source_file_with`] for the already read `content` of `path`.
+fn analyze_content(
+  path: &Path,
+  root: &Path,
+  spec: &dyn LanguageSpec,
+  options: &AnalysisOptions,
+  content: &str,
+) -> FileAnalysis {
+  let tokens = spec.tokenize(content);
+  let file = FileAnalysis::new(
+    relative_path(path, root),
+    0,
+    spec.count_keywords_in(content, &tokens),
+  );
+  file
+    .with_lines(LineCounts::from_tokens(content, &tokens))
+    .with_comments(comments::scan(spec, content, &tokens))
+    .with_complexity(complexity::measure(spec, content, &tokens))
+    .with_sequences(sequences::count_sequences(
+      spec,
+      content,
+      &tokens,
+      options.sequences,
+    ))
+}
+
+/// `path` relative to `root`, or `path` itself when it is `root`.
+pub(crate) fn relative_path(path: &Path, root: &Path) -> String {
+  path
+    .strip_prefix(root)
+    .ok()
+    .filter(|relative| !relative.as_os_str().is_empty())
+    .unwrap_or(path)
+    .display()
+    .to_string()
+}
+
+pub fn analyze_file(
+  path: &Path,
+  spec: &dyn LanguageSpec,
+  total_counts: &mut HashMap<String, usize>,
+) -> Result<(), AnalyzerError> {
+  let content = read_source(path, Encoding::default())?;
+  let file_counts = spec.count_keywords(&content);
+
+  for (keyword, count) in file_counts {
+    *total_counts.entry(keyword).or_insert(0) += count;
+  }
+
+  Ok(())
+}
+
 pub fn is_git_url(input: &str) -> bool {
   input.starts_with("https://github.com/")
     || input.starts_with("http://github.com/")
@@ -208,8 +1396,20 @@ pub fn is_valid_gitlab_repo_url(url: &str) -> bool {
   }
 }
 
-pub fn clone_git_repo(url: &str) -> Result<String, Box<dyn std::error::Error>> {
-  let temp_dir = format!("/tmp/rust_analyzer_{}", std::process::id());
+/// A directory for the next clone, distinct for every clone of this process
+/// so that concurrent analyses, such as overlapping API requests, don't share
+/// a checkout.
+fn clone_dir() -> String {
+  static CLONES: AtomicUsize = AtomicUsize::new(0);
+  format!(
+    "/tmp/rust_analyzer_{}_{}",
+    std::process::id(),
+    CLONES.fetch_add(1, Ordering::Relaxed)
+  )
+}
+
+pub fn clone_git_repo(url: &str) -> Result<String, AnalyzerError> {
+  let temp_dir = clone_dir();
 
   let normalized_url = if url.starts_with("github.com/") || url.starts_with("gitlab.com/") {
     format!("https://{url}")
@@ -220,604 +1420,375 @@ pub fn clone_git_repo(url: &str) -> Result<String, Box<dyn std::error::Error>> {
   eprintln!("Cloning repository: {normalized_url}");
   eprintln!("Target directory: {temp_dir}");
 
+  // Missing and private repositories fail instead of prompting for credentials
   let output = Command::new("git")
     .args(["clone", "--depth", "1", &normalized_url, &temp_dir])
-    .output()?;
+    .env("GIT_TERMINAL_PROMPT", "0")
+    .output()
+    .map_err(|e| AnalyzerError::CloneFailed {
+      url: normalized_url.clone(),
+      message: format!("could not run git: {e}"),
+    })?;
 
   if !output.status.success() {
     let error_msg = String::from_utf8_lossy(&output.stderr);
-    return Err(format!("Failed to clone repository: {error_msg}").into());
+    let not_found = ["not found", "could not be found", "could not read Username"]
+      .iter()
+      .any(|pattern| error_msg.contains(pattern));
+    return Err(if not_found {
+      AnalyzerError::RepositoryNotFound(normalized_url)
+    } else {
+      AnalyzerError::CloneFailed {
+        url: normalized_url,
+        message: error_msg.trim().to_string(),
+      }
+    });
   }
 
   eprintln!("Repository cloned successfully");
   Ok(temp_dir)
 }
 
-fn format_plain(
-  sorted_counts: &[(&String, &usize)],
-  file_count: usize,
-  language: Language,
-) -> String {
-  let language_name = match language {
-    Language::Rust => "Rust",
-    Language::JavaScript => "JavaScript/TypeScript",
-    Language::Ruby => "Ruby",
-    Language::Golang => "Go",
-    Language::Python => "Python",
-    Language::Dart => "Dart",
-  };
-
-  let mut output = String::new();
-  output.push_str(&format!(
-    "\n=== {language_name} Keyword Analysis Results ===\n"
-  ));
-  output.push_str(&format!("Files analyzed: {file_count}\n"));
-  output.push_str(&format!(
-    "Total keywords found: {}\n\n",
-    sorted_counts
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_dir::TestDir;
+
+  #[test]
+  fn test_analyze_repository_detects_languages() {
+    let root = TestDir::new(&[
+      ("src/main.rs", "fn main() { let x = 1; }"),
+      ("src/app.py", "def run():\n    return 1\n"),
+      ("vendor/gem/lib.rb", "def vendored; end"),
+      // `vendor` is skipped by Ruby and Go only, so Python still looks inside
+      ("vendor/gem/build.py", "import os\n"),
+      // `bin` is skipped by Go only
+      ("bin/tool", "#!/usr/bin/env ruby\nputs 1 if true\n"),
+      ("README.md", "fn def class"),
+      ("Cargo.toml", "[package]\nname = \"demo\"\n"),
+    ]);
+
+    let analysis = KeywordAnalyzer::analyze_repository(root.as_str()).unwrap();
+
+    let ids: Vec<_> = analysis
+      .results
       .iter()
-      .map(|(_, count)| **count)
-      .sum::<usize>()
-  ));
-
-  for (keyword, count) in sorted_counts {
-    if **count > 0 {
-      output.push_str(&format!("{keyword:12} : {count}\n"));
-    }
-  }
-
-  output
-}
-
-fn generate_default_filename(language: Language, extension: &str) -> String {
-  let language_name = match language {
-    Language::Rust => "rust",
-    Language::JavaScript => "javascript",
-    Language::Ruby => "ruby",
-    Language::Golang => "go",
-    Language::Python => "python",
-    Language::Dart => "dart",
-  };
-
-  let timestamp = std::time::SystemTime::now()
-    .duration_since(std::time::UNIX_EPOCH)
-    .unwrap()
-    .as_secs();
-
-  format!("keyword_analysis_{language_name}_{timestamp}.{extension}")
-}
-
-fn write_json_to_file(
-  sorted_counts: &[(&String, &usize)],
-  file_count: usize,
-  file_path: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-  let mut file = fs::File::create(file_path)?;
-  let total_keywords = sorted_counts
-    .iter()
-    .map(|(_, count)| **count)
-    .sum::<usize>();
-
-  writeln!(file, "{{")?;
-  writeln!(file, "  \"files_analyzed\": {file_count},")?;
-  writeln!(file, "  \"total_keywords\": {total_keywords},")?;
-  writeln!(file, "  \"keywords\": {{")?;
-
-  let mut first = true;
-  for (keyword, count) in sorted_counts {
-    if **count > 0 {
-      if !first {
-        writeln!(file, ",")?;
-      }
-      write!(file, "    \"{keyword}\": {count}")?;
-      first = false;
-    }
-  }
-
-  if !first {
-    writeln!(file)?;
+      .map(|result| result.language.as_str())
+      .collect();
+    assert_eq!(ids, ["python", "ruby", "rust"]);
+    assert_eq!(analysis.file_count, 4);
+
+    let python = analysis.find("python").unwrap();
+    assert_eq!(python.keyword_counts.get("import"), Some(&1));
+    let app = python.files.iter().find(|file| file.path == "src/app.py");
+    assert_eq!(app.map(|file| file.line_count), Some(2));
+    assert_eq!(python.lines.code, 3);
+    assert_eq!(analysis.lines.total, 6);
+    let ruby = analysis.find("ruby").unwrap();
+    assert_eq!(ruby.file_count, 1);
+    assert_eq!(ruby.keyword_counts.get("if"), Some(&1));
+    assert_eq!(analysis.find("rust").unwrap().total_keywords, 2);
+    let packages = &analysis.find("rust").unwrap().packages;
+    assert_eq!(packages.len(), 1);
+    assert_eq!(
+      (packages[0].path.as_str(), packages[0].name.as_str()),
+      ("", "demo")
+    );
+    assert!(analysis.find("javascript").is_none());
   }
-  writeln!(file, "  }}")?;
-  writeln!(file, "}}")?;
-
-  println!("JSON results written to: {file_path}");
-  Ok(())
-}
 
-fn write_csv_to_file(
-  sorted_counts: &[(&String, &usize)],
-  file_count: usize,
-  file_path: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-  let mut file = fs::File::create(file_path)?;
-  let total_keywords = sorted_counts
-    .iter()
-    .map(|(_, count)| **count)
-    .sum::<usize>();
-
-  writeln!(file, "keyword,count")?;
-  writeln!(file, "_files_analyzed,{file_count}")?;
-  writeln!(file, "_total_keywords,{total_keywords}")?;
-
-  for (keyword, count) in sorted_counts {
-    if **count > 0 {
-      writeln!(file, "{keyword},{count}")?;
-    }
+  #[test]
+  fn test_ignore_files_globs_and_generated_files() {
+    let root = TestDir::new(&[
+      (".gitignore", "src/generated/\n"),
+      ("api/.ignore", "*.pb.go\n"),
+      ("src/main.go", "package main\nfunc main() {}\n"),
+      ("src/main_test.go", "package main\n"),
+      ("src/generated/types.go", "package generated\n"),
+      ("api/user.pb.go", "package api\n"),
+      (
+        "api/mock.go",
+        "// Code generated by mockgen. DO NOT EDIT.\npackage api\n",
+      ),
+    ]);
+
+    let path = root.as_str();
+    let spec = Language::Golang.spec();
+    let analyze = |filter: FileFilter| {
+      let options = AnalysisOptions {
+        filter,
+        ..AnalysisOptions::default()
+      };
+      let result = KeywordAnalyzer::analyze_path_with_options(path, spec.as_ref(), &options);
+      let mut files = result.unwrap().files_analyzed;
+      files.sort();
+      files
+    };
+    let default = analyze(FileFilter::default());
+    let excluded = analyze(FileFilter {
+      exclude: vec![Glob::new("*_test.go")],
+      ..FileFilter::default()
+    });
+    let everything = analyze(FileFilter {
+      no_ignore_files: true,
+      include_generated: true,
+      ..FileFilter::default()
+    });
+    let repository = KeywordAnalyzer::analyze_repository(path);
+
+    assert_eq!(default, ["src/main.go", "src/main_test.go"]);
+    assert_eq!(excluded, ["src/main.go"]);
+    assert_eq!(everything.len(), 5);
+    let repository = repository.unwrap();
+    assert_eq!(repository.file_count, 2);
+    assert_eq!(
+      repository.skipped,
+      [SkippedFile {
+        path: "api/mock.go".to_string(),
+        reason: "generated".to_string(),
+      }]
+    );
   }
 
-  println!("CSV results written to: {file_path}");
-  Ok(())
-}
-
-fn write_html_to_file(
-  sorted_counts: &[(&String, &usize)],
-  file_count: usize,
-  language: Language,
-  file_path: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-  let mut file = fs::File::create(file_path)?;
-  let language_name = match language {
-    Language::Rust => "Rust",
-    Language::JavaScript => "JavaScript/TypeScript",
-    Language::Ruby => "Ruby",
-    Language::Golang => "Go",
-    Language::Python => "Python",
-    Language::Dart => "Dart",
-  };
-
-  let total_keywords: usize = sorted_counts.iter().map(|(_, count)| **count).sum();
-
-  let html_content = format!(
-    r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>{language_name} Keyword Analysis Results</title>
-    <style>
-        body {{ font-family: 'Segoe UI', Tahoma, Geneva, Verdana, sans-serif; margin: 40px; background-color: #f5f5f5; }}
-        .container {{ max-width: 800px; margin: 0 auto; background: white; padding: 30px; border-radius: 10px; box-shadow: 0 2px 10px rgba(0,0,0,0.1); }}
-        h1 {{ color: #333; text-align: center; margin-bottom: 30px; border-bottom: 3px solid #007acc; padding-bottom: 10px; }}
-        .summary {{ background: #f8f9fa; padding: 20px; border-radius: 8px; margin-bottom: 30px; border-left: 4px solid #007acc; }}
-        .summary h2 {{ margin-top: 0; color: #495057; }}
-        .stat {{ display: inline-block; margin-right: 30px; }}
-        .stat-value {{ font-size: 24px; font-weight: bold; color: #007acc; }}
-        .stat-label {{ font-size: 14px; color: #6c757d; }}
-        .keywords-table {{ width: 100%; border-collapse: collapse; margin-top: 20px; }}
-        .keywords-table th {{ background: #007acc; color: white; padding: 12px; text-align: left; font-weight: 600; }}
-        .keywords-table td {{ padding: 10px 12px; border-bottom: 1px solid #dee2e6; }}
-        .keywords-table tr:nth-child(even) {{ background-color: #f8f9fa; }}
-        .keywords-table tr:hover {{ background-color: #e3f2fd; }}
-        .keyword {{ font-family: 'Consolas', 'Monaco', monospace; font-weight: 600; color: #495057; }}
-        .count {{ font-weight: bold; color: #007acc; }}
-        .progress-bar {{ width: 100%; height: 8px; background: #e9ecef; border-radius: 4px; overflow: hidden; }}
-        .progress-fill {{ height: 100%; background: linear-gradient(90deg, #007acc, #40a9ff); transition: width 0.3s ease; }}
-        .footer {{ text-align: center; margin-top: 30px; color: #6c757d; font-size: 12px; }}
-    </style>
-</head>
-<body>
-    <div class="container">
-        <h1>{language_name} Keyword Analysis Results</h1>
-        <div class="summary">
-            <h2>Analysis Summary</h2>
-            <div class="stat">
-                <div class="stat-value">{file_count}</div>
-                <div class="stat-label">Files Analyzed</div>
-            </div>
-            <div class="stat">
-                <div class="stat-value">{total_keywords}</div>
-                <div class="stat-label">Total Keywords Found</div>
-            </div>
-        </div>"#
-  );
-
-  write!(file, "{html_content}")?;
-
-  if !sorted_counts.is_empty() && total_keywords > 0 {
-    let max_count = sorted_counts
-      .iter()
-      .map(|(_, count)| **count)
-      .max()
-      .unwrap_or(1);
-
-    writeln!(
-      file,
-      r#"        <table class="keywords-table">
-            <thead>
-                <tr>
-                    <th>Keyword</th>
-                    <th>Count</th>
-                    <th>Distribution</th>
-                </tr>
-            </thead>
-            <tbody>"#
-    )?;
-
-    for (keyword, count) in sorted_counts {
-      if **count > 0 {
-        let percentage = ((**count as f64) / (max_count as f64) * 100.0) as u32;
-        writeln!(
-          file,
-          r#"                <tr>
-                    <td class="keyword">{keyword}</td>
-                    <td class="count">{count}</td>
-                    <td>
-                        <div class="progress-bar">
-                            <div class="progress-fill" style="width: {percentage}%;"></div>
-                        </div>
-                    </td>
-                </tr>"#
-        )?;
+  #[test]
+  fn test_parallel_analysis_is_deterministic() {
+    let root = TestDir::new(&[]);
+    for dir in ["b", "a/c", "a"] {
+      for i in 0..5 {
+        let source = "fn f() { if x { loop {} } }\n".repeat(i + 1);
+        root.write(&format!("{dir}/f{i}.rs"), source);
       }
     }
 
-    writeln!(
-      file,
-      r#"            </tbody>
-        </table>"#
-    )?;
-  } else {
-    writeln!(
-      file,
-      r#"        <p style="text-align: center; color: #6c757d; font-style: italic;">No keywords found in the analyzed files.</p>"#
-    )?;
-  }
-
-  writeln!(
-    file,
-    r#"        <div class="footer">
-            <p>Generated by Multi-Language Keyword Analyzer</p>
-        </div>
-    </div>
-</body>
-</html>"#
-  )?;
-
-  println!("HTML results written to: {file_path}");
-  Ok(())
-}
-
-fn write_graph_to_file(
-  sorted_counts: &[(&String, &usize)],
-  file_count: usize,
-  language: Language,
-  file_path: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-  let mut file = fs::File::create(file_path)?;
-  let language_name = match language {
-    Language::Rust => "Rust",
-    Language::JavaScript => "JavaScript/TypeScript",
-    Language::Ruby => "Ruby",
-    Language::Golang => "Go",
-    Language::Python => "Python",
-    Language::Dart => "Dart",
-  };
-
-  let total_keywords: usize = sorted_counts.iter().map(|(_, count)| **count).sum();
-  let top_keywords: Vec<_> = sorted_counts
-    .iter()
-    .filter(|(_, count)| **count > 0)
-    .take(20)
-    .collect();
-
-  if top_keywords.is_empty() {
-    return Err("No keywords found to visualize".into());
-  }
-
-  let max_count = top_keywords
-    .iter()
-    .map(|(_, count)| **count)
-    .max()
-    .unwrap_or(1);
-  let width = 1000;
-  let height = 600;
-  let margin = 60;
-  let chart_height = height - 2 * margin - 50;
-  let bar_width = (width - 2 * margin) / top_keywords.len().max(1);
-  let colors = generate_color_palette(top_keywords.len());
-
-  writeln!(file, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
-  writeln!(
-    file,
-    "<svg width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\" xmlns=\"http://www.w3.org/2000/svg\">"
-  )?;
-
-  writeln!(
-    file,
-    r#"    <defs>
-        <style>
-            .title {{ font-family: 'Arial', sans-serif; font-size: 24px; font-weight: bold; text-anchor: middle; fill: #333; }}
-            .subtitle {{ font-family: 'Arial', sans-serif; font-size: 14px; text-anchor: middle; fill: #666; }}
-            .bar {{ cursor: pointer; transition: opacity 0.2s; }}
-            .bar:hover {{ opacity: 0.8; }}
-            .bar-label {{ font-family: 'Arial', sans-serif; font-size: 12px; text-anchor: middle; fill: #333; }}
-            .count-label {{ font-family: 'Arial', sans-serif; font-size: 11px; text-anchor: middle; fill: #fff; font-weight: bold; }}
-            .axis {{ stroke: #ccc; stroke-width: 1; }}
-            .grid {{ stroke: #eee; stroke-width: 0.5; }}
-        </style>
-    </defs>"#
-  )?;
-
-  writeln!(
-    file,
-    "    <rect width=\"100%\" height=\"100%\" fill=\"#fafafa\"/>"
-  )?;
-  writeln!(
-    file,
-    "    <text x=\"{}\" y=\"30\" class=\"title\">{} Keyword Analysis</text>",
-    width / 2,
-    language_name
-  )?;
-  writeln!(file, "    <text x=\"{}\" y=\"50\" class=\"subtitle\">Files: {} | Total Keywords: {} | Top {} Keywords</text>", width / 2, file_count, total_keywords, top_keywords.len())?;
-
-  let grid_lines = generate_grid_lines(margin, margin + 30, chart_height);
-  write!(file, "{grid_lines}")?;
-
-  for (i, (keyword, count)) in top_keywords.iter().enumerate() {
-    let x = margin + i * bar_width;
-    let bar_height = ((**count as f64) / (max_count as f64) * chart_height as f64) as usize;
-    let y = margin + 30 + chart_height - bar_height;
-    let color = &colors[i % colors.len()];
-
-    writeln!(
-      file,
-      "    <rect class=\"bar\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\">",
-      x + 2,
-      y,
-      bar_width - 4,
-      bar_height,
-      color
-    )?;
-    writeln!(file, "        <title>{keyword}: {count}</title>")?;
-    writeln!(file, "    </rect>")?;
-
-    if bar_height > 25 {
-      writeln!(
-        file,
-        "    <text x=\"{}\" y=\"{}\" class=\"count-label\">{}</text>",
-        x + bar_width / 2,
-        y + 15,
-        count
-      )?;
-    }
-
-    let label_y = margin + 30 + chart_height + 15;
-    let truncated_keyword = if keyword.len() > 8 {
-      format!("{}...", &keyword[..5])
-    } else {
-      keyword.to_string()
+    let path = root.as_str();
+    let analyze = |jobs| {
+      let options = AnalysisOptions {
+        jobs,
+        ..AnalysisOptions::default()
+      };
+      KeywordAnalyzer::analyze_path_with_options(path, Language::Rust.spec().as_ref(), &options)
+        .unwrap()
     };
-
-    writeln!(
-      file,
-      "    <text x=\"{}\" y=\"{}\" class=\"bar-label\" transform=\"rotate(-45, {}, {})\">{}</text>",
-      x + bar_width / 2,
-      label_y,
-      x + bar_width / 2,
-      label_y,
-      truncated_keyword
-    )?;
-  }
-
-  writeln!(
-    file,
-    "    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" class=\"axis\"/>",
-    margin,
-    margin + 30,
-    margin,
-    margin + 30 + chart_height
-  )?;
-  writeln!(
-    file,
-    "    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" class=\"axis\"/>",
-    margin,
-    margin + 30 + chart_height,
-    margin + (width - 2 * margin),
-    margin + 30 + chart_height
-  )?;
-
-  let y_labels = generate_y_axis_labels(margin - 10, margin + 30, chart_height, max_count);
-  write!(file, "{y_labels}")?;
-  writeln!(file, "</svg>")?;
-
-  println!("Graph results written to: {file_path}");
-  Ok(())
-}
-
-fn generate_color_palette(count: usize) -> Vec<String> {
-  let base_colors = vec![
-    "#3498db", "#e74c3c", "#2ecc71", "#f39c12", "#9b59b6", "#1abc9c", "#34495e", "#e67e22",
-    "#95a5a6", "#f1c40f", "#c0392b", "#27ae60", "#8e44ad", "#16a085", "#2c3e50", "#d35400",
-    "#7f8c8d", "#f4d03f", "#85c1e9", "#f8c471",
-  ];
-
-  (0..count)
-    .map(|i| base_colors[i % base_colors.len()].to_string())
-    .collect()
-}
-
-fn generate_grid_lines(x_start: usize, y_start: usize, height: usize) -> String {
-  let mut lines = String::new();
-  let grid_count = 5;
-
-  for i in 0..=grid_count {
-    let y = y_start + (height * i) / grid_count;
-    lines.push_str(&format!(
-      "    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" class=\"grid\"/>\n",
-      x_start,
-      y,
-      x_start + 880,
-      y
-    ));
+    let sequential = analyze(1);
+    let parallel = analyze(4);
+
+    assert_eq!(sequential.file_count, 15);
+    assert_eq!(sequential.files_analyzed[..2], ["a/c/f0.rs", "a/c/f1.rs"]);
+    assert_eq!(sequential.files_analyzed.last().unwrap(), "b/f4.rs");
+    assert_eq!(parallel.files_analyzed, sequential.files_analyzed);
+    assert_eq!(parallel.keyword_counts, sequential.keyword_counts);
+    assert_eq!(parallel.total_keywords, 3 * 45);
+    assert_eq!(
+      output::generate_json_content(&parallel),
+      output::generate_json_content(&sequential)
+    );
   }
 
-  lines
-}
-
-fn generate_y_axis_labels(x: usize, y_start: usize, height: usize, max_value: usize) -> String {
-  let mut labels = String::new();
-  let label_count = 5;
+  #[test]
+  fn test_analyze_directory_missing_path() {
+    let root = TestDir::new(&[("main.rs", "fn main() { if x {} }\n")]);
+    let spec = rust::spec();
+    let mut counts = HashMap::new();
+    let mut file_count = 0;
 
-  for i in 0..=label_count {
-    let y = y_start + height - (height * i) / label_count;
-    let value = (max_value * i) / label_count;
-    labels.push_str(&format!(
-      "    <text x=\"{}\" y=\"{}\" class=\"bar-label\" text-anchor=\"end\">{}</text>\n",
-      x,
-      y + 4,
-      value
-    ));
+    let missing = analyze_directory(
+      &root.join("missing"),
+      spec.as_ref(),
+      &mut counts,
+      &mut file_count,
+    );
+    assert!(matches!(missing, Err(AnalyzerError::PathNotFound(_))));
+
+    analyze_directory(
+      &root.join("main.rs"),
+      spec.as_ref(),
+      &mut counts,
+      &mut file_count,
+    )
+    .unwrap();
+    assert_eq!(file_count, 1);
+    assert_eq!(counts.get("if"), Some(&1));
   }
 
-  labels
-}
-
-pub fn generate_json_content(sorted_counts: &[(&String, &usize)], file_count: usize) -> String {
-  let total_keywords = sorted_counts
-    .iter()
-    .map(|(_, count)| **count)
-    .sum::<usize>();
-
-  let mut json = String::new();
-  json.push_str("{\n");
-  json.push_str(&format!("  \"files_analyzed\": {file_count},\n"));
-  json.push_str(&format!("  \"total_keywords\": {total_keywords},\n"));
-  json.push_str("  \"keywords\": {\n");
+  #[test]
+  fn test_unreadable_files_are_skipped() {
+    let root = TestDir::new(&[("main.rs", "fn main() {}\n")]);
+    root.write("latin1.rs", b"// caf\xE9\nfn f() {}\n");
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(root.join("missing.rs"), root.join("broken.rs")).unwrap();
+
+    let path = root.as_str();
+    let spec = Language::Rust.spec();
+    let strict = KeywordAnalyzer::analyze_path_with_spec(path, spec.as_ref());
+    let detect = KeywordAnalyzer::analyze_path_with_options(
+      path,
+      spec.as_ref(),
+      &AnalysisOptions {
+        encoding: Encoding::Detect,
+        ..AnalysisOptions::default()
+      },
+    );
+    let repository = KeywordAnalyzer::analyze_repository(path);
+
+    let strict = strict.unwrap();
+    assert_eq!(strict.file_count, 1);
+    assert!(strict.skipped.contains(&SkippedFile {
+      path: "latin1.rs".to_string(),
+      reason: "not valid UTF-8".to_string(),
+    }));
+    #[cfg(unix)]
+    assert!(strict
+      .skipped
+      .iter()
+      .any(|file| file.path == "broken.rs" && file.reason.starts_with("not found")));
 
-  let mut first = true;
-  for (keyword, count) in sorted_counts {
-    if **count > 0 {
-      if !first {
-        json.push_str(",\n");
-      }
-      json.push_str(&format!("    \"{keyword}\": {count}"));
-      first = false;
-    }
-  }
+    let detect = detect.unwrap();
+    assert_eq!(detect.file_count, 2);
+    assert_eq!(detect.keyword_counts.get("fn"), Some(&2));
+    assert!(detect.skipped.iter().all(|file| file.path != "latin1.rs"));
 
-  if !first {
-    json.push('\n');
+    let repository = repository.unwrap();
+    assert_eq!(repository.file_count, 1);
+    assert!(repository
+      .skipped
+      .iter()
+      .any(|file| file.path == "latin1.rs"));
   }
-  json.push_str("  }\n");
-  json.push('}');
-
-  json
-}
-
-pub fn generate_html_content(
-  sorted_counts: &[(&String, &usize)],
-  file_count: usize,
-  language: Language,
-) -> String {
-  let language_name = match language {
-    Language::Rust => "Rust",
-    Language::JavaScript => "JavaScript/TypeScript",
-    Language::Ruby => "Ruby",
-    Language::Golang => "Go",
-    Language::Python => "Python",
-    Language::Dart => "Dart",
-  };
 
-  let total_keywords: usize = sorted_counts.iter().map(|(_, count)| **count).sum();
-
-  let mut html = format!(
-    r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>{language_name} Keyword Analysis Results</title>
-    <style>
-        body {{ font-family: 'Segoe UI', Tahoma, Geneva, Verdana, sans-serif; margin: 40px; background-color: #f5f5f5; }}
-        .container {{ max-width: 800px; margin: 0 auto; background: white; padding: 30px; border-radius: 10px; box-shadow: 0 2px 10px rgba(0,0,0,0.1); }}
-        h1 {{ color: #333; text-align: center; margin-bottom: 30px; border-bottom: 3px solid #007acc; padding-bottom: 10px; }}
-        .summary {{ background: #f8f9fa; padding: 20px; border-radius: 8px; margin-bottom: 30px; border-left: 4px solid #007acc; }}
-        .summary h2 {{ margin-top: 0; color: #495057; }}
-        .stat {{ display: inline-block; margin-right: 30px; }}
-        .stat-value {{ font-size: 24px; font-weight: bold; color: #007acc; }}
-        .stat-label {{ font-size: 14px; color: #6c757d; }}
-        .keywords-table {{ width: 100%; border-collapse: collapse; margin-top: 20px; }}
-        .keywords-table th {{ background: #007acc; color: white; padding: 12px; text-align: left; font-weight: 600; }}
-        .keywords-table td {{ padding: 10px 12px; border-bottom: 1px solid #dee2e6; }}
-        .keywords-table tr:nth-child(even) {{ background-color: #f8f9fa; }}
-        .keywords-table tr:hover {{ background-color: #e3f2fd; }}
-        .keyword {{ font-family: 'Consolas', 'Monaco', monospace; font-weight: 600; color: #495057; }}
-        .count {{ font-weight: bold; color: #007acc; }}
-        .progress-bar {{ width: 100%; height: 8px; background: #e9ecef; border-radius: 4px; overflow: hidden; }}
-        .progress-fill {{ height: 100%; background: linear-gradient(90deg, #007acc, #40a9ff); transition: width 0.3s ease; }}
-        .footer {{ text-align: center; margin-top: 30px; color: #6c757d; font-size: 12px; }}
-    </style>
-</head>
-<body>
-    <div class="container">
-        <h1>{language_name} Keyword Analysis Results</h1>
-        <div class="summary">
-            <h2>Analysis Summary</h2>
-            <div class="stat">
-                <div class="stat-value">{file_count}</div>
-                <div class="stat-label">Files Analyzed</div>
-            </div>
-            <div class="stat">
-                <div class="stat-value">{total_keywords}</div>
-                <div class="stat-label">Total Keywords Found</div>
-            </div>
-        </div>"#
-  );
-
-  if !sorted_counts.is_empty() && total_keywords > 0 {
-    let max_count = sorted_counts
-      .iter()
-      .map(|(_, count)| **count)
-      .max()
-      .unwrap_or(1);
-
-    html.push_str(
-      r#"        <table class="keywords-table">
-            <thead>
-                <tr>
-                    <th>Keyword</th>
-                    <th>Count</th>
-                    <th>Distribution</th>
-                </tr>
-            </thead>
-            <tbody>"#,
+  #[test]
+  fn test_find_occurrences() {
+    let root = TestDir::new(&[
+      ("src/lib.rs", "// unsafe\nfn a() { unsafe { b() } }\n"),
+      ("src/util.py", "def f():\n    return \"def\"\n"),
+      ("main.rs", "fn main() {}\n"),
+    ]);
+    root.write("latin1.rs", b"// caf\xE9\nfn f() {}\n");
+
+    let dir = root.as_str();
+    let rust = Language::Rust.spec();
+    let default = AnalysisOptions::default();
+    let unsafe_uses =
+      KeywordAnalyzer::find_occurrences(dir, "unsafe", Some(rust.as_ref()), 0, &default);
+    let fn_uses = KeywordAnalyzer::find_occurrences(dir, "fn", None, 0, &default);
+    let def_uses = KeywordAnalyzer::find_occurrences(dir, "def", None, 0, &default);
+    let single = KeywordAnalyzer::find_occurrences(
+      root.join("main.rs").to_str().unwrap(),
+      "fn",
+      Some(rust.as_ref()),
+      0,
+      &default,
+    );
+    let not_keyword =
+      KeywordAnalyzer::find_occurrences(dir, "def", Some(rust.as_ref()), 0, &default);
+    let unknown = KeywordAnalyzer::find_occurrences(dir, "frobnicate", None, 0, &default);
+    let excluded = KeywordAnalyzer::find_occurrences(
+      dir,
+      "fn",
+      None,
+      0,
+      &AnalysisOptions {
+        filter: FileFilter {
+          exclude: vec![Glob::new("src/")],
+          ..FileFilter::default()
+        },
+        ..AnalysisOptions::default()
+      },
     );
 
-    for (keyword, count) in sorted_counts {
-      if **count > 0 {
-        let percentage = ((**count as f64) / (max_count as f64) * 100.0) as u32;
-        html.push_str(&format!(
-          r#"                <tr>
-                    <td class="keyword">{keyword}</td>
-                    <td class="count">{count}</td>
-                    <td>
-                        <div class="progress-bar">
-                            <div class="progress-fill" style="width: {percentage}%;"></div>
-                        </div>
-                    </td>
-                </tr>"#
-        ));
-      }
-    }
-
-    html.push_str(
-      r#"            </tbody>
-        </table>"#,
+    let locations = |found: Vec<Occurrence>| -> Vec<(String, usize, usize)> {
+      found
+        .into_iter()
+        .map(|o| (o.path, o.line, o.column))
+        .collect()
+    };
+    assert_eq!(
+      locations(unsafe_uses.unwrap()),
+      [("src/lib.rs".to_string(), 2, 10)]
     );
-  } else {
-    html.push_str(
-      r#"        <p style="text-align: center; color: #6c757d; font-style: italic;">No keywords found in the analyzed files.</p>"#,
+    assert_eq!(
+      locations(fn_uses.unwrap()),
+      [
+        ("main.rs".to_string(), 1, 1),
+        ("src/lib.rs".to_string(), 2, 1)
+      ]
+    );
+    assert_eq!(
+      locations(def_uses.unwrap()),
+      [("src/util.py".to_string(), 1, 1)]
+    );
+    assert_eq!(single.unwrap().len(), 1);
+    assert_eq!(
+      locations(excluded.unwrap()),
+      [("main.rs".to_string(), 1, 1)]
     );
+    assert!(not_keyword
+      .unwrap_err()
+      .to_string()
+      .contains("not a Rust keyword"));
+    assert!(unknown.is_err());
   }
 
-  html.push_str(
-    r#"        <div class="footer">
-            <p>Generated by Multi-Language Keyword Analyzer</p>
-        </div>
-    </div>
-</body>
-</html>"#,
-  );
+  #[test]
+  fn test_analyze_identifiers_skips_unreadable_files() {
+    let root = TestDir::new(&[
+      ("main.rs", "fn main() { let total_count = 1; }\n"),
+      ("app.py", "import os\n"),
+    ]);
+    root.write("latin1.rs", b"// caf\xE9\nfn f() {}\n");
+
+    let path = root.as_str();
+    let rust = Language::Rust.spec();
+    let options = AnalysisOptions::default();
+    let rust_only = KeywordAnalyzer::analyze_identifiers(path, Some(rust.as_ref()), None, &options);
+    let all = KeywordAnalyzer::analyze_identifiers(path, None, None, &options);
+
+    let rust_only = rust_only.unwrap();
+    assert_eq!(rust_only.len(), 1);
+    let files: Vec<_> = rust_only[0]
+      .files
+      .iter()
+      .map(|file| file.path.as_str())
+      .collect();
+    assert_eq!(files, ["main.rs"]);
+    assert_eq!(rust_only[0].counts.get("total_count"), Some(&1));
+    let languages: Vec<_> = all
+      .unwrap()
+      .into_iter()
+      .map(|report| report.language)
+      .collect();
+    assert_eq!(languages, ["python", "rust"]);
+  }
 
-  html
+  #[test]
+  fn test_audit_unsafe_skips_unreadable_files() {
+    let root = TestDir::new(&[
+      ("Cargo.toml", "[package]\nname = \"demo\"\n"),
+      (
+        "src/lib.rs",
+        "fn f() {\n  // SAFETY: checked\n  unsafe { g() }\n}\n",
+      ),
+    ]);
+    root.write("src/latin1.rs", b"// caf\xE9\nunsafe fn h() {}\n");
+
+    let audit = KeywordAnalyzer::audit_unsafe(root.as_str(), &AnalysisOptions::default());
+
+    let audit = audit.unwrap();
+    let locations: Vec<_> = audit
+      .uses
+      .iter()
+      .map(|found| (found.path.as_str(), found.line))
+      .collect();
+    assert_eq!(locations, [("src/lib.rs", 3)]);
+    assert_eq!(audit.uses[0].package.as_deref(), Some("demo"));
+    assert_eq!(audit.crates.len(), 1);
+  }
+
+  #[test]
+  fn test_clone_dirs_are_unique() {
+    let dirs: Vec<_> = (0..4).map(|_| thread::spawn(clone_dir)).collect();
+    let mut dirs: Vec<_> = dirs.into_iter().map(|dir| dir.join().unwrap()).collect();
+    dirs.sort();
+    dirs.dedup();
+    assert_eq!(dirs.len(), 4);
+  }
 }
@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+
+use crate::lexer::{Token, TokenKind};
+
+/// Physical lines of source split by what they hold, like `cloc` does. A
+/// line with both code and a comment counts as code and is also counted in
+/// `mixed`; lines inside a multi-line string count as code.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineCounts {
+  pub total: usize,
+  pub code: usize,
+  pub comment: usize,
+  pub blank: usize,
+  /// Code lines that also hold a comment.
+  #[serde(default)]
+  pub mixed: usize,
+}
+
+impl LineCounts {
+  /// Classifies the lines of `content` using the tokens it was lexed into.
+  pub fn from_tokens(content: &str, tokens: &[Token]) -> Self {
+    let line_starts: Vec<usize> = std::iter::once(0)
+      .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+      .collect();
+    let line_of = |offset: usize| line_starts.partition_point(|&start| start <= offset) - 1;
+
+    let total = content.lines().count();
+    let mut has_code = vec![false; total];
+    let mut has_comment = vec![false; total];
+    for token in tokens {
+      let text = token.text.trim_end();
+      let first = line_of(token.offset);
+      let last = line_of(token.offset + text.len().saturating_sub(1));
+      let lines = if token.kind == TokenKind::Comment {
+        &mut has_comment
+      } else {
+        &mut has_code
+      };
+      for line in lines.iter_mut().take(last + 1).skip(first) {
+        *line = true;
+      }
+    }
+
+    let code = has_code.iter().filter(|code| **code).count();
+    let comment = (0..total)
+      .filter(|&line| has_comment[line] && !has_code[line])
+      .count();
+    let mixed = (0..total)
+      .filter(|&line| has_comment[line] && has_code[line])
+      .count();
+    Self {
+      total,
+      code,
+      comment,
+      blank: total - code - comment,
+      mixed,
+    }
+  }
+
+  /// Comment lines per line of code; zero when there is no code.
+  pub fn comment_ratio(&self) -> f64 {
+    if self.code == 0 {
+      0.0
+    } else {
+      self.comment as f64 / self.code as f64
+    }
+  }
+
+  pub fn add(&mut self, other: &LineCounts) {
+    self.total += other.total;
+    self.code += other.code;
+    self.comment += other.comment;
+    self.blank += other.blank;
+    self.mixed += other.mixed;
+  }
+}
+
+/// Keywords per 1000 lines of code; zero when there is no code.
+pub fn per_kloc(keywords: usize, code_lines: usize) -> f64 {
+  if code_lines == 0 {
+    0.0
+  } else {
+    keywords as f64 * 1000.0 / code_lines as f64
+  }
+}
+
+/// Summary statistics of a set of per-file values. Percentiles interpolate
+/// linearly between the closest ranks; every field is zero for no values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Distribution {
+  pub mean: f64,
+  pub median: f64,
+  pub p75: f64,
+  pub p90: f64,
+  pub p95: f64,
+  pub max: f64,
+}
+
+impl Distribution {
+  pub fn from_values(mut values: Vec<f64>) -> Self {
+    if values.is_empty() {
+      return Self::default();
+    }
+
+    values.sort_by(f64::total_cmp);
+    Self {
+      mean: values.iter().sum::<f64>() / values.len() as f64,
+      median: percentile(&values, 50.0),
+      p75: percentile(&values, 75.0),
+      p90: percentile(&values, 90.0),
+      p95: percentile(&values, 95.0),
+      max: values[values.len() - 1],
+    }
+  }
+}
+
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+  let rank = p / 100.0 * (sorted.len() - 1) as f64;
+  let lower = rank.floor() as usize;
+  let upper = rank.ceil() as usize;
+  sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Size-normalized keyword statistics, comparable between code bases of
+/// different sizes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Density {
+  /// Keywords per 1000 lines of code over all files.
+  pub keywords_per_kloc: f64,
+  /// Keywords found in each file.
+  pub keywords_per_file: Distribution,
+  /// Keywords per 1000 lines of code of each file with code.
+  pub file_keywords_per_kloc: Distribution,
+}
+
+impl Density {
+  /// Statistics for files given as `(keywords, code lines)` pairs.
+  pub fn from_files(files: impl IntoIterator<Item = (usize, usize)>) -> Self {
+    let files: Vec<(usize, usize)> = files.into_iter().collect();
+    let keywords: usize = files.iter().map(|(keywords, _)| keywords).sum();
+    let code: usize = files.iter().map(|(_, code)| code).sum();
+
+    Self {
+      keywords_per_kloc: per_kloc(keywords, code),
+      keywords_per_file: Distribution::from_values(
+        files.iter().map(|(keywords, _)| *keywords as f64).collect(),
+      ),
+      file_keywords_per_kloc: Distribution::from_values(
+        files
+          .iter()
+          .filter(|(_, code)| *code > 0)
+          .map(|(keywords, code)| per_kloc(*keywords, *code))
+          .collect(),
+      ),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Language;
+
+  #[test]
+  fn test_line_counts() {
+    let source = "\
+// header
+fn main() {
+
+    let s = \"a
+b\"; // trailing
+    /* block
+       comment */
+}
+";
+    let tokens = Language::Rust.spec().tokenize(source);
+    assert_eq!(
+      LineCounts::from_tokens(source, &tokens),
+      LineCounts {
+        total: 8,
+        code: 4,
+        comment: 3,
+        blank: 1,
+        mixed: 1,
+      }
+    );
+    assert_eq!(LineCounts::from_tokens("", &[]), LineCounts::default());
+  }
+
+  #[test]
+  fn test_density() {
+    let density = Density::from_files([(10, 100), (30, 100), (0, 0), (20, 200)]);
+    assert_eq!(density.keywords_per_kloc, 150.0);
+    assert_eq!(density.keywords_per_file.mean, 15.0);
+    assert_eq!(density.keywords_per_file.median, 15.0);
+    assert_eq!(density.keywords_per_file.max, 30.0);
+    assert_eq!(density.keywords_per_file.p75, 22.5);
+    // The file without code has no density of its own
+    assert_eq!(density.file_keywords_per_kloc.median, 100.0);
+    assert_eq!(density.file_keywords_per_kloc.max, 300.0);
+    assert_eq!(Density::from_files([]), Density::default());
+  }
+}
@@ -0,0 +1,160 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::lexer::TokenKind;
+use crate::spec::LanguageSpec;
+
+/// Where a keyword was used. Like the counts, words inside comments and
+/// strings are not occurrences.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Occurrence {
+  /// Path relative to the analyzed directory.
+  pub path: String,
+  /// 1-based line number.
+  pub line: usize,
+  /// 1-based column, in characters.
+  pub column: usize,
+  pub keyword: String,
+  /// The line holding the keyword, without trailing whitespace.
+  pub snippet: String,
+  /// Up to `context` lines before and after the snippet.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub before: Vec<String>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub after: Vec<String>,
+}
+
+/// Every use of `keyword` as a keyword in `content`, in source order.
+pub fn find_in_content(
+  spec: &dyn LanguageSpec,
+  path: &str,
+  content: &str,
+  keyword: &str,
+  context: usize,
+) -> Vec<Occurrence> {
+  let lines: Vec<&str> = content.lines().collect();
+  let line_starts: Vec<usize> = std::iter::once(0)
+    .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+    .collect();
+  let snippet = |line: usize| lines[line].trim_end().to_string();
+  let tokens = spec.tokenize(content);
+
+  tokens
+    .iter()
+    .enumerate()
+    .filter(|(index, token)| {
+      token.kind == TokenKind::Word
+        && token.text == keyword
+        && spec.is_keyword_token(content, &tokens, *index)
+    })
+    .map(|(_, token)| {
+      let line = line_starts.partition_point(|&start| start <= token.offset) - 1;
+      Occurrence {
+        path: path.to_string(),
+        line: line + 1,
+        column: content[line_starts[line]..token.offset].chars().count() + 1,
+        keyword: keyword.to_string(),
+        snippet: snippet(line),
+        before: (line.saturating_sub(context)..line).map(snippet).collect(),
+        after: (line + 1..(line + 1 + context).min(lines.len()))
+          .map(snippet)
+          .collect(),
+      }
+    })
+    .collect()
+}
+
+/// `path:line:column: snippet` lines, like `grep -n`, with context lines
+/// marked by `-` and groups separated by `--`.
+pub fn format_occurrences(occurrences: &[Occurrence]) -> String {
+  let mut output = String::new();
+  for (index, occurrence) in occurrences.iter().enumerate() {
+    let has_context = !occurrence.before.is_empty() || !occurrence.after.is_empty();
+    if has_context && index > 0 {
+      output.push_str("--\n");
+    }
+
+    let first = occurrence.line - occurrence.before.len();
+    for (offset, line) in occurrence.before.iter().enumerate() {
+      output.push_str(&format!("{}-{}-{line}\n", occurrence.path, first + offset));
+    }
+    output.push_str(&format!(
+      "{}:{}:{}: {}\n",
+      occurrence.path, occurrence.line, occurrence.column, occurrence.snippet
+    ));
+    for (offset, line) in occurrence.after.iter().enumerate() {
+      output.push_str(&format!(
+        "{}-{}-{line}\n",
+        occurrence.path,
+        occurrence.line + 1 + offset
+      ));
+    }
+  }
+  output
+}
+
+/// A JSON report with the keyword, the number of occurrences and of files
+/// holding them, and the occurrences themselves.
+pub fn generate_occurrences_json(keyword: &str, occurrences: &[Occurrence]) -> String {
+  let files: HashSet<&str> = occurrences
+    .iter()
+    .map(|occurrence| occurrence.path.as_str())
+    .collect();
+  let report = json!({
+    "keyword": keyword,
+    "count": occurrences.len(),
+    "files": files.len(),
+    "occurrences": occurrences,
+  });
+  serde_json::to_string_pretty(&report).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Language;
+
+  #[test]
+  fn test_find_in_content() {
+    let source = "\
+// unsafe in a comment
+fn main() {
+    let s = \"unsafe\";
+    unsafe { ptr(); } let é = unsafe { 1 };
+}
+";
+    let spec = Language::Rust.spec();
+    let found = find_in_content(spec.as_ref(), "src/main.rs", source, "unsafe", 1);
+    let locations: Vec<_> = found.iter().map(|o| (o.line, o.column)).collect();
+    assert_eq!(locations, [(4, 5), (4, 31)]);
+    assert_eq!(
+      found[0].snippet,
+      "    unsafe { ptr(); } let é = unsafe { 1 };"
+    );
+    assert_eq!(found[0].before, ["    let s = \"unsafe\";"]);
+    assert_eq!(found[0].after, ["}"]);
+
+    let output = format_occurrences(&found[..1]);
+    assert_eq!(
+      output,
+      "src/main.rs-3-    let s = \"unsafe\";\n\
+       src/main.rs:4:5:     unsafe { ptr(); } let é = unsafe { 1 };\n\
+       src/main.rs-5-}\n"
+    );
+
+    // Soft keywords only count where they act as keywords
+    let python = Language::Python.spec();
+    let found = find_in_content(python.as_ref(), "a.py", "match = 1\nmatch x:\n", "match", 0);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].line, 2);
+    assert!(format_occurrences(&found).starts_with("a.py:2:1: match x:"));
+
+    let json: serde_json::Value =
+      serde_json::from_str(&generate_occurrences_json("match", &found)).unwrap();
+    assert_eq!(json["count"], 1);
+    assert_eq!(json["occurrences"][0]["column"], 1);
+    assert!(json["occurrences"][0].get("before").is_none());
+  }
+}
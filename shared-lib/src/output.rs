@@ -0,0 +1,2582 @@
+use std::collections::HashMap;
+use std::fs;
+
+use serde_json::{json, Map, Value};
+
+use crate::comments::{DocCoverage, Marker, MARKERS};
+use crate::error::AnalyzerError;
+use crate::metrics::{Density, Distribution, LineCounts};
+use crate::sequences::CooccurrenceMatrix;
+use crate::tree::{package_for, DirectoryNode};
+use crate::{
+  AnalysisResult, CategoryTotal, FileAnalysis, OutputFormat, RepositoryAnalysis, SkippedFile,
+};
+
+/// How many files the plain summary lists.
+const TOP_FILES: usize = 10;
+/// Keyword sequences listed in the plain, JSON and HTML reports.
+const TOP_SEQUENCES: usize = 10;
+/// Rows and columns of the keyword co-occurrence matrix.
+const MATRIX_SIZE: usize = 10;
+
+const HTML_STYLE: &str = r#"        body { font-family: 'Segoe UI', Tahoma, Geneva, Verdana, sans-serif; margin: 40px; background-color: #f5f5f5; }
+        .container { max-width: 800px; margin: 0 auto; background: white; padding: 30px; border-radius: 10px; box-shadow: 0 2px 10px rgba(0,0,0,0.1); }
+        h1 { color: #333; text-align: center; margin-bottom: 30px; border-bottom: 3px solid #007acc; padding-bottom: 10px; }
+        h2.language { color: #333; margin-top: 40px; border-bottom: 2px solid #dee2e6; padding-bottom: 6px; }
+        .summary { background: #f8f9fa; padding: 20px; border-radius: 8px; margin-bottom: 30px; border-left: 4px solid #007acc; }
+        .summary h2 { margin-top: 0; color: #495057; }
+        .stat { display: inline-block; margin-right: 30px; }
+        .stat-value { font-size: 24px; font-weight: bold; color: #007acc; }
+        .stat-label { font-size: 14px; color: #6c757d; }
+        .keywords-table { width: 100%; border-collapse: collapse; margin-top: 20px; }
+        .keywords-table th { background: #007acc; color: white; padding: 12px; text-align: left; font-weight: 600; }
+        .keywords-table td { padding: 10px 12px; border-bottom: 1px solid #dee2e6; }
+        .keywords-table tr:nth-child(even) { background-color: #f8f9fa; }
+        .keywords-table tr:hover { background-color: #e3f2fd; }
+        .keyword { font-family: 'Consolas', 'Monaco', monospace; font-weight: 600; color: #495057; }
+        .count { font-weight: bold; color: #007acc; }
+        .progress-bar { width: 100%; height: 8px; background: #e9ecef; border-radius: 4px; overflow: hidden; }
+        .progress-fill { height: 100%; background: linear-gradient(90deg, #007acc, #40a9ff); transition: width 0.3s ease; }
+        .tree details { margin-left: 20px; }
+        .tree > details { margin-left: 0; }
+        .tree summary { cursor: pointer; padding: 4px 0; }
+        .tree-leaf { margin-left: 20px; padding: 4px 0 4px 16px; }
+        .matrix td { text-align: center; padding: 6px; }
+        .matrix tbody th { background: #f8f9fa; color: #495057; font-family: 'Consolas', 'Monaco', monospace; }
+        .package { font-size: 12px; color: white; background: #6c757d; border-radius: 4px; padding: 1px 6px; margin-left: 6px; }
+        .footer { text-align: center; margin-top: 30px; color: #6c757d; font-size: 12px; }"#;
+
+/// What a report is rendered from: one language or a whole repository.
+#[derive(Clone, Copy)]
+pub(crate) enum Report<'a> {
+  Single(&'a AnalysisResult),
+  Repository(&'a RepositoryAnalysis),
+}
+
+/// Renders `report` in `format`. Plain text is returned directly; every other
+/// format is written to `output_file` (or a generated file name) and a short
+/// confirmation message is returned instead.
+pub(crate) fn render_output(
+  report: Report,
+  format: OutputFormat,
+  output_file: Option<String>,
+) -> Result<String, AnalyzerError> {
+  let (content, extension, label) = match (format, report) {
+    (OutputFormat::Plain, Report::Single(result)) => return Ok(format_plain(result)),
+    (OutputFormat::Plain, Report::Repository(analysis)) => {
+      return Ok(format_repository_plain(analysis))
+    }
+    (OutputFormat::Json, Report::Single(result)) => (generate_json_content(result), "json", "JSON"),
+    (OutputFormat::Json, Report::Repository(analysis)) => {
+      (generate_repository_json_content(analysis), "json", "JSON")
+    }
+    (OutputFormat::Csv, Report::Single(result)) => (generate_csv_content(result), "csv", "CSV"),
+    (OutputFormat::Csv, Report::Repository(analysis)) => {
+      (generate_repository_csv_content(analysis), "csv", "CSV")
+    }
+    (OutputFormat::Html, Report::Single(result)) => (generate_html_content(result), "html", "HTML"),
+    (OutputFormat::Html, Report::Repository(analysis)) => {
+      (generate_repository_html_content(analysis), "html", "HTML")
+    }
+    (OutputFormat::Graph, Report::Single(result)) => {
+      (generate_svg_content(result)?, "svg", "Graph")
+    }
+    (OutputFormat::Graph, Report::Repository(analysis)) => {
+      (generate_repository_svg_content(analysis)?, "svg", "Graph")
+    }
+  };
+
+  let file_stem = match report {
+    Report::Single(result) => result.language.as_str(),
+    Report::Repository(_) => "auto",
+  };
+  let file_path = output_file.unwrap_or_else(|| generate_default_filename(file_stem, extension));
+  fs::write(&file_path, content).map_err(|source| AnalyzerError::OutputWriteFailed {
+    path: file_path.clone().into(),
+    source,
+  })?;
+
+  Ok(format!("{label} results written to: {file_path}"))
+}
+
+fn generate_default_filename(language: &str, extension: &str) -> String {
+  let timestamp = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs();
+
+  format!("keyword_analysis_{language}_{timestamp}.{extension}")
+}
+
+fn nonzero_counts(result: &AnalysisResult) -> Vec<(&String, &usize)> {
+  result
+    .get_sorted_counts()
+    .into_iter()
+    .filter(|(_, count)| **count > 0)
+    .collect()
+}
+
+/// Non-zero keyword counts, most frequent first and ties by keyword.
+fn sorted_counts(counts: &HashMap<String, usize>) -> Vec<(&String, &usize)> {
+  let mut counts: Vec<_> = counts.iter().filter(|(_, count)| **count > 0).collect();
+  counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+  counts
+}
+
+/// `keyword count` pairs for the `limit` most frequent keywords of a file.
+fn top_keywords(file: &FileAnalysis, limit: usize) -> String {
+  sorted_counts(&file.keyword_counts)
+    .into_iter()
+    .take(limit)
+    .map(|(keyword, count)| format!("{keyword} {count}"))
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+pub fn format_plain(result: &AnalysisResult) -> String {
+  let mut output = String::new();
+  output.push_str(&format!(
+    "\n=== {} Keyword Analysis Results ===\n",
+    result.language_name
+  ));
+  output.push_str(&format!("Files analyzed: {}\n", result.file_count));
+  output.push_str(&format!(
+    "Total keywords found: {}\n",
+    result.total_keywords
+  ));
+  output.push_str(&plain_density(&result.lines, &result.density()));
+  output.push_str(&plain_categories(&result.category_totals()));
+  output.push_str(&plain_sequences(result));
+
+  for (keyword, count) in nonzero_counts(result) {
+    output.push_str(&format!("{keyword:12} : {count}\n"));
+  }
+
+  if !result.files.is_empty() {
+    output.push_str("\nTop files by keywords:\n");
+    for file in result.files_sorted_by(None).into_iter().take(TOP_FILES) {
+      output.push_str(&format!(
+        "{:8} keywords {:8} lines  {}\n",
+        file.total_keywords, file.line_count, file.path
+      ));
+    }
+  }
+
+  let files = result.most_complex_files(TOP_FILES);
+  output.push_str(&plain_complex_files(
+    files.into_iter().map(|file| (file, None)),
+  ));
+  output.push_str(&plain_skipped(&result.skipped));
+
+  output
+}
+
+/// The files that could not be read, with the reason.
+fn plain_skipped(skipped: &[SkippedFile]) -> String {
+  if skipped.is_empty() {
+    return String::new();
+  }
+  let mut output = format!(
+    "
+Skipped files ({}):
+",
+    skipped.len()
+  );
+  for file in skipped {
+    output.push_str(&format!(
+      "  {}: {}
+",
+      file.path, file.reason
+    ));
+  }
+  output
+}
+
+/// The most complex files, each with its most complex function and, in
+/// repository reports, its language.
+fn plain_complex_files<'a>(
+  files: impl Iterator<Item = (&'a FileAnalysis, Option<&'a str>)>,
+) -> String {
+  let mut output = String::new();
+  for (file, language) in files {
+    if output.is_empty() {
+      output.push_str("\nMost complex files:\n");
+    }
+    output.push_str(&format!(
+      "{:8} complexity {:5} functions  {}\n",
+      file.complexity.complexity,
+      file.complexity.functions.len(),
+      complex_file_label(file, language)
+    ));
+  }
+  output
+}
+
+/// `path [language] (max name line N: C)`, naming the file's most complex
+/// function.
+fn complex_file_label(file: &FileAnalysis, language: Option<&str>) -> String {
+  let mut label = file.path.clone();
+  if let Some(language) = language {
+    label.push_str(&format!(" [{language}]"));
+  }
+  if let Some(function) = file.complexity.most_complex() {
+    label.push_str(&format!(
+      " (max {} line {}: {})",
+      function.name, function.line, function.complexity
+    ));
+  }
+  label
+}
+
+/// Line counts and keyword density, followed by a blank line.
+fn plain_density(lines: &LineCounts, density: &Density) -> String {
+  format!(
+    "Lines: {} (code {}, comment {}, blank {}, mixed {})\n\
+     Keywords per 1000 code lines: {:.1}\n\
+     Keywords per file: {}\n\
+     Keywords per 1000 code lines per file: {}\n\n",
+    lines.total,
+    lines.code,
+    lines.comment,
+    lines.blank,
+    lines.mixed,
+    density.keywords_per_kloc,
+    plain_distribution(&density.keywords_per_file),
+    plain_distribution(&density.file_keywords_per_kloc)
+  )
+}
+
+fn plain_distribution(distribution: &Distribution) -> String {
+  format!(
+    "mean {:.1}, median {:.1}, p75 {:.1}, p90 {:.1}, p95 {:.1}, max {:.1}",
+    distribution.mean,
+    distribution.median,
+    distribution.p75,
+    distribution.p90,
+    distribution.p95,
+    distribution.max
+  )
+}
+
+/// `name : count (percentage)` lines, followed by a blank line.
+fn plain_categories(totals: &[CategoryTotal]) -> String {
+  if totals.is_empty() {
+    return String::new();
+  }
+
+  let mut output = String::from("Keywords by category:\n");
+  for total in totals {
+    output.push_str(&format!(
+      "{:24} : {} ({:.1}%)\n",
+      total.name, total.count, total.percentage
+    ));
+  }
+  output.push('\n');
+  output
+}
+
+/// `sequence : count` lines for the most frequent keyword sequences,
+/// followed by a blank line.
+fn plain_sequences(result: &AnalysisResult) -> String {
+  let sequences = result.top_sequences(TOP_SEQUENCES);
+  if sequences.is_empty() {
+    return String::new();
+  }
+
+  let options = result.sequence_options;
+  let mut output = format!(
+    "Top keyword sequences ({} keywords, window {}):\n",
+    options.length, options.window
+  );
+  for (sequence, count) in sequences {
+    output.push_str(&format!("{sequence:24} : {count}\n"));
+  }
+  output.push('\n');
+  output
+}
+
+/// A table of every analyzed file, sorted by `sort_by` (a keyword) or by the
+/// total number of keywords.
+pub fn format_per_file_plain(result: &AnalysisResult, sort_by: Option<&str>) -> String {
+  let mut output = format!(
+    "\n=== {} Per-File Keyword Breakdown ===\n",
+    result.language_name
+  );
+  match sort_by {
+    Some(keyword) => {
+      output.push_str(&format!("Sorted by: {keyword}\n\n"));
+      output.push_str(&format!(
+        "{keyword:>10} {:>10} {:>10} {:>10} {:>10}  file\n",
+        "keywords", "lines", "code", "per_kloc"
+      ));
+    }
+    None => output.push_str(&format!(
+      "\n{:>10} {:>10} {:>10} {:>10}  file\n",
+      "keywords", "lines", "code", "per_kloc"
+    )),
+  }
+
+  for file in result.files_sorted_by(sort_by) {
+    if let Some(keyword) = sort_by {
+      output.push_str(&format!("{:>10} ", file.count(keyword)));
+    }
+    output.push_str(&format!(
+      "{:>10} {:>10} {:>10} {:>10.1}  {}\n",
+      file.total_keywords,
+      file.line_count,
+      file.lines.code,
+      file.keywords_per_kloc(),
+      file.path
+    ));
+  }
+
+  output
+}
+
+/// Keyword totals rolled up per directory, indented by depth.
+pub fn format_tree_plain(result: &AnalysisResult) -> String {
+  let mut output = format!(
+    "\n=== {} Directory Breakdown ===\n\n{:>8} {:>10} {:>10}  directory\n",
+    result.language_name, "files", "keywords", "lines"
+  );
+
+  let tree = result.directory_tree();
+  for entry in tree.flatten() {
+    let node = entry.node;
+    output.push_str(&format!(
+      "{:>8} {:>10} {:>10}  {}{}",
+      node.file_count,
+      node.total_keywords,
+      node.line_count,
+      "  ".repeat(entry.depth),
+      node.name
+    ));
+    if let Some(package) = &node.package {
+      output.push_str(&format!("  [{} {}]", package.manifest, package.name));
+    }
+    output.push('\n');
+  }
+
+  output
+}
+
+/// A `cloc`-style line summary: the language's totals followed by one row per
+/// directory.
+pub fn format_lines_plain(result: &AnalysisResult) -> String {
+  let mut output = plain_lines_header("Language");
+  output.push_str(&plain_lines_row(
+    &result.language_name,
+    result.file_count,
+    &result.lines,
+  ));
+  output.push_str(&plain_directory_lines(result));
+  output
+}
+
+/// [`format_lines_plain`] for every language, with a `SUM` row across them.
+pub fn format_repository_lines_plain(analysis: &RepositoryAnalysis) -> String {
+  let mut output = plain_lines_header("Language");
+  for result in &analysis.results {
+    output.push_str(&plain_lines_row(
+      &result.language_name,
+      result.file_count,
+      &result.lines,
+    ));
+  }
+  output.push_str(&format!("{PLAIN_RULE}\n"));
+  output.push_str(&plain_lines_row(
+    "SUM:",
+    analysis.file_count,
+    &analysis.lines,
+  ));
+
+  for result in &analysis.results {
+    output.push_str(&plain_directory_lines(result));
+  }
+  output
+}
+
+const PLAIN_RULE: &str =
+  "--------------------------------------------------------------------------------";
+
+fn plain_lines_header(label: &str) -> String {
+  format!(
+    "\n{PLAIN_RULE}\n{label:<28}{:>10}{:>10}{:>10}{:>10}{:>10}\n{PLAIN_RULE}\n",
+    "files", "blank", "comment", "code", "mixed"
+  )
+}
+
+fn plain_lines_row(label: &str, files: usize, lines: &LineCounts) -> String {
+  format!(
+    "{label:<28}{files:>10}{:>10}{:>10}{:>10}{:>10}\n",
+    lines.blank, lines.comment, lines.code, lines.mixed
+  )
+}
+
+fn plain_directory_lines(result: &AnalysisResult) -> String {
+  let mut output = format!("\n=== {} Lines by Directory ===\n", result.language_name);
+  output.push_str(&plain_lines_header("Directory"));
+  let tree = result.directory_tree();
+  for entry in tree.flatten() {
+    let node = entry.node;
+    output.push_str(&plain_lines_row(
+      &format!("{}{}", "  ".repeat(entry.depth), node.name),
+      node.file_count,
+      &node.lines,
+    ));
+  }
+  output
+}
+
+/// Comment ratio, doc coverage and every `TODO`-style marker with its
+/// location and owner.
+pub fn format_comments_plain(result: &AnalysisResult) -> String {
+  let markers = result.markers();
+  let mut output = format!(
+    "\n=== {} Comment Analysis ===\n{}",
+    result.language_name,
+    plain_comment_summary(&result.lines, &result.docs)
+  );
+  output.push_str(&format!(
+    "Markers: {}\n",
+    marker_counts(markers.iter().map(|(_, marker)| *marker))
+  ));
+
+  if !markers.is_empty() {
+    output.push('\n');
+  }
+  for (path, marker) in markers {
+    let owner = marker
+      .owner
+      .as_ref()
+      .map_or(String::new(), |owner| format!("({owner})"));
+    output.push_str(&format!(
+      "{path}:{}:{}: {}{owner} {}\n",
+      marker.line, marker.column, marker.kind, marker.text
+    ));
+  }
+
+  output
+}
+
+pub fn format_repository_comments_plain(analysis: &RepositoryAnalysis) -> String {
+  let mut output = format!(
+    "\n=== Repository Comment Analysis ===\n{}",
+    plain_comment_summary(&analysis.lines, &analysis.docs)
+  );
+  output.push_str(&format!(
+    "Markers: {}\n",
+    marker_counts(
+      analysis
+        .results
+        .iter()
+        .flat_map(|result| &result.files)
+        .flat_map(|file| &file.markers)
+    )
+  ));
+
+  for result in &analysis.results {
+    output.push_str(&format_comments_plain(result));
+  }
+  output
+}
+
+fn plain_comment_summary(lines: &LineCounts, docs: &DocCoverage) -> String {
+  let coverage = match docs.percentage() {
+    Some(percentage) => format!(
+      "{} of {} items ({percentage:.1}%)",
+      docs.documented, docs.items
+    ),
+    None => "no documentable items".to_string(),
+  };
+  format!(
+    "Comment lines: {} ({:.2} per line of code)\nDoc coverage: {coverage}\n",
+    lines.comment,
+    lines.comment_ratio()
+  )
+}
+
+/// `total (KIND count, ...)`, listing only the kinds found.
+fn marker_counts<'a>(markers: impl Iterator<Item = &'a Marker>) -> String {
+  let mut counts = [0; MARKERS.len()];
+  let mut total = 0;
+  for marker in markers {
+    if let Some(index) = MARKERS.iter().position(|kind| *kind == marker.kind) {
+      counts[index] += 1;
+      total += 1;
+    }
+  }
+
+  let kinds: Vec<String> = MARKERS
+    .iter()
+    .zip(counts)
+    .filter(|(_, count)| *count > 0)
+    .map(|(kind, count)| format!("{kind} {count}"))
+    .collect();
+  if kinds.is_empty() {
+    total.to_string()
+  } else {
+    format!("{total} ({})", kinds.join(", "))
+  }
+}
+
+pub fn format_repository_tree_plain(analysis: &RepositoryAnalysis) -> String {
+  analysis.results.iter().map(format_tree_plain).collect()
+}
+
+pub fn format_repository_per_file_plain(
+  analysis: &RepositoryAnalysis,
+  sort_by: Option<&str>,
+) -> String {
+  analysis
+    .results
+    .iter()
+    .map(|result| format_per_file_plain(result, sort_by))
+    .collect()
+}
+
+pub fn format_repository_plain(analysis: &RepositoryAnalysis) -> String {
+  let mut output = String::new();
+  output.push_str("\n=== Repository Keyword Analysis Results ===\n");
+  output.push_str(&format!("Files analyzed: {}\n", analysis.file_count));
+  output.push_str(&format!(
+    "Total keywords found: {}\n",
+    analysis.total_keywords
+  ));
+  output.push_str(&format!("Languages: {}\n", analysis.results.len()));
+  output.push_str(&plain_density(&analysis.lines, &analysis.density()));
+  output.push_str(&plain_categories(&analysis.category_totals()));
+
+  for result in &analysis.results {
+    output.push_str(&format!(
+      "{:24} : {} files, {} keywords\n",
+      result.language_name, result.file_count, result.total_keywords
+    ));
+  }
+
+  let files = analysis.most_complex_files(TOP_FILES);
+  output.push_str(&plain_complex_files(
+    files
+      .into_iter()
+      .map(|(result, file)| (file, Some(result.language_name.as_str()))),
+  ));
+  output.push_str(&plain_skipped(&analysis.skipped));
+
+  for result in &analysis.results {
+    output.push_str(&format_plain(result));
+  }
+
+  output
+}
+
+fn categories_json(totals: &[CategoryTotal]) -> Value {
+  let categories: Vec<Value> = totals
+    .iter()
+    .map(|total| {
+      json!({
+        "name": total.name,
+        "count": total.count,
+        "percentage": (total.percentage * 10.0).round() / 10.0,
+      })
+    })
+    .collect();
+  Value::Array(categories)
+}
+
+fn lines_json(lines: &LineCounts) -> Value {
+  json!({
+    "total": lines.total,
+    "code": lines.code,
+    "comment": lines.comment,
+    "blank": lines.blank,
+    "mixed": lines.mixed,
+  })
+}
+
+/// Rounds to two decimals so reports stay readable.
+fn round2(value: f64) -> f64 {
+  (value * 100.0).round() / 100.0
+}
+
+fn distribution_json(distribution: &Distribution) -> Value {
+  json!({
+    "mean": round2(distribution.mean),
+    "median": round2(distribution.median),
+    "p75": round2(distribution.p75),
+    "p90": round2(distribution.p90),
+    "p95": round2(distribution.p95),
+    "max": round2(distribution.max),
+  })
+}
+
+fn density_json(density: &Density) -> Value {
+  json!({
+    "keywords_per_kloc": round2(density.keywords_per_kloc),
+    "keywords_per_file": distribution_json(&density.keywords_per_file),
+    "file_keywords_per_kloc": distribution_json(&density.file_keywords_per_kloc),
+  })
+}
+
+fn docs_json(docs: &DocCoverage) -> Value {
+  json!({
+    "documented": docs.documented,
+    "items": docs.items,
+    "percentage": docs.percentage().map(round2),
+  })
+}
+
+/// Comment ratio, doc coverage and markers of a result.
+fn comments_json(result: &AnalysisResult) -> Value {
+  let markers: Vec<Value> = result
+    .markers()
+    .into_iter()
+    .map(|(path, marker)| {
+      json!({
+        "path": path,
+        "line": marker.line,
+        "column": marker.column,
+        "kind": marker.kind,
+        "owner": marker.owner,
+        "text": marker.text,
+      })
+    })
+    .collect();
+  json!({
+    "comment_ratio": round2(result.lines.comment_ratio()),
+    "doc_coverage": docs_json(&result.docs),
+    "markers": markers,
+  })
+}
+
+/// The most frequent keyword sequences and how they were counted.
+fn sequences_json(result: &AnalysisResult) -> Value {
+  let top: Vec<Value> = result
+    .top_sequences(TOP_SEQUENCES)
+    .into_iter()
+    .map(|(sequence, count)| json!({ "sequence": sequence, "count": count }))
+    .collect();
+  json!({
+    "length": result.sequence_options.length,
+    "window": result.sequence_options.window,
+    "top": top,
+  })
+}
+
+fn complex_file_json(file: &FileAnalysis) -> Value {
+  json!({
+    "path": file.path,
+    "complexity": file.complexity.complexity,
+    "functions": file.complexity.functions.len(),
+    "max_function": file.complexity.most_complex(),
+  })
+}
+
+/// Total complexity with the most complex files and functions of a result.
+fn complexity_json(result: &AnalysisResult) -> Value {
+  let files: Vec<Value> = result
+    .most_complex_files(TOP_FILES)
+    .into_iter()
+    .map(complex_file_json)
+    .collect();
+  let functions: Vec<Value> = result
+    .most_complex_functions(TOP_FILES)
+    .into_iter()
+    .map(|(path, function)| {
+      json!({
+        "path": path,
+        "name": function.name,
+        "line": function.line,
+        "complexity": function.complexity,
+      })
+    })
+    .collect();
+  json!({
+    "total": result.complexity(),
+    "most_complex_files": files,
+    "most_complex_functions": functions,
+  })
+}
+
+fn keywords_json(result: &AnalysisResult) -> Value {
+  let mut keywords = Map::new();
+  for (keyword, count) in nonzero_counts(result) {
+    keywords.insert(keyword.clone(), json!(count));
+  }
+  Value::Object(keywords)
+}
+
+/// Every file with its non-zero keyword counts, most keywords first.
+fn files_json(result: &AnalysisResult) -> Value {
+  let files: Vec<Value> = result
+    .files_sorted_by(None)
+    .into_iter()
+    .map(|file| {
+      let mut keywords = Map::new();
+      for (keyword, count) in sorted_counts(&file.keyword_counts) {
+        keywords.insert(keyword.clone(), json!(count));
+      }
+      json!({
+        "path": file.path,
+        "lines": file.line_count,
+        "code_lines": file.lines.code,
+        "comment_lines": file.lines.comment,
+        "blank_lines": file.lines.blank,
+        "mixed_lines": file.lines.mixed,
+        "total_keywords": file.total_keywords,
+        "keywords_per_kloc": round2(file.keywords_per_kloc()),
+        "documented_items": file.docs.documented,
+        "documentable_items": file.docs.items,
+        "markers": file.markers.len(),
+        "complexity": file.complexity.complexity,
+        "functions": file.complexity.functions.len(),
+        "max_function_complexity": file
+          .complexity
+          .most_complex()
+          .map(|function| function.complexity),
+        "keywords": keywords,
+      })
+    })
+    .collect();
+  Value::Array(files)
+}
+
+/// The directory tree flattened in depth-first order.
+fn directories_json(result: &AnalysisResult) -> Value {
+  let tree = result.directory_tree();
+  let directories: Vec<Value> = tree
+    .flatten()
+    .into_iter()
+    .map(|entry| {
+      let node = entry.node;
+      let mut keywords = Map::new();
+      for (keyword, count) in sorted_counts(&node.keyword_counts) {
+        keywords.insert(keyword.clone(), json!(count));
+      }
+      json!({
+        "path": node.display_path(),
+        "depth": entry.depth,
+        "package": entry.package,
+        "files": node.file_count,
+        "lines": node.line_count,
+        "code_lines": node.lines.code,
+        "comment_lines": node.lines.comment,
+        "blank_lines": node.lines.blank,
+        "mixed_lines": node.lines.mixed,
+        "total_keywords": node.total_keywords,
+        "keywords": keywords,
+      })
+    })
+    .collect();
+  Value::Array(directories)
+}
+
+pub fn generate_json_content(result: &AnalysisResult) -> String {
+  let report = json!({
+    "files_analyzed": result.file_count,
+    "total_keywords": result.total_keywords,
+    "lines": lines_json(&result.lines),
+    "density": density_json(&result.density()),
+    "comments": comments_json(result),
+    "categories": categories_json(&result.category_totals()),
+    "sequences": sequences_json(result),
+    "cooccurrence": result.cooccurrence(MATRIX_SIZE),
+    "complexity": complexity_json(result),
+    "keywords": keywords_json(result),
+    "files": files_json(result),
+    "directories": directories_json(result),
+    "packages": result.packages,
+    "skipped": result.skipped,
+  });
+
+  serde_json::to_string_pretty(&report).unwrap()
+}
+
+pub fn generate_repository_json_content(analysis: &RepositoryAnalysis) -> String {
+  let languages: Vec<Value> = analysis
+    .results
+    .iter()
+    .map(|result| {
+      json!({
+        "language": result.language,
+        "name": result.language_name,
+        "files_analyzed": result.file_count,
+        "total_keywords": result.total_keywords,
+        "lines": lines_json(&result.lines),
+        "density": density_json(&result.density()),
+        "comments": comments_json(result),
+        "categories": categories_json(&result.category_totals()),
+        "sequences": sequences_json(result),
+        "cooccurrence": result.cooccurrence(MATRIX_SIZE),
+        "complexity": complexity_json(result),
+        "keywords": keywords_json(result),
+        "files": files_json(result),
+        "directories": directories_json(result),
+        "packages": result.packages,
+      })
+    })
+    .collect();
+
+  let most_complex_files: Vec<Value> = analysis
+    .most_complex_files(TOP_FILES)
+    .into_iter()
+    .map(|(result, file)| {
+      let mut file_json = complex_file_json(file);
+      file_json["language"] = json!(result.language);
+      file_json
+    })
+    .collect();
+
+  let report = json!({
+    "files_analyzed": analysis.file_count,
+    "total_keywords": analysis.total_keywords,
+    "lines": lines_json(&analysis.lines),
+    "density": density_json(&analysis.density()),
+    "comments": {
+      "comment_ratio": round2(analysis.lines.comment_ratio()),
+      "doc_coverage": docs_json(&analysis.docs),
+    },
+    "complexity": {
+      "total": analysis.complexity(),
+      "most_complex_files": most_complex_files,
+    },
+    "categories": categories_json(&analysis.category_totals()),
+    "languages": languages,
+    "skipped": analysis.skipped,
+  });
+
+  serde_json::to_string_pretty(&report).unwrap()
+}
+
+/// Quotes a CSV field when it contains a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+  if value.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", value.replace('"', "\"\""))
+  } else {
+    value.to_string()
+  }
+}
+
+/// Appends `{prefix}{kind},{path},{package},` rows for `stats` and the
+/// non-zero keyword counts.
+fn csv_rows(
+  csv: &mut String,
+  row: &str,
+  stats: &[(&str, usize)],
+  keyword_counts: &HashMap<String, usize>,
+) {
+  for (stat, value) in stats {
+    csv.push_str(&format!("{row}{stat},{value}\n"));
+  }
+  for (keyword, count) in sorted_counts(keyword_counts) {
+    csv.push_str(&format!("{row}{keyword},{count}\n"));
+  }
+}
+
+/// `{prefix}density,,,` rows with the density statistics.
+fn csv_density_rows(csv: &mut String, prefix: &str, density: &Density) {
+  let mut rows = vec![("keywords_per_kloc".to_string(), density.keywords_per_kloc)];
+  for (name, distribution) in [
+    ("keywords_per_file", &density.keywords_per_file),
+    ("file_keywords_per_kloc", &density.file_keywords_per_kloc),
+  ] {
+    rows.extend([
+      (format!("{name}_mean"), distribution.mean),
+      (format!("{name}_median"), distribution.median),
+      (format!("{name}_p75"), distribution.p75),
+      (format!("{name}_p90"), distribution.p90),
+      (format!("{name}_p95"), distribution.p95),
+      (format!("{name}_max"), distribution.max),
+    ]);
+  }
+
+  for (stat, value) in rows {
+    csv.push_str(&format!("{prefix}density,,,{stat},{value:.2}\n"));
+  }
+}
+
+/// Total, density, category, directory and file rows for a result, each
+/// prefixed with `prefix`.
+fn csv_result_rows(csv: &mut String, prefix: &str, result: &AnalysisResult) {
+  csv_rows(
+    csv,
+    &format!("{prefix}total,,,"),
+    &[
+      ("_files_analyzed", result.file_count),
+      ("_total_keywords", result.total_keywords),
+      ("_lines", result.lines.total),
+      ("_code_lines", result.lines.code),
+      ("_comment_lines", result.lines.comment),
+      ("_blank_lines", result.lines.blank),
+      ("_mixed_lines", result.lines.mixed),
+      ("_documented_items", result.docs.documented),
+      ("_documentable_items", result.docs.items),
+      (
+        "_markers",
+        result.files.iter().map(|file| file.markers.len()).sum(),
+      ),
+      ("_complexity", result.complexity()),
+    ],
+    &result.keyword_counts,
+  );
+  csv_density_rows(csv, prefix, &result.density());
+  for total in result.category_totals() {
+    csv.push_str(&format!(
+      "{prefix}category,,,{},{}\n",
+      csv_field(&total.name),
+      total.count
+    ));
+  }
+  for (sequence, count) in sorted_counts(&result.keyword_sequences) {
+    csv.push_str(&format!("{prefix}sequence,,,{sequence},{count}\n"));
+  }
+
+  let tree = result.directory_tree();
+  for entry in tree.flatten() {
+    let node = entry.node;
+    let row = format!(
+      "{prefix}directory,{},{},",
+      csv_field(node.display_path()),
+      csv_field(entry.package.unwrap_or_default())
+    );
+    csv_rows(
+      csv,
+      &row,
+      &[
+        ("_files", node.file_count),
+        ("_lines", node.line_count),
+        ("_code_lines", node.lines.code),
+        ("_comment_lines", node.lines.comment),
+        ("_blank_lines", node.lines.blank),
+        ("_mixed_lines", node.lines.mixed),
+        ("_total_keywords", node.total_keywords),
+      ],
+      &node.keyword_counts,
+    );
+  }
+
+  for file in result.files_sorted_by(None) {
+    let package = package_for(&result.packages, &file.path).map_or("", |p| p.name.as_str());
+    let row = format!(
+      "{prefix}file,{},{},",
+      csv_field(&file.path),
+      csv_field(package)
+    );
+    csv_rows(
+      csv,
+      &row,
+      &[
+        ("_lines", file.line_count),
+        ("_code_lines", file.lines.code),
+        ("_comment_lines", file.lines.comment),
+        ("_blank_lines", file.lines.blank),
+        ("_mixed_lines", file.lines.mixed),
+        ("_total_keywords", file.total_keywords),
+        ("_documented_items", file.docs.documented),
+        ("_documentable_items", file.docs.items),
+        ("_markers", file.markers.len()),
+        ("_complexity", file.complexity.complexity),
+        ("_functions", file.complexity.functions.len()),
+      ],
+      &file.keyword_counts,
+    );
+    csv.push_str(&format!(
+      "{row}_keywords_per_kloc,{:.2}\n",
+      file.keywords_per_kloc()
+    ));
+    for function in &file.complexity.functions {
+      csv.push_str(&format!(
+        "{prefix}function,{},{},{},{}\n",
+        csv_field(&file.path),
+        csv_field(package),
+        csv_field(&format!("{}:{}", function.name, function.line)),
+        function.complexity
+      ));
+    }
+  }
+  csv_skipped_rows(csv, prefix, &result.skipped);
+}
+
+/// `{prefix}skipped,{path},,{reason},1` rows for the files that could not be
+/// read.
+fn csv_skipped_rows(csv: &mut String, prefix: &str, skipped: &[SkippedFile]) {
+  for file in skipped {
+    csv.push_str(&format!(
+      "{prefix}skipped,{},,{},1\n",
+      csv_field(&file.path),
+      csv_field(&file.reason)
+    ));
+  }
+}
+
+/// A flat table of `total`, `density`, `category`, `sequence`, `directory`,
+/// `file`, `function` and `skipped` rows. `density`, `category` and
+/// `sequence` rows hold the statistic, category name or space-separated
+/// keywords in the `keyword` column, `function` rows `name:line` and the
+/// function's complexity, `skipped` rows the reason the file was not read;
+/// `package` is the innermost crate or package containing the directory or
+/// file.
+pub fn generate_csv_content(result: &AnalysisResult) -> String {
+  let mut csv = String::from("kind,path,package,keyword,count\n");
+  csv_result_rows(&mut csv, "", result);
+  csv
+}
+
+/// Like [`generate_csv_content`] with a leading `language` column; `_all`
+/// rows hold the repository totals.
+pub fn generate_repository_csv_content(analysis: &RepositoryAnalysis) -> String {
+  let mut csv = String::from("language,kind,path,package,keyword,count\n");
+  csv.push_str(&format!(
+    "_all,total,,,_files_analyzed,{}\n",
+    analysis.file_count
+  ));
+  csv.push_str(&format!(
+    "_all,total,,,_total_keywords,{}\n",
+    analysis.total_keywords
+  ));
+  for (stat, value) in [
+    ("_lines", analysis.lines.total),
+    ("_code_lines", analysis.lines.code),
+    ("_comment_lines", analysis.lines.comment),
+    ("_blank_lines", analysis.lines.blank),
+    ("_mixed_lines", analysis.lines.mixed),
+    ("_complexity", analysis.complexity()),
+  ] {
+    csv.push_str(&format!("_all,total,,,{stat},{value}\n"));
+  }
+  csv_density_rows(&mut csv, "_all,", &analysis.density());
+  csv_skipped_rows(&mut csv, "_all,", &analysis.skipped);
+
+  for result in &analysis.results {
+    csv_result_rows(&mut csv, &format!("{},", result.language), result);
+  }
+
+  csv
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+fn html_page(title: &str, body: &str) -> String {
+  let title = escape_html(title);
+  format!(
+    r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{title}</title>
+    <style>
+{HTML_STYLE}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <h1>{title}</h1>
+{body}        <div class="footer">
+            <p>Generated by Multi-Language Keyword Analyzer</p>
+        </div>
+    </div>
+</body>
+</html>"#
+  )
+}
+
+fn html_summary(heading: &str, stats: &[(String, &str)]) -> String {
+  let mut html = String::new();
+  html.push_str("        <div class=\"summary\">\n");
+  html.push_str(&format!("            <h2>{heading}</h2>\n"));
+  for (value, label) in stats {
+    html.push_str(&format!(
+      r#"            <div class="stat">
+                <div class="stat-value">{value}</div>
+                <div class="stat-label">{label}</div>
+            </div>
+"#
+    ));
+  }
+  html.push_str("        </div>\n");
+  html
+}
+
+/// A table of `(label, value)` rows with a bar relative to the largest value.
+fn html_bar_table(headers: [&str; 2], rows: &[(String, usize)]) -> String {
+  let max_count = rows
+    .iter()
+    .map(|(_, count)| *count)
+    .max()
+    .unwrap_or(1)
+    .max(1);
+  let [label_header, count_header] = headers;
+
+  let mut html = format!(
+    r#"        <table class="keywords-table">
+            <thead>
+                <tr>
+                    <th>{label_header}</th>
+                    <th>{count_header}</th>
+                    <th>Distribution</th>
+                </tr>
+            </thead>
+            <tbody>
+"#
+  );
+
+  for (label, count) in rows {
+    let percentage = ((*count as f64) / (max_count as f64) * 100.0) as u32;
+    let label = escape_html(label);
+    html.push_str(&format!(
+      r#"                <tr>
+                    <td class="keyword">{label}</td>
+                    <td class="count">{count}</td>
+                    <td>
+                        <div class="progress-bar">
+                            <div class="progress-fill" style="width: {percentage}%;"></div>
+                        </div>
+                    </td>
+                </tr>
+"#
+    ));
+  }
+
+  html.push_str(
+    r#"            </tbody>
+        </table>
+"#,
+  );
+  html
+}
+
+/// Summary and keyword table for a single language.
+fn html_result_section(result: &AnalysisResult) -> String {
+  let mut html = html_summary(
+    "Analysis Summary",
+    &[
+      (result.file_count.to_string(), "Files Analyzed"),
+      (result.total_keywords.to_string(), "Total Keywords Found"),
+      (result.lines.code.to_string(), "Lines of Code"),
+    ],
+  );
+  html.push_str(&html_density(&result.lines, &result.density()));
+  html.push_str(&html_lines_table([(
+    result.language_name.as_str(),
+    result.file_count,
+    &result.lines,
+  )]));
+
+  let categories = result.category_totals();
+  if !categories.is_empty() {
+    html.push_str(&html_bar_table(
+      ["Category", "Keywords"],
+      &category_rows(&categories),
+    ));
+  }
+
+  let rows: Vec<(String, usize)> = nonzero_counts(result)
+    .into_iter()
+    .map(|(keyword, count)| (keyword.clone(), *count))
+    .collect();
+
+  if rows.is_empty() {
+    html.push_str(
+      r#"        <p style="text-align: center; color: #6c757d; font-style: italic;">No keywords found in the analyzed files.</p>
+"#,
+    );
+  } else {
+    html.push_str(&html_bar_table(["Keyword", "Count"], &rows));
+  }
+
+  let sequences: Vec<(String, usize)> = result
+    .top_sequences(TOP_SEQUENCES)
+    .into_iter()
+    .map(|(sequence, count)| (sequence.to_string(), count))
+    .collect();
+  if !sequences.is_empty() {
+    html.push_str(&html_bar_table(["Keyword Sequence", "Count"], &sequences));
+    html.push_str(&html_cooccurrence(&result.cooccurrence(MATRIX_SIZE)));
+  }
+
+  html.push_str(&html_comments(result));
+  html.push_str(&html_complexity(
+    result
+      .most_complex_files(TOP_FILES)
+      .into_iter()
+      .map(|file| (file, None)),
+  ));
+  let functions: Vec<(String, usize)> = result
+    .most_complex_functions(TOP_FILES)
+    .into_iter()
+    .map(|(path, function)| {
+      (
+        format!("{} ({path}:{})", function.name, function.line),
+        function.complexity,
+      )
+    })
+    .collect();
+  if !functions.is_empty() {
+    html.push_str(&html_bar_table(
+      ["Most Complex Function", "Complexity"],
+      &functions,
+    ));
+  }
+
+  if !result.files.is_empty() {
+    html.push_str(&html_directory_tree(&result.directory_tree()));
+    html.push_str(&html_file_table(result));
+  }
+  html.push_str(&html_skipped(&result.skipped));
+
+  html
+}
+
+/// A table of the files that could not be read, with the reason.
+fn html_skipped(skipped: &[SkippedFile]) -> String {
+  if skipped.is_empty() {
+    return String::new();
+  }
+  let mut html = format!(
+    r#"        <h2>Skipped Files ({})</h2>
+        <table class="keywords-table">
+            <thead>
+                <tr>
+                    <th>Path</th>
+                    <th>Reason</th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+    skipped.len()
+  );
+  for file in skipped {
+    html.push_str(&format!(
+      r#"                <tr>
+                    <td class="keyword">{}</td>
+                    <td>{}</td>
+                </tr>
+"#,
+      escape_html(&file.path),
+      escape_html(&file.reason)
+    ));
+  }
+  html.push_str(
+    r#"            </tbody>
+        </table>
+"#,
+  );
+  html
+}
+
+/// A bar table of the most complex files, with their most complex function
+/// and, in repository reports, their language.
+fn html_complexity<'a>(files: impl Iterator<Item = (&'a FileAnalysis, Option<&'a str>)>) -> String {
+  let rows: Vec<(String, usize)> = files
+    .map(|(file, language)| {
+      (
+        complex_file_label(file, language),
+        file.complexity.complexity,
+      )
+    })
+    .collect();
+  if rows.is_empty() {
+    return String::new();
+  }
+  html_bar_table(["Most Complex File", "Complexity"], &rows)
+}
+
+/// The co-occurrence matrix as a table shaded by count; rows are the first
+/// keyword of a pair, columns the second.
+fn html_cooccurrence(matrix: &CooccurrenceMatrix) -> String {
+  let max = matrix.max().max(1);
+  let mut html = String::from(
+    r#"        <h3>Keyword Co-occurrence</h3>
+        <table class="keywords-table matrix">
+            <thead>
+                <tr>
+                    <th>First \ Next</th>
+"#,
+  );
+  for keyword in &matrix.keywords {
+    html.push_str(&format!(
+      "                    <th>{}</th>\n",
+      escape_html(keyword)
+    ));
+  }
+  html.push_str(
+    r#"                </tr>
+            </thead>
+            <tbody>
+"#,
+  );
+
+  for (first, row) in matrix.keywords.iter().zip(&matrix.counts) {
+    html.push_str(&format!(
+      "                <tr>\n                    <th>{}</th>\n",
+      escape_html(first)
+    ));
+    for (next, count) in matrix.keywords.iter().zip(row) {
+      let opacity = *count as f64 / max as f64;
+      html.push_str(&format!(
+        "                    <td style=\"background-color: rgba(0, 122, 204, {opacity:.2});\" title=\"{} {}: {count}\">{count}</td>\n",
+        escape_html(first),
+        escape_html(next)
+      ));
+    }
+    html.push_str("                </tr>\n");
+  }
+
+  html.push_str(
+    r#"            </tbody>
+        </table>
+"#,
+  );
+  html
+}
+
+/// Comment ratio, doc coverage and a table of the markers.
+fn html_comments(result: &AnalysisResult) -> String {
+  let coverage = result
+    .docs
+    .percentage()
+    .map_or("-".to_string(), |percentage| format!("{percentage:.1}%"));
+  let markers = result.markers();
+  let mut html = html_summary(
+    "Comments",
+    &[
+      (
+        format!("{:.2}", result.lines.comment_ratio()),
+        "Comment Lines per Code Line",
+      ),
+      (coverage, "Doc Coverage"),
+      (markers.len().to_string(), "TODO/FIXME/HACK/XXX Markers"),
+    ],
+  );
+  if markers.is_empty() {
+    return html;
+  }
+
+  html.push_str(
+    r#"        <table class="keywords-table">
+            <thead>
+                <tr>
+                    <th>Location</th>
+                    <th>Marker</th>
+                    <th>Owner</th>
+                    <th>Text</th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+  );
+  for (path, marker) in markers {
+    html.push_str(&format!(
+      r#"                <tr>
+                    <td class="keyword">{}:{}</td>
+                    <td class="count">{}</td>
+                    <td>{}</td>
+                    <td>{}</td>
+                </tr>
+"#,
+      escape_html(path),
+      marker.line,
+      marker.kind,
+      escape_html(marker.owner.as_deref().unwrap_or("")),
+      escape_html(&marker.text)
+    ));
+  }
+  html.push_str(
+    r#"            </tbody>
+        </table>
+"#,
+  );
+  html
+}
+
+/// A `cloc`-style table of `(language, files, lines)` rows.
+fn html_lines_table<'a>(
+  rows: impl IntoIterator<Item = (&'a str, usize, &'a LineCounts)>,
+) -> String {
+  let mut html = String::from(
+    r#"        <table class="keywords-table">
+            <thead>
+                <tr>
+                    <th>Language</th>
+                    <th>Files</th>
+                    <th>Blank</th>
+                    <th>Comment</th>
+                    <th>Code</th>
+                    <th>Mixed</th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+  );
+  for (language, files, lines) in rows {
+    html.push_str(&format!(
+      r#"                <tr>
+                    <td class="keyword">{}</td>
+                    <td>{files}</td>
+                    <td>{}</td>
+                    <td>{}</td>
+                    <td class="count">{}</td>
+                    <td>{}</td>
+                </tr>
+"#,
+      escape_html(language),
+      lines.blank,
+      lines.comment,
+      lines.code,
+      lines.mixed
+    ));
+  }
+  html.push_str(
+    r#"            </tbody>
+        </table>
+"#,
+  );
+  html
+}
+
+/// Line counts and density statistics.
+fn html_density(lines: &LineCounts, density: &Density) -> String {
+  let mut html = html_summary(
+    "Density",
+    &[
+      (
+        format!("{:.1}", density.keywords_per_kloc),
+        "Keywords per 1000 Code Lines",
+      ),
+      (lines.comment.to_string(), "Comment Lines"),
+      (lines.blank.to_string(), "Blank Lines"),
+    ],
+  );
+
+  html.push_str(
+    r#"        <table class="keywords-table">
+            <thead>
+                <tr>
+                    <th>Per File</th>
+                    <th>Mean</th>
+                    <th>Median</th>
+                    <th>P75</th>
+                    <th>P90</th>
+                    <th>P95</th>
+                    <th>Max</th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+  );
+  for (label, distribution) in [
+    ("Keywords", &density.keywords_per_file),
+    (
+      "Keywords per 1000 code lines",
+      &density.file_keywords_per_kloc,
+    ),
+  ] {
+    html.push_str(&format!(
+      r#"                <tr>
+                    <td class="keyword">{label}</td>
+                    <td>{:.1}</td>
+                    <td>{:.1}</td>
+                    <td>{:.1}</td>
+                    <td>{:.1}</td>
+                    <td>{:.1}</td>
+                    <td>{:.1}</td>
+                </tr>
+"#,
+      distribution.mean,
+      distribution.median,
+      distribution.p75,
+      distribution.p90,
+      distribution.p95,
+      distribution.max
+    ));
+  }
+  html.push_str(
+    r#"            </tbody>
+        </table>
+"#,
+  );
+  html
+}
+
+fn category_rows(totals: &[CategoryTotal]) -> Vec<(String, usize)> {
+  totals
+    .iter()
+    .map(|total| {
+      (
+        format!("{} ({:.1}%)", total.name, total.percentage),
+        total.count,
+      )
+    })
+    .collect()
+}
+
+/// The directory roll-up as nested `<details>` elements, with the top level
+/// expanded.
+fn html_directory_tree(tree: &DirectoryNode) -> String {
+  let mut html = String::from("        <h3>Directories</h3>\n        <div class=\"tree\">\n");
+  html_tree_node(tree, 0, &mut html);
+  html.push_str("        </div>\n");
+  html
+}
+
+fn html_tree_node(node: &DirectoryNode, depth: usize, html: &mut String) {
+  let indent = " ".repeat(12 + depth * 4);
+  let package = node.package.as_ref().map_or(String::new(), |package| {
+    format!(
+      r#" <span class="package">{} {}</span>"#,
+      escape_html(&package.manifest),
+      escape_html(&package.name)
+    )
+  });
+  let summary = format!(
+    r#"<span class="keyword">{}/</span> <span class="count">{}</span> keywords in {} files, {} lines ({} code, {} comment, {} blank){package}"#,
+    escape_html(&node.name),
+    node.total_keywords,
+    node.file_count,
+    node.line_count,
+    node.lines.code,
+    node.lines.comment,
+    node.lines.blank
+  );
+
+  if node.children.is_empty() {
+    html.push_str(&format!(
+      "{indent}<div class=\"tree-leaf\">{summary}</div>\n"
+    ));
+    return;
+  }
+
+  let open = if depth == 0 { " open" } else { "" };
+  html.push_str(&format!(
+    "{indent}<details{open}>\n{indent}    <summary>{summary}</summary>\n"
+  ));
+  for child in &node.children {
+    html_tree_node(child, depth + 1, html);
+  }
+  html.push_str(&format!("{indent}</details>\n"));
+}
+
+/// Every analyzed file with its line counts, keyword total, density and top
+/// keywords.
+fn html_file_table(result: &AnalysisResult) -> String {
+  let mut html = String::from(
+    r#"        <table class="keywords-table">
+            <thead>
+                <tr>
+                    <th>File</th>
+                    <th>Lines</th>
+                    <th>Code</th>
+                    <th>Comment</th>
+                    <th>Keywords</th>
+                    <th>Per 1000 Code Lines</th>
+                    <th>Top Keywords</th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+  );
+
+  for file in result.files_sorted_by(None) {
+    html.push_str(&format!(
+      r#"                <tr>
+                    <td class="keyword">{}</td>
+                    <td>{}</td>
+                    <td>{}</td>
+                    <td>{}</td>
+                    <td class="count">{}</td>
+                    <td>{:.1}</td>
+                    <td>{}</td>
+                </tr>
+"#,
+      escape_html(&file.path),
+      file.line_count,
+      file.lines.code,
+      file.lines.comment,
+      file.total_keywords,
+      file.keywords_per_kloc(),
+      escape_html(&top_keywords(file, 5))
+    ));
+  }
+
+  html.push_str(
+    r#"            </tbody>
+        </table>
+"#,
+  );
+  html
+}
+
+pub fn generate_html_content(result: &AnalysisResult) -> String {
+  html_page(
+    &format!("{} Keyword Analysis Results", result.language_name),
+    &html_result_section(result),
+  )
+}
+
+pub fn generate_repository_html_content(analysis: &RepositoryAnalysis) -> String {
+  let mut body = html_summary(
+    "Repository Summary",
+    &[
+      (analysis.file_count.to_string(), "Files Analyzed"),
+      (analysis.total_keywords.to_string(), "Total Keywords Found"),
+      (analysis.results.len().to_string(), "Languages"),
+      (analysis.lines.code.to_string(), "Lines of Code"),
+    ],
+  );
+  body.push_str(&html_density(&analysis.lines, &analysis.density()));
+
+  let language_rows: Vec<(String, usize)> = analysis
+    .results
+    .iter()
+    .map(|result| (result.language_name.clone(), result.total_keywords))
+    .collect();
+  if !language_rows.is_empty() {
+    body.push_str(&html_bar_table(["Language", "Keywords"], &language_rows));
+  }
+  body.push_str(&html_lines_table(
+    analysis
+      .results
+      .iter()
+      .map(|result| {
+        (
+          result.language_name.as_str(),
+          result.file_count,
+          &result.lines,
+        )
+      })
+      .chain([("Total", analysis.file_count, &analysis.lines)]),
+  ));
+  let categories = analysis.category_totals();
+  if !categories.is_empty() {
+    body.push_str(&html_bar_table(
+      ["Category", "Keywords"],
+      &category_rows(&categories),
+    ));
+  }
+  body.push_str(&html_complexity(
+    analysis
+      .most_complex_files(TOP_FILES)
+      .into_iter()
+      .map(|(result, file)| (file, Some(result.language_name.as_str()))),
+  ));
+  body.push_str(&html_skipped(&analysis.skipped));
+
+  for result in &analysis.results {
+    body.push_str(&format!(
+      "        <h2 class=\"language\">{}</h2>\n",
+      escape_html(&result.language_name)
+    ));
+    body.push_str(&html_result_section(result));
+  }
+
+  html_page("Repository Keyword Analysis Results", &body)
+}
+
+struct Bar {
+  label: String,
+  value: usize,
+  tooltip: String,
+}
+
+pub fn generate_svg_content(result: &AnalysisResult) -> Result<String, AnalyzerError> {
+  let bars: Vec<Bar> = nonzero_counts(result)
+    .into_iter()
+    .take(20)
+    .map(|(keyword, count)| {
+      let top_files: Vec<String> = result
+        .files_sorted_by(Some(keyword))
+        .into_iter()
+        .filter(|file| file.count(keyword) > 0)
+        .take(3)
+        .map(|file| format!("{} {}", file.path, file.count(keyword)))
+        .collect();
+      Bar {
+        label: keyword.clone(),
+        value: *count,
+        tooltip: format!(
+          "{keyword} ({}): {count} (top files: {})",
+          result.category_of(keyword),
+          top_files.join(", ")
+        ),
+      }
+    })
+    .collect();
+
+  if bars.is_empty() {
+    return Err(AnalyzerError::NothingToVisualize);
+  }
+
+  let subtitle = format!(
+    "Files: {}{} | Total Keywords: {} | {:.1} per 1000 Code Lines | Top {} Keywords",
+    result.file_count,
+    svg_skipped(&result.skipped),
+    result.total_keywords,
+    result.density().keywords_per_kloc,
+    bars.len()
+  );
+  let mut sections = Vec::new();
+  let matrix = result.cooccurrence(MATRIX_SIZE);
+  if !matrix.is_empty() {
+    sections.push(svg_cooccurrence(&matrix, SVG_WIDTH));
+  }
+  sections.extend(svg_complex_files(
+    result
+      .most_complex_files(TOP_FILES)
+      .into_iter()
+      .map(|file| (file, None)),
+  ));
+  Ok(svg_bar_chart(
+    &format!("{} Keyword Analysis", result.language_name),
+    &subtitle,
+    &bars,
+    &sections,
+  ))
+}
+
+/// One bar per language, sized by its keyword total.
+pub fn generate_repository_svg_content(
+  analysis: &RepositoryAnalysis,
+) -> Result<String, AnalyzerError> {
+  let bars: Vec<Bar> = analysis
+    .results
+    .iter()
+    .filter(|result| result.total_keywords > 0)
+    .map(|result| {
+      let top: Vec<String> = nonzero_counts(result)
+        .into_iter()
+        .take(5)
+        .map(|(keyword, count)| format!("{keyword} {count}"))
+        .collect();
+      Bar {
+        label: result.language_name.clone(),
+        value: result.total_keywords,
+        tooltip: format!(
+          "{}: {} keywords in {} files (top: {})",
+          result.language_name,
+          result.total_keywords,
+          result.file_count,
+          top.join(", ")
+        ),
+      }
+    })
+    .collect();
+
+  if bars.is_empty() {
+    return Err(AnalyzerError::NothingToVisualize);
+  }
+
+  let subtitle = format!(
+    "Files: {}{} | Total Keywords: {} | {:.1} per 1000 Code Lines | Languages: {}",
+    analysis.file_count,
+    svg_skipped(&analysis.skipped),
+    analysis.total_keywords,
+    analysis.density().keywords_per_kloc,
+    bars.len()
+  );
+  let sections: Vec<SvgSection> = svg_complex_files(
+    analysis
+      .most_complex_files(TOP_FILES)
+      .into_iter()
+      .map(|(result, file)| (file, Some(result.language_name.as_str()))),
+  )
+  .into_iter()
+  .collect();
+  Ok(svg_bar_chart(
+    "Repository Keyword Analysis",
+    &subtitle,
+    &bars,
+    &sections,
+  ))
+}
+
+const SVG_WIDTH: usize = 1000;
+
+/// ` (N skipped)` after the file count of a subtitle, when files were skipped.
+fn svg_skipped(skipped: &[SkippedFile]) -> String {
+  if skipped.is_empty() {
+    String::new()
+  } else {
+    format!(" ({} skipped)", skipped.len())
+  }
+}
+
+/// Part of a chart drawn below the bars, in coordinates relative to its own
+/// top edge.
+struct SvgSection {
+  height: usize,
+  content: String,
+}
+
+/// A bar chart of `bars`, followed by `sections` one below the other.
+fn svg_bar_chart(title: &str, subtitle: &str, bars: &[Bar], sections: &[SvgSection]) -> String {
+  let max_count = bars.iter().map(|bar| bar.value).max().unwrap_or(1).max(1);
+  let width = SVG_WIDTH;
+  let chart_bottom = 600;
+  let height = chart_bottom + sections.iter().map(|section| section.height).sum::<usize>();
+  let margin = 60;
+  let chart_height = chart_bottom - 2 * margin - 50;
+  let bar_width = (width - 2 * margin) / bars.len().max(1);
+  let colors = generate_color_palette(bars.len());
+
+  let mut svg = String::new();
+  svg.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+  svg.push_str(&format!(
+    "<svg width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\" xmlns=\"http://www.w3.org/2000/svg\">\n"
+  ));
+  svg.push_str(
+    r#"    <defs>
+        <style>
+            .title { font-family: 'Arial', sans-serif; font-size: 24px; font-weight: bold; text-anchor: middle; fill: #333; }
+            .subtitle { font-family: 'Arial', sans-serif; font-size: 14px; text-anchor: middle; fill: #666; }
+            .bar { cursor: pointer; transition: opacity 0.2s; }
+            .bar:hover { opacity: 0.8; }
+            .bar-label { font-family: 'Arial', sans-serif; font-size: 12px; text-anchor: middle; fill: #333; }
+            .count-label { font-family: 'Arial', sans-serif; font-size: 11px; text-anchor: middle; fill: #fff; font-weight: bold; }
+            .axis { stroke: #ccc; stroke-width: 1; }
+            .grid { stroke: #eee; stroke-width: 0.5; }
+            .cell-label { font-family: 'Arial', sans-serif; font-size: 11px; text-anchor: middle; fill: #333; }
+        </style>
+    </defs>
+"#,
+  );
+  svg.push_str("    <rect width=\"100%\" height=\"100%\" fill=\"#fafafa\"/>\n");
+  svg.push_str(&format!(
+    "    <text x=\"{}\" y=\"30\" class=\"title\">{}</text>\n",
+    width / 2,
+    escape_html(title)
+  ));
+  svg.push_str(&format!(
+    "    <text x=\"{}\" y=\"50\" class=\"subtitle\">{}</text>\n",
+    width / 2,
+    escape_html(subtitle)
+  ));
+
+  svg.push_str(&generate_grid_lines(margin, margin + 30, chart_height));
+
+  for (i, bar) in bars.iter().enumerate() {
+    let x = margin + i * bar_width;
+    let bar_height = ((bar.value as f64) / (max_count as f64) * chart_height as f64) as usize;
+    let y = margin + 30 + chart_height - bar_height;
+    let color = &colors[i % colors.len()];
+
+    svg.push_str(&format!(
+      "    <rect class=\"bar\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\">\n",
+      x + 2,
+      y,
+      bar_width - 4,
+      bar_height,
+      color
+    ));
+    svg.push_str(&format!(
+      "        <title>{}</title>\n",
+      escape_html(&bar.tooltip)
+    ));
+    svg.push_str("    </rect>\n");
+
+    if bar_height > 25 {
+      svg.push_str(&format!(
+        "    <text x=\"{}\" y=\"{}\" class=\"count-label\">{}</text>\n",
+        x + bar_width / 2,
+        y + 15,
+        bar.value
+      ));
+    }
+
+    let label_y = margin + 30 + chart_height + 15;
+    let truncated_label = if bar.label.chars().count() > 8 {
+      format!("{}...", bar.label.chars().take(5).collect::<String>())
+    } else {
+      bar.label.clone()
+    };
+
+    svg.push_str(&format!(
+      "    <text x=\"{}\" y=\"{}\" class=\"bar-label\" transform=\"rotate(-45, {}, {})\">{}</text>\n",
+      x + bar_width / 2,
+      label_y,
+      x + bar_width / 2,
+      label_y,
+      escape_html(&truncated_label)
+    ));
+  }
+
+  svg.push_str(&format!(
+    "    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" class=\"axis\"/>\n",
+    margin,
+    margin + 30,
+    margin,
+    margin + 30 + chart_height
+  ));
+  svg.push_str(&format!(
+    "    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" class=\"axis\"/>\n",
+    margin,
+    margin + 30 + chart_height,
+    margin + (width - 2 * margin),
+    margin + 30 + chart_height
+  ));
+
+  svg.push_str(&generate_y_axis_labels(
+    margin - 10,
+    margin + 30,
+    chart_height,
+    max_count,
+  ));
+  let mut top = chart_bottom;
+  for section in sections {
+    svg.push_str(&format!("    <g transform=\"translate(0, {top})\">\n"));
+    svg.push_str(&section.content);
+    svg.push_str("    </g>\n");
+    top += section.height;
+  }
+  svg.push_str("</svg>\n");
+
+  svg
+}
+
+/// Space above the co-occurrence heat map for its title and column labels.
+const SVG_MATRIX_TOP: usize = 110;
+const SVG_MATRIX_CELL: usize = 40;
+
+/// The co-occurrence matrix as a grid of cells shaded by count, centered
+/// horizontally.
+fn svg_cooccurrence(matrix: &CooccurrenceMatrix, width: usize) -> SvgSection {
+  let size = matrix.keywords.len();
+  let max = matrix.max().max(1);
+  let left = (width - size * SVG_MATRIX_CELL) / 2;
+  let grid_top = SVG_MATRIX_TOP;
+
+  let mut svg = format!(
+    "    <text x=\"{}\" y=\"20\" class=\"subtitle\">Keyword co-occurrence (row followed by column)</text>\n",
+    width / 2
+  );
+  for (i, keyword) in matrix.keywords.iter().enumerate() {
+    let center = left + i * SVG_MATRIX_CELL + SVG_MATRIX_CELL / 2;
+    svg.push_str(&format!(
+      "    <text x=\"{center}\" y=\"{}\" class=\"bar-label\" transform=\"rotate(-45, {center}, {})\">{}</text>\n",
+      grid_top - 10,
+      grid_top - 10,
+      escape_html(keyword)
+    ));
+    svg.push_str(&format!(
+      "    <text x=\"{}\" y=\"{}\" class=\"bar-label\" text-anchor=\"end\">{}</text>\n",
+      left - 8,
+      grid_top + i * SVG_MATRIX_CELL + SVG_MATRIX_CELL / 2 + 4,
+      escape_html(keyword)
+    ));
+  }
+
+  for (row, (first, counts)) in matrix.keywords.iter().zip(&matrix.counts).enumerate() {
+    for (column, (next, count)) in matrix.keywords.iter().zip(counts).enumerate() {
+      let x = left + column * SVG_MATRIX_CELL;
+      let y = grid_top + row * SVG_MATRIX_CELL;
+      svg.push_str(&format!(
+        "    <rect x=\"{x}\" y=\"{y}\" width=\"{SVG_MATRIX_CELL}\" height=\"{SVG_MATRIX_CELL}\" fill=\"#007acc\" fill-opacity=\"{:.2}\" stroke=\"#fff\">\n",
+        0.05 + 0.95 * *count as f64 / max as f64
+      ));
+      svg.push_str(&format!(
+        "        <title>{} {}: {count}</title>\n    </rect>\n",
+        escape_html(first),
+        escape_html(next)
+      ));
+      if *count > 0 {
+        svg.push_str(&format!(
+          "    <text x=\"{}\" y=\"{}\" class=\"cell-label\">{count}</text>\n",
+          x + SVG_MATRIX_CELL / 2,
+          y + SVG_MATRIX_CELL / 2 + 4
+        ));
+      }
+    }
+  }
+
+  SvgSection {
+    height: SVG_MATRIX_TOP + size * SVG_MATRIX_CELL + 40,
+    content: svg,
+  }
+}
+
+const SVG_ROW_HEIGHT: usize = 24;
+
+/// Horizontal bars for the most complex files, or `None` without any.
+fn svg_complex_files<'a>(
+  files: impl Iterator<Item = (&'a FileAnalysis, Option<&'a str>)>,
+) -> Option<SvgSection> {
+  let files: Vec<_> = files.collect();
+  let max = files.first()?.0.complexity.complexity.max(1);
+  let label_right = 400;
+  let bar_left = label_right + 10;
+  let bar_span = SVG_WIDTH - bar_left - 100;
+
+  let mut svg = format!(
+    "    <text x=\"{}\" y=\"20\" class=\"subtitle\">Most complex files</text>\n",
+    SVG_WIDTH / 2
+  );
+  for (i, (file, language)) in files.iter().enumerate() {
+    let y = 40 + i * SVG_ROW_HEIGHT;
+    let complexity = file.complexity.complexity;
+    let label: String = if file.path.chars().count() > 50 {
+      let tail: Vec<char> = file.path.chars().rev().take(47).collect();
+      format!("...{}", tail.into_iter().rev().collect::<String>())
+    } else {
+      file.path.clone()
+    };
+    svg.push_str(&format!(
+      "    <text x=\"{label_right}\" y=\"{}\" class=\"bar-label\" text-anchor=\"end\">{}</text>\n",
+      y + SVG_ROW_HEIGHT / 2 + 4,
+      escape_html(&label)
+    ));
+    svg.push_str(&format!(
+      "    <rect class=\"bar\" x=\"{bar_left}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#dc3545\">\n",
+      y + 3,
+      (complexity * bar_span / max).max(1),
+      SVG_ROW_HEIGHT - 6
+    ));
+    svg.push_str(&format!(
+      "        <title>{}: complexity {complexity}</title>\n    </rect>\n",
+      escape_html(&complex_file_label(file, *language))
+    ));
+    svg.push_str(&format!(
+      "    <text x=\"{}\" y=\"{}\" class=\"bar-label\" text-anchor=\"start\">{complexity}</text>\n",
+      bar_left + complexity * bar_span / max + 6,
+      y + SVG_ROW_HEIGHT / 2 + 4
+    ));
+  }
+
+  Some(SvgSection {
+    height: 40 + files.len() * SVG_ROW_HEIGHT + 30,
+    content: svg,
+  })
+}
+
+fn generate_color_palette(count: usize) -> Vec<String> {
+  let base_colors = [
+    "#3498db", "#e74c3c", "#2ecc71", "#f39c12", "#9b59b6", "#1abc9c", "#34495e", "#e67e22",
+    "#95a5a6", "#f1c40f", "#c0392b", "#27ae60", "#8e44ad", "#16a085", "#2c3e50", "#d35400",
+    "#7f8c8d", "#f4d03f", "#85c1e9", "#f8c471",
+  ];
+
+  (0..count)
+    .map(|i| base_colors[i % base_colors.len()].to_string())
+    .collect()
+}
+
+fn generate_grid_lines(x_start: usize, y_start: usize, height: usize) -> String {
+  let mut lines = String::new();
+  let grid_count = 5;
+
+  for i in 0..=grid_count {
+    let y = y_start + (height * i) / grid_count;
+    lines.push_str(&format!(
+      "    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" class=\"grid\"/>\n",
+      x_start,
+      y,
+      x_start + 880,
+      y
+    ));
+  }
+
+  lines
+}
+
+fn generate_y_axis_labels(x: usize, y_start: usize, height: usize, max_value: usize) -> String {
+  let mut labels = String::new();
+  let label_count = 5;
+
+  for i in 0..=label_count {
+    let y = y_start + height - (height * i) / label_count;
+    let value = (max_value * i) / label_count;
+    labels.push_str(&format!(
+      "    <text x=\"{}\" y=\"{}\" class=\"bar-label\" text-anchor=\"end\">{}</text>\n",
+      x,
+      y + 4,
+      value
+    ));
+  }
+
+  labels
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::complexity::{FileComplexity, FunctionComplexity};
+  use crate::Language;
+
+  fn sample_file(path: &str, counts: &[(&str, usize)]) -> FileAnalysis {
+    let counts = counts
+      .iter()
+      .map(|(keyword, count)| (keyword.to_string(), *count))
+      .collect();
+    FileAnalysis::new(path.to_string(), 10, counts)
+  }
+
+  fn sample_result(language: Language, counts: &[(&str, usize)]) -> AnalysisResult {
+    let mut result = AnalysisResult::new(language.spec().as_ref());
+    result.add_file(sample_file("sample", counts));
+    result
+  }
+
+  #[test]
+  fn test_generate_json_content() {
+    let result = sample_result(Language::Rust, &[("fn", 3), ("let", 5), ("mut", 0)]);
+    let json: Value = serde_json::from_str(&generate_json_content(&result)).unwrap();
+    assert_eq!(json["files_analyzed"], 1);
+    assert_eq!(json["total_keywords"], 8);
+
+    // Keywords keep their count order and zero counts are left out
+    let keywords: Vec<_> = json["keywords"].as_object().unwrap().keys().collect();
+    assert_eq!(keywords, ["let", "fn"]);
+  }
+
+  #[test]
+  fn test_generate_repository_content() {
+    let analysis = RepositoryAnalysis::from_results(vec![
+      sample_result(Language::Python, &[("def", 2)]),
+      sample_result(Language::Rust, &[("fn", 4)]),
+    ]);
+
+    let json: Value = serde_json::from_str(&generate_repository_json_content(&analysis)).unwrap();
+    assert_eq!(json["files_analyzed"], 2);
+    assert_eq!(json["total_keywords"], 6);
+    assert_eq!(json["languages"][0]["language"], "python");
+    assert_eq!(json["languages"][1]["keywords"]["fn"], 4);
+
+    let csv = generate_repository_csv_content(&analysis);
+    assert!(
+      csv.starts_with("language,kind,path,package,keyword,count\n_all,total,,,_files_analyzed,2\n")
+    );
+    assert!(csv.contains("rust,total,,,fn,4\n"));
+    assert!(csv.contains("rust,directory,.,,fn,4\n"));
+    assert!(csv.contains("rust,file,sample,,fn,4\n"));
+
+    let html = generate_repository_html_content(&analysis);
+    assert!(html.contains("<h2 class=\"language\">Python</h2>"));
+    assert!(generate_repository_svg_content(&analysis)
+      .unwrap()
+      .contains("<title>Rust: 4 keywords in 1 files (top: fn 4)</title>"));
+  }
+
+  #[test]
+  fn test_per_file_breakdown() {
+    let mut result = AnalysisResult::new(Language::Rust.spec().as_ref());
+    result.add_file(sample_file("src/a.rs", &[("fn", 1), ("unsafe", 4)]));
+    result.add_file(sample_file("src/b.rs", &[("fn", 9)]));
+    result.add_file(sample_file("src/c,d.rs", &[("unsafe", 2)]));
+    assert_eq!(result.total_keywords, 16);
+    assert_eq!(
+      result.files_analyzed,
+      ["src/a.rs", "src/b.rs", "src/c,d.rs"]
+    );
+
+    let paths = |keyword| -> Vec<&str> {
+      result
+        .files_sorted_by(keyword)
+        .iter()
+        .map(|file| file.path.as_str())
+        .collect()
+    };
+    assert_eq!(paths(None), ["src/b.rs", "src/a.rs", "src/c,d.rs"]);
+    assert_eq!(
+      paths(Some("unsafe")),
+      ["src/a.rs", "src/c,d.rs", "src/b.rs"]
+    );
+
+    let json: Value = serde_json::from_str(&generate_json_content(&result)).unwrap();
+    assert_eq!(json["files"][0]["path"], "src/b.rs");
+    assert_eq!(json["files"][1]["lines"], 10);
+    assert_eq!(json["files"][1]["keywords"]["unsafe"], 4);
+
+    let csv = generate_csv_content(&result);
+    assert!(csv.starts_with("kind,path,package,keyword,count\ntotal,,,_files_analyzed,3\n"));
+    assert!(csv.contains("file,src/a.rs,,unsafe,4\n"));
+    assert!(csv.contains("file,\"src/c,d.rs\",,_lines,10\n"));
+
+    let plain = format_per_file_plain(&result, Some("unsafe"));
+    let rows: Vec<&str> = plain.lines().skip(5).collect();
+    assert!(rows[0].ends_with("src/a.rs") && rows[0].trim_start().starts_with('4'));
+    assert!(format_plain(&result).contains("Top files by keywords:"));
+
+    assert!(generate_html_content(&result).contains("<td>unsafe 4, fn 1</td>"));
+    assert!(generate_svg_content(&result)
+      .unwrap()
+      .contains("<title>unsafe (Other keywords): 6 (top files: src/a.rs 4, src/c,d.rs 2)</title>"));
+  }
+
+  #[test]
+  fn test_directory_rollup() {
+    let mut result = AnalysisResult::new(Language::Rust.spec().as_ref());
+    result.add_file(sample_file("core/src/lib.rs", &[("unsafe", 3)]));
+    result.add_file(sample_file("core/build.rs", &[("fn", 1)]));
+    result.add_file(sample_file("tools/gen.rs", &[("fn", 2)]));
+    result.packages.push(crate::Package {
+      path: "core".to_string(),
+      name: "core-crate".to_string(),
+      manifest: "Cargo.toml".to_string(),
+    });
+
+    let json: Value = serde_json::from_str(&generate_json_content(&result)).unwrap();
+    let directories: Vec<_> = json["directories"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|dir| {
+        (
+          dir["path"].as_str().unwrap(),
+          dir["total_keywords"].as_u64().unwrap(),
+        )
+      })
+      .collect();
+    assert_eq!(
+      directories,
+      [(".", 6), ("core", 4), ("core/src", 3), ("tools", 2)]
+    );
+    assert_eq!(json["directories"][2]["package"], "core-crate");
+    assert_eq!(json["packages"][0]["manifest"], "Cargo.toml");
+
+    let csv = generate_csv_content(&result);
+    assert!(csv.contains("directory,core/src,core-crate,unsafe,3\n"));
+    assert!(csv.contains("directory,tools,,_files,1\n"));
+    assert!(csv.contains("file,core/build.rs,core-crate,fn,1\n"));
+
+    let html = generate_html_content(&result);
+    assert!(html.contains("<details open>"));
+    assert!(html.contains(r#"<span class="package">Cargo.toml core-crate</span>"#));
+    assert!(html.contains(r#"<div class="tree-leaf"><span class="keyword">src/</span>"#));
+
+    let plain = format_tree_plain(&result);
+    assert!(plain.contains(" 20    core  [Cargo.toml core-crate]\n"));
+    assert!(plain.contains(" 10      src\n"));
+  }
+
+  #[test]
+  fn test_category_totals() {
+    let mut result = sample_result(
+      Language::Rust,
+      &[("if", 3), ("match", 1), ("async", 1), ("fn", 5)],
+    );
+    let totals: Vec<_> = result
+      .category_totals()
+      .into_iter()
+      .map(|total| (total.name, total.count, total.percentage))
+      .collect();
+    assert_eq!(
+      totals,
+      [
+        ("Function-related".to_string(), 5, 50.0),
+        ("Control flow".to_string(), 4, 40.0),
+        ("Concurrency".to_string(), 1, 10.0),
+      ]
+    );
+
+    assert!(format_plain(&result).contains("Control flow             : 4 (40.0%)\n"));
+    let json: Value = serde_json::from_str(&generate_json_content(&result)).unwrap();
+    assert_eq!(json["categories"][1]["name"], "Control flow");
+    assert_eq!(json["categories"][1]["percentage"], 40.0);
+    assert!(generate_csv_content(&result).contains("category,,,Concurrency,1\n"));
+    assert!(generate_html_content(&result).contains("Control flow (40.0%)"));
+
+    assert!(result.retain_category("control-flow").is_ok());
+    assert_eq!(result.total_keywords, 4);
+    assert_eq!(result.files[0].total_keywords, 4);
+    assert_eq!(result.keyword_counts.get("fn"), None);
+    assert!(result.retain_category("loops").is_err());
+
+    let mut analysis = RepositoryAnalysis::from_results(vec![
+      sample_result(Language::Python, &[("def", 2), ("import", 2)]),
+      sample_result(Language::Golang, &[("go", 2), ("func", 2)]),
+    ]);
+    assert_eq!(analysis.category_totals()[0].name, "Function-related");
+    assert!(analysis.retain_category("Concurrency").is_ok());
+    assert_eq!(analysis.total_keywords, 2);
+    assert!(analysis.retain_category("Primitive types").is_err());
+  }
+
+  #[test]
+  fn test_density_metrics() {
+    let mut result = AnalysisResult::new(Language::Rust.spec().as_ref());
+    let lines = |code, comment| LineCounts {
+      total: code + comment + 10,
+      code,
+      comment,
+      blank: 10,
+      mixed: 0,
+    };
+    result.add_file(sample_file("a.rs", &[("fn", 2)]).with_lines(lines(20, 5)));
+    result.add_file(sample_file("b.rs", &[("fn", 6), ("if", 2)]).with_lines(lines(80, 0)));
+    assert_eq!(
+      result.lines,
+      LineCounts {
+        total: 125,
+        code: 100,
+        comment: 5,
+        blank: 20,
+        mixed: 0,
+      }
+    );
+
+    let plain = format_plain(&result);
+    assert!(plain.contains("Lines: 125 (code 100, comment 5, blank 20, mixed 0)\n"));
+    assert!(plain.contains("Keywords per 1000 code lines: 100.0\n"));
+    assert!(plain.contains("Keywords per file: mean 5.0, median 5.0, p75 6.5,"));
+
+    let json: Value = serde_json::from_str(&generate_json_content(&result)).unwrap();
+    assert_eq!(json["lines"]["code"], 100);
+    assert_eq!(json["density"]["keywords_per_kloc"], 100.0);
+    assert_eq!(json["density"]["file_keywords_per_kloc"]["max"], 100.0);
+    assert_eq!(json["files"][0]["keywords_per_kloc"], 100.0);
+    assert_eq!(json["files"][1]["comment_lines"], 5);
+
+    let csv = generate_csv_content(&result);
+    assert!(csv.contains("total,,,_code_lines,100\n"));
+    assert!(csv.contains("density,,,keywords_per_file_median,5.00\n"));
+    assert!(csv.contains("file,a.rs,,_keywords_per_kloc,100.00\n"));
+
+    let html = generate_html_content(&result);
+    assert!(html.contains("Keywords per 1000 Code Lines"));
+    assert!(format_per_file_plain(&result, None).contains("100.0  a.rs\n"));
+  }
+
+  #[test]
+  fn test_line_statistics() {
+    let lines = |code, comment, blank, mixed| LineCounts {
+      total: code + comment + blank,
+      code,
+      comment,
+      blank,
+      mixed,
+    };
+    let mut rust = AnalysisResult::new(Language::Rust.spec().as_ref());
+    rust.add_file(sample_file("src/lib.rs", &[("fn", 1)]).with_lines(lines(50, 10, 5, 2)));
+    rust.add_file(sample_file("src/ffi/mod.rs", &[]).with_lines(lines(30, 0, 3, 1)));
+    let mut python = AnalysisResult::new(Language::Python.spec().as_ref());
+    python.add_file(sample_file("tools/gen.py", &[("def", 1)]).with_lines(lines(8, 2, 1, 0)));
+    let analysis = RepositoryAnalysis::from_results(vec![rust, python]);
+    assert_eq!(analysis.lines, lines(88, 12, 9, 3));
+
+    let plain = format_repository_lines_plain(&analysis);
+    let row = |label: &str| {
+      plain
+        .lines()
+        .find(|line| line.starts_with(label))
+        .map(|line| {
+          line
+            .split_whitespace()
+            .skip(1)
+            .collect::<Vec<_>>()
+            .join(" ")
+        })
+    };
+    assert_eq!(row("Rust").as_deref(), Some("2 8 10 80 3"));
+    assert_eq!(row("SUM:").as_deref(), Some("3 9 12 88 3"));
+    assert_eq!(row("    ffi").as_deref(), Some("1 3 0 30 1"));
+
+    let rust = analysis.find("rust").unwrap();
+    let json: Value = serde_json::from_str(&generate_json_content(rust)).unwrap();
+    assert_eq!(json["lines"]["mixed"], 3);
+    assert_eq!(json["directories"][1]["path"], "src");
+    assert_eq!(json["directories"][1]["comment_lines"], 10);
+    assert_eq!(json["files"][0]["mixed_lines"], 2);
+
+    let csv = generate_repository_csv_content(&analysis);
+    assert!(csv.contains("_all,total,,,_code_lines,88\n"));
+    assert!(csv.contains("rust,directory,src/ffi,,_blank_lines,3\n"));
+    assert!(csv.contains("python,file,tools/gen.py,,_comment_lines,2\n"));
+
+    let html = generate_repository_html_content(&analysis);
+    assert!(html.contains("<td class=\"keyword\">Total</td>"));
+    assert!(
+      html.contains("80 lines (80 code, 0 comment, 0 blank)")
+        || html.contains("(80 code, 10 comment, 8 blank)")
+    );
+  }
+
+  #[test]
+  fn test_keyword_sequences() {
+    let source = "pub(crate) unsafe fn a() {}\npub unsafe fn b() {}\n";
+    let spec = Language::Rust.spec();
+    let tokens = spec.tokenize(source);
+    let counts =
+      crate::sequences::count_sequences(spec.as_ref(), source, &tokens, Default::default());
+    let mut result = AnalysisResult::new(spec.as_ref());
+    result.add_file(sample_file("src/lib.rs", &[("fn", 2)]).with_sequences(counts));
+
+    let plain = format_plain(&result);
+    assert!(plain.contains(
+      "Top keyword sequences (2 keywords, window 2):\n\
+       unsafe fn                : 2\n\
+       crate unsafe             : 1\n\
+       pub crate                : 1\n"
+    ));
+
+    let json: Value = serde_json::from_str(&generate_json_content(&result)).unwrap();
+    assert_eq!(json["sequences"]["top"][0]["sequence"], "unsafe fn");
+    assert_eq!(json["cooccurrence"]["keywords"][0], "unsafe");
+    assert_eq!(json["cooccurrence"]["keywords"][2], "fn");
+    assert_eq!(json["cooccurrence"]["counts"][0][2], 2);
+
+    assert!(generate_csv_content(&result).contains("sequence,,,unsafe fn,2\n"));
+    let html = generate_html_content(&result);
+    assert!(html.contains("<h3>Keyword Co-occurrence</h3>"));
+    assert!(html.contains("title=\"unsafe fn: 2\">2</td>"));
+    let svg = generate_svg_content(&result).unwrap();
+    assert!(svg.contains("<title>unsafe fn: 2</title>"));
+  }
+
+  #[test]
+  fn test_comment_analysis() {
+    let marker = |kind: &str, line, owner: Option<&str>| Marker {
+      kind: kind.to_string(),
+      line,
+      column: 4,
+      owner: owner.map(str::to_string),
+      text: "fix <this>".to_string(),
+    };
+    let mut result = AnalysisResult::new(Language::Rust.spec().as_ref());
+    let mut file = sample_file("src/b.rs", &[("fn", 2)]).with_lines(LineCounts {
+      total: 30,
+      code: 20,
+      comment: 5,
+      blank: 5,
+      mixed: 0,
+    });
+    file.markers = vec![marker("TODO", 3, Some("alice")), marker("FIXME", 9, None)];
+    file.docs = DocCoverage {
+      documented: 1,
+      items: 2,
+    };
+    result.add_file(file);
+    let mut file = sample_file("src/a.rs", &[]);
+    file.markers = vec![marker("TODO", 1, None)];
+    file.docs = DocCoverage {
+      documented: 2,
+      items: 2,
+    };
+    result.add_file(file);
+
+    let plain = format_comments_plain(&result);
+    assert!(plain.contains("Comment lines: 5 (0.17 per line of code)\n"));
+    assert!(plain.contains("Doc coverage: 3 of 4 items (75.0%)\n"));
+    assert!(plain.contains("Markers: 3 (TODO 2, FIXME 1)\n"));
+    assert!(plain.ends_with(
+      "src/a.rs:1:4: TODO fix <this>\n\
+       src/b.rs:3:4: TODO(alice) fix <this>\n\
+       src/b.rs:9:4: FIXME fix <this>\n"
+    ));
+
+    let json: Value = serde_json::from_str(&generate_json_content(&result)).unwrap();
+    assert_eq!(json["comments"]["doc_coverage"]["percentage"], 75.0);
+    assert_eq!(json["comments"]["markers"][1]["owner"], "alice");
+    assert_eq!(json["files"][0]["markers"], 2);
+
+    let csv = generate_csv_content(&result);
+    assert!(csv.contains("total,,,_markers,3\n"));
+    assert!(csv.contains("file,src/a.rs,,_documented_items,2\n"));
+
+    let html = generate_html_content(&result);
+    assert!(html.contains("<td>fix &lt;this&gt;</td>"));
+    assert!(html.contains("75.0%"));
+
+    let analysis = RepositoryAnalysis::from_results(vec![result]);
+    assert!(format_repository_comments_plain(&analysis)
+      .starts_with("\n=== Repository Comment Analysis ===\nComment lines: 5"));
+  }
+
+  #[test]
+  fn test_complexity_ranking() {
+    let function = |name: &str, line, complexity| FunctionComplexity {
+      name: name.to_string(),
+      line,
+      complexity,
+    };
+    let mut result = AnalysisResult::new(Language::Rust.spec().as_ref());
+    result.add_file(
+      sample_file("src/simple.rs", &[("fn", 1)]).with_complexity(FileComplexity {
+        complexity: 2,
+        functions: vec![function("main", 1, 2)],
+      }),
+    );
+    result.add_file(
+      sample_file("src/parser.rs", &[("fn", 2)]).with_complexity(FileComplexity {
+        complexity: 9,
+        functions: vec![function("parse", 4, 7), function("peek", 20, 1)],
+      }),
+    );
+    result.add_file(sample_file("src/empty.rs", &[("fn", 1)]));
+
+    let plain = format_plain(&result);
+    assert!(plain.ends_with(
+      "\nMost complex files:\n       \
+       9 complexity     2 functions  src/parser.rs (max parse line 4: 7)\n       \
+       2 complexity     1 functions  src/simple.rs (max main line 1: 2)\n"
+    ));
+
+    let json: Value = serde_json::from_str(&generate_json_content(&result)).unwrap();
+    assert_eq!(json["complexity"]["total"], 11);
+    assert_eq!(
+      json["complexity"]["most_complex_files"][0]["path"],
+      "src/parser.rs"
+    );
+    assert_eq!(
+      json["complexity"]["most_complex_files"][0]["max_function"]["name"],
+      "parse"
+    );
+    assert_eq!(
+      json["complexity"]["most_complex_functions"][1]["name"],
+      "main"
+    );
+
+    let csv = generate_csv_content(&result);
+    assert!(csv.contains("total,,,_complexity,11\n"));
+    assert!(csv.contains("function,src/parser.rs,,peek:20,1\n"));
+
+    let html = generate_html_content(&result);
+    assert!(html.contains("<th>Most Complex File</th>"));
+    assert!(html.contains("parse (src/parser.rs:4)"));
+    let svg = generate_svg_content(&result).unwrap();
+    assert!(svg.contains("<title>src/parser.rs (max parse line 4: 7): complexity 9</title>"));
+
+    let analysis = RepositoryAnalysis::from_results(vec![result]);
+    assert!(
+      format_repository_plain(&analysis).contains("src/parser.rs [Rust] (max parse line 4: 7)\n")
+    );
+    let json: Value = serde_json::from_str(&generate_repository_json_content(&analysis)).unwrap();
+    assert_eq!(
+      json["complexity"]["most_complex_files"][1]["language"],
+      "rust"
+    );
+  }
+
+  #[test]
+  fn test_skipped_files() {
+    let mut result = sample_result(Language::Rust, &[("fn", 1)]);
+    result.skipped.push(SkippedFile {
+      path: "src/old, latin1.rs".to_string(),
+      reason: "not valid UTF-8".to_string(),
+    });
+
+    assert!(format_plain(&result)
+      .contains("\nSkipped files (1):\n  src/old, latin1.rs: not valid UTF-8\n"));
+    let json: Value = serde_json::from_str(&generate_json_content(&result)).unwrap();
+    assert_eq!(json["skipped"][0]["reason"], "not valid UTF-8");
+    assert!(generate_csv_content(&result)
+      .ends_with("skipped,\"src/old, latin1.rs\",,not valid UTF-8,1\n"));
+    assert!(generate_html_content(&result).contains("<h2>Skipped Files (1)</h2>"));
+    assert!(generate_svg_content(&result)
+      .unwrap()
+      .contains("Files: 1 (1 skipped) |"));
+
+    let analysis = RepositoryAnalysis::from_results(vec![result]);
+    assert_eq!(analysis.skipped.len(), 1);
+    assert!(generate_repository_csv_content(&analysis).contains("_all,skipped,"));
+  }
+
+  #[test]
+  fn test_generate_svg_content_without_keywords() {
+    let result = sample_result(Language::Golang, &[]);
+    assert!(generate_svg_content(&result).is_err());
+  }
+}
@@ -1,374 +1,375 @@
 use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
-
-pub const PYTHON_KEYWORDS: &[&str] = &[
-  // Keywords (Python 3.11)
-  "False",
-  "None",
-  "True",
-  "and",
-  "as",
-  "assert",
-  "async",
-  "await",
-  "break",
-  "class",
-  "continue",
-  "def",
-  "del",
-  "elif",
-  "else",
-  "except",
-  "finally",
-  "for",
-  "from",
-  "global",
-  "if",
-  "import",
-  "in",
-  "is",
-  "lambda",
-  "nonlocal",
-  "not",
-  "or",
-  "pass",
-  "raise",
-  "return",
-  "try",
-  "while",
-  "with",
-  "yield",
-  // Built-in functions (commonly used)
-  "abs",
-  "all",
-  "any",
-  "ascii",
-  "bin",
-  "bool",
-  "bytearray",
-  "bytes",
-  "callable",
-  "chr",
-  "classmethod",
-  "compile",
-  "complex",
-  "delattr",
-  "dict",
-  "dir",
-  "divmod",
-  "enumerate",
-  "eval",
-  "exec",
-  "filter",
-  "float",
-  "format",
-  "frozenset",
-  "getattr",
-  "globals",
-  "hasattr",
-  "hash",
-  "help",
-  "hex",
-  "id",
-  "input",
-  "int",
-  "isinstance",
-  "issubclass",
-  "iter",
-  "len",
-  "list",
-  "locals",
-  "map",
-  "max",
-  "memoryview",
-  "min",
-  "next",
-  "object",
-  "oct",
-  "open",
-  "ord",
-  "pow",
-  "print",
-  "property",
-  "range",
-  "repr",
-  "reversed",
-  "round",
-  "set",
-  "setattr",
-  "slice",
-  "sorted",
-  "staticmethod",
-  "str",
-  "sum",
-  "super",
-  "tuple",
-  "type",
-  "vars",
-  "zip",
-  // Built-in exceptions (common ones)
-  "Exception",
-  "AttributeError",
-  "IOError",
-  "ImportError",
-  "IndexError",
-  "KeyError",
-  "NameError",
-  "RuntimeError",
-  "SyntaxError",
-  "TypeError",
-  "ValueError",
-  "ZeroDivisionError",
-  // Built-in constants
-  "NotImplemented",
-  "Ellipsis",
-  "__debug__",
-  // Special methods (dunder methods - common ones)
-  "__init__",
-  "__str__",
-  "__repr__",
-  "__len__",
-  "__getitem__",
-  "__setitem__",
-  "__delitem__",
-  "__contains__",
-  "__call__",
-  "__enter__",
-  "__exit__",
-  "__iter__",
-  "__next__",
-];
-
-pub fn analyze_directory(
-  path: &str,
-  total_counts: &mut HashMap<String, usize>,
-  file_count: &mut usize,
-) -> Result<(), Box<dyn std::error::Error>> {
-  let path = Path::new(path);
-
-  if path.is_file() && is_python_file(path) {
-    eprintln!("Analyzing file: {}", path.display());
-    let content = fs::read_to_string(path)?;
-    let file_counts = count_keywords(&content);
+use std::sync::{Arc, OnceLock};
 
-    for (keyword, count) in file_counts {
-      *total_counts.entry(keyword).or_insert(0) += count;
-    }
-    *file_count += 1;
-    eprintln!("Files processed: {}", *file_count);
-  } else if path.is_dir() {
-    let entries = fs::read_dir(path)?;
-    for entry in entries {
-      let entry = entry?;
-      let entry_path = entry.path();
-
-      if entry_path.is_dir() {
-        if should_skip_dir(&entry_path) {
-          continue;
-        }
-        eprintln!("Entering directory: {}", entry_path.display());
-        analyze_directory(entry_path.to_str().unwrap(), total_counts, file_count)?;
-      } else if is_python_file(&entry_path) {
-        eprintln!("Analyzing file: {}", entry_path.display());
-        let content = fs::read_to_string(&entry_path)?;
-        let file_counts = count_keywords(&content);
-
-        for (keyword, count) in file_counts {
-          *total_counts.entry(keyword).or_insert(0) += count;
-        }
-        *file_count += 1;
-        eprintln!("Files processed: {}", *file_count);
-      }
+use crate::lexer::{Token, TokenKind};
+use crate::spec::{LanguageDefinition, LanguageSpec};
+
+const DEFINITION: &str = include_str!("../languages/python.toml");
+
+pub struct PythonSpec {
+  definition: LanguageDefinition,
+}
+
+impl PythonSpec {
+  pub fn new() -> Self {
+    Self {
+      definition: LanguageDefinition::from_toml_str(DEFINITION)
+        .expect("bundled Python definition should be valid"),
     }
   }
+}
 
-  Ok(())
+impl Default for PythonSpec {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
-fn is_python_file(path: &Path) -> bool {
-  if let Some(extension) = path.extension() {
-    let ext = extension.to_string_lossy().to_lowercase();
-    matches!(ext.as_str(), "py" | "pyw" | "pyi")
-  } else {
-    // Check for files without extensions but with Python shebang
-    if let Some(filename) = path.file_name() {
-      let name = filename.to_string_lossy();
-      // Common Python files without extensions
-      matches!(name.as_ref(), "Pipfile" | "Pipfile.lock" | "__init__")
-    } else {
-      false
+impl LanguageSpec for PythonSpec {
+  fn definition(&self) -> &LanguageDefinition {
+    &self.definition
+  }
+
+  fn tokenize<'a>(&self, content: &'a str) -> Vec<Token<'a>> {
+    tokenize(content)
+  }
+
+  /// Soft keywords only count where they act as keywords: `match`/`case`
+  /// statement headers, `_` as a case pattern, and `type` as an alias
+  /// statement or a call to the builtin.
+  fn is_keyword_token(&self, content: &str, tokens: &[Token], index: usize) -> bool {
+    let text = tokens[index].text;
+    if !self.is_keyword(text) {
+      return false;
+    }
+
+    match text {
+      "match" | "case" => is_soft_keyword_statement(content, tokens, index),
+      "_" => is_case_pattern(content, tokens, index),
+      "type" => is_type_alias(content, tokens, index) || is_builtin_call(tokens, index),
+      _ => true,
     }
   }
 }
 
-fn should_skip_dir(path: &Path) -> bool {
-  if let Some(dir_name) = path.file_name() {
-    let name = dir_name.to_string_lossy();
-    matches!(
-      name.as_ref(),
-      "__pycache__"
-        | ".pytest_cache"
-        | "venv"
-        | "env"
-        | ".venv"
-        | ".env"
-        | "site-packages"
-        | "dist"
-        | "build"
-        | ".git"
-        | "target"
-        | "node_modules"
-        | ".tox"
-        | ".coverage"
-        | "htmlcov"
-        | ".mypy_cache"
-        | ".idea"
-        | ".vscode"
-    )
-  } else {
-    false
-  }
+/// Splits Python source into tokens.
+///
+/// Understands string prefixes (`r`, `b`, `rb`, `f`, `rf`, `t`, ...) and lexes
+/// the expressions inside f-string replacement fields as code, including
+/// nested f-strings reusing the outer quote (PEP 701). Literal text and format
+/// specs are emitted as `Str` tokens. Backslash line continuations are
+/// treated as whitespace.
+pub fn tokenize(content: &str) -> Vec<Token<'_>> {
+  let mut lexer = Lexer {
+    content,
+    pos: 0,
+    tokens: Vec::new(),
+  };
+  lexer.lex_code(false);
+  lexer.tokens
 }
 
-pub fn count_keywords(content: &str) -> HashMap<String, usize> {
-  let mut counts = HashMap::new();
-  let mut chars = content.chars().peekable();
-  let mut current_token = String::new();
-  let mut in_string = false;
-  let mut in_comment = false;
-  let mut string_char = '\0';
-  let mut in_triple_quote = false;
-  let mut triple_quote_type = '\0';
-
-  while let Some(c) = chars.next() {
-    match c {
-      // Handle single-line comments (Python uses #)
-      '#' if !in_string && !in_triple_quote => {
-        in_comment = true;
-        if !current_token.is_empty() {
-          check_and_count_token(&current_token, &mut counts);
-          current_token.clear();
+struct Lexer<'a> {
+  content: &'a str,
+  pos: usize,
+  tokens: Vec<Token<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+  fn rest(&self) -> &'a str {
+    &self.content[self.pos..]
+  }
+
+  fn emit(&mut self, kind: TokenKind, start: usize, end: usize) {
+    self
+      .tokens
+      .push(Token::new(kind, &self.content[start..end], start));
+    self.pos = end;
+  }
+
+  /// Lexes code until the end of input or, inside an f-string replacement
+  /// field, until the `}`, `!` conversion or `:` format spec that ends the
+  /// expression. Returns the character that stopped it, `None` at the end.
+  fn lex_code(&mut self, in_field: bool) -> Option<char> {
+    let mut depth: usize = 0;
+
+    while let Some(c) = self.rest().chars().next() {
+      let rest = self.rest();
+      let start = self.pos;
+
+      if in_field && depth == 0 && (c == '}' || c == ':' || is_conversion(rest)) {
+        return Some(c);
+      }
+
+      if c.is_whitespace() {
+        self.pos += c.len_utf8();
+      } else if rest.starts_with("\\\n") || rest.starts_with("\\\r\n") {
+        self.pos = start + rest.find('\n').unwrap() + 1;
+      } else if c == '#' {
+        let end = rest.find('\n').map_or(self.content.len(), |i| start + i);
+        self.emit(TokenKind::Comment, start, end);
+      } else if let Some((prefix, quote)) = string_start(rest) {
+        self.lex_string(prefix, quote);
+      } else if is_identifier_char(c) {
+        let end = rest
+          .char_indices()
+          .find(|(_, ch)| !is_identifier_char(*ch))
+          .map_or(self.content.len(), |(i, _)| start + i);
+        self.emit(TokenKind::Word, start, end);
+      } else {
+        match c {
+          '(' | '[' | '{' => depth += 1,
+          ')' | ']' | '}' => depth = depth.saturating_sub(1),
+          _ => {}
         }
-        continue;
+        self.emit(TokenKind::Punct, start, start + c.len_utf8());
       }
-      // End single-line comment
-      '\n' if in_comment => {
-        in_comment = false;
-        continue;
+    }
+
+    None
+  }
+
+  /// Lexes a string literal with the given prefix and quote at `self.pos`.
+  fn lex_string(&mut self, prefix: &str, quote: &str) {
+    let formatted = prefix.contains(['f', 'F', 't', 'T']);
+    let single_line = quote.len() == 1;
+    let mut chunk_start = self.pos;
+    self.pos += prefix.len() + quote.len();
+
+    while let Some(c) = self.rest().chars().next() {
+      let rest = self.rest();
+      if rest.starts_with(quote) {
+        self.pos += quote.len();
+        break;
+      } else if c == '\n' && single_line {
+        // Unterminated; do not swallow the rest of the file
+        break;
+      } else if c == '\\' {
+        self.pos += 1;
+        self.pos += self.rest().chars().next().map_or(0, char::len_utf8);
+      } else if formatted && (rest.starts_with("{{") || rest.starts_with("}}")) {
+        self.pos += 2;
+      } else if formatted && c == '{' {
+        self.emit(TokenKind::Str, chunk_start, self.pos + 1);
+        if !self.lex_replacement_field() {
+          return;
+        }
+        // The closing `}` starts the next chunk of literal text
+        chunk_start = self.pos;
+        self.pos += 1;
+      } else {
+        self.pos += c.len_utf8();
       }
-      // Handle triple quotes (Python docstrings)
-      '"' | '\'' if !in_comment => {
-        if !in_string && !in_triple_quote {
-          // Check for triple quotes
-          if chars.peek() == Some(&c) {
-            chars.next(); // consume second quote
-            if chars.peek() == Some(&c) {
-              chars.next(); // consume third quote
-              in_triple_quote = true;
-              triple_quote_type = c;
-            } else {
-              // Two quotes, treat as empty string
-              in_string = true;
-              string_char = c;
+    }
+
+    self.emit(TokenKind::Str, chunk_start, self.pos);
+  }
+
+  /// Lexes a replacement field after its `{`, stopping at the closing `}`.
+  /// Returns `false` if the input ends first.
+  fn lex_replacement_field(&mut self) -> bool {
+    match self.lex_code(true) {
+      Some('}') => true,
+      Some(_) => {
+        // Conversion and format spec, which may contain nested fields
+        let mut spec_start = self.pos;
+        while let Some(c) = self.rest().chars().next() {
+          if c == '}' {
+            if self.pos > spec_start {
+              self.emit(TokenKind::Str, spec_start, self.pos);
             }
-          } else {
-            // Single quote string
-            in_string = true;
-            string_char = c;
-          }
-          if !current_token.is_empty() {
-            check_and_count_token(&current_token, &mut counts);
-            current_token.clear();
-          }
-        } else if in_triple_quote && c == triple_quote_type {
-          // Check for end of triple quote
-          if chars.peek() == Some(&c) {
-            chars.next(); // consume second quote
-            if chars.peek() == Some(&c) {
-              chars.next(); // consume third quote
-              in_triple_quote = false;
-              triple_quote_type = '\0';
+            return true;
+          } else if c == '{' {
+            self.emit(TokenKind::Str, spec_start, self.pos + 1);
+            if !self.lex_replacement_field() {
+              return false;
             }
+            spec_start = self.pos;
           }
-        } else if in_string && c == string_char {
-          in_string = false;
-          string_char = '\0';
-        }
-        continue;
-      }
-      // Handle escape sequences in strings
-      '\\' if in_string && !in_triple_quote => {
-        // Skip the next character if we're in a string (escape sequence)
-        chars.next();
-        continue;
-      }
-      // Skip content inside strings, triple quotes, and comments
-      _ if in_string || in_triple_quote || in_comment => {
-        continue;
-      }
-      // Handle regular tokens
-      _ => {
-        if c.is_alphanumeric() || c == '_' {
-          current_token.push(c);
-        } else if !current_token.is_empty() {
-          check_and_count_token(&current_token, &mut counts);
-          current_token.clear();
+          self.pos += c.len_utf8();
         }
+        self.emit(TokenKind::Str, spec_start, self.pos);
+        false
       }
+      None => false,
     }
   }
+}
+
+fn is_identifier_char(c: char) -> bool {
+  c.is_alphanumeric() || c == '_'
+}
 
-  // Check final token
-  if !current_token.is_empty() {
-    check_and_count_token(&current_token, &mut counts);
+/// Whether `rest` starts with a `!r`, `!s` or `!a` f-string conversion.
+fn is_conversion(rest: &str) -> bool {
+  let mut chars = rest.chars();
+  chars.next() == Some('!')
+    && matches!(chars.next(), Some('r' | 's' | 'a'))
+    && matches!(chars.next(), Some(':' | '}'))
+}
+
+/// Returns the prefix and quote of a string literal starting `rest`.
+fn string_start(rest: &str) -> Option<(&str, &'static str)> {
+  let prefix_len = rest
+    .chars()
+    .take(3)
+    .take_while(|c| matches!(c.to_ascii_lowercase(), 'r' | 'b' | 'u' | 'f' | 't'))
+    .count();
+  let prefix = &rest[..prefix_len];
+  let valid_prefix = matches!(
+    prefix.to_ascii_lowercase().as_str(),
+    "" | "r" | "u" | "b" | "f" | "t" | "br" | "rb" | "fr" | "rf" | "tr" | "rt"
+  );
+  if !valid_prefix {
+    return None;
   }
 
-  counts
+  let body = &rest[prefix_len..];
+  ["\"\"\"", "'''", "\"", "'"]
+    .into_iter()
+    .find(|quote| body.starts_with(quote))
+    .map(|quote| (prefix, quote))
+}
+
+/// Whether `tokens[index]` is the first token of a logical line.
+fn starts_logical_line(content: &str, tokens: &[Token], index: usize) -> bool {
+  let Some(previous) = index.checked_sub(1).map(|i| &tokens[i]) else {
+    return true;
+  };
+  let gap = &content[previous.offset + previous.text.len()..tokens[index].offset];
+  let mut lines = gap.split('\n');
+  lines.next_back();
+
+  previous.text == ";" || lines.any(|line| !line.trim_end().ends_with('\\'))
 }
 
-fn check_and_count_token(token: &str, counts: &mut HashMap<String, usize>) {
-  if PYTHON_KEYWORDS.contains(&token) {
-    *counts.entry(token.to_string()).or_insert(0) += 1;
+/// Whether the logical line after `tokens[index]` contains a `:` outside
+/// brackets, as `match x:` and `case _:` headers do.
+fn has_header_colon(content: &str, tokens: &[Token], index: usize) -> bool {
+  let mut depth: usize = 0;
+
+  for (i, token) in tokens.iter().enumerate().skip(index + 1) {
+    if depth == 0 && starts_logical_line(content, tokens, i) {
+      return false;
+    }
+    if token.kind != TokenKind::Punct {
+      continue;
+    }
+    match token.text {
+      "(" | "[" | "{" => depth += 1,
+      ")" | "]" | "}" => depth = depth.saturating_sub(1),
+      ":" if depth == 0 => {
+        // Skip walrus operators
+        let walrus = tokens
+          .get(i + 1)
+          .is_some_and(|next| next.text == "=" && next.offset == token.offset + 1);
+        if !walrus {
+          return true;
+        }
+      }
+      _ => {}
+    }
   }
+
+  false
+}
+
+/// `match`/`case` at the start of a statement header, as opposed to a name
+/// such as `match = re.match(...)` or `case.id`.
+fn is_soft_keyword_statement(content: &str, tokens: &[Token], index: usize) -> bool {
+  let used_as_name = tokens.get(index + 1).is_none_or(|next| {
+    next.kind == TokenKind::Punct
+      && matches!(next.text, "=" | "." | "," | ")" | "]" | "}" | ":" | ";")
+  });
+
+  !used_as_name
+    && starts_logical_line(content, tokens, index)
+    && has_header_colon(content, tokens, index)
+}
+
+/// `_` inside a `case` pattern.
+fn is_case_pattern(content: &str, tokens: &[Token], index: usize) -> bool {
+  let Some(line_start) = (0..=index)
+    .rev()
+    .find(|i| starts_logical_line(content, tokens, *i))
+  else {
+    return false;
+  };
+
+  line_start < index
+    && tokens[line_start].text == "case"
+    && is_soft_keyword_statement(content, tokens, line_start)
+}
+
+/// `type Alias = ...` or `type Alias[T] = ...`.
+fn is_type_alias(content: &str, tokens: &[Token], index: usize) -> bool {
+  let name_follows = tokens
+    .get(index + 1)
+    .is_some_and(|name| name.kind == TokenKind::Word);
+  let assignment_follows = tokens
+    .get(index + 2)
+    .is_some_and(|next| matches!(next.text, "=" | "["));
+
+  starts_logical_line(content, tokens, index) && name_follows && assignment_follows
+}
+
+/// A call such as `type(obj)` rather than a method or function definition
+/// named `type`.
+fn is_builtin_call(tokens: &[Token], index: usize) -> bool {
+  let called = tokens.get(index + 1).is_some_and(|next| next.text == "(");
+  let defined_or_attribute = index
+    .checked_sub(1)
+    .is_some_and(|previous| matches!(tokens[previous].text, "." | "def"));
+
+  called && !defined_or_attribute
+}
+
+pub fn spec() -> Arc<PythonSpec> {
+  static SPEC: OnceLock<Arc<PythonSpec>> = OnceLock::new();
+  SPEC.get_or_init(|| Arc::new(PythonSpec::new())).clone()
+}
+
+pub fn count_keywords(content: &str) -> HashMap<String, usize> {
+  spec().count_keywords(content)
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use std::path::Path;
 
   #[test]
   fn test_is_python_file() {
-    assert!(is_python_file(Path::new("test.py")));
-    assert!(is_python_file(Path::new("script.pyw")));
-    assert!(is_python_file(Path::new("types.pyi")));
-    assert!(is_python_file(Path::new("Pipfile")));
-    assert!(is_python_file(Path::new("__init__")));
-
-    assert!(!is_python_file(Path::new("test.rs")));
-    assert!(!is_python_file(Path::new("test.js")));
-    assert!(!is_python_file(Path::new("README.md")));
+    assert!(spec().is_source_file(Path::new("test.py")));
+    assert!(spec().is_source_file(Path::new("script.pyw")));
+    assert!(spec().is_source_file(Path::new("types.pyi")));
+    assert!(spec().is_source_file(Path::new("Pipfile")));
+    assert!(spec().is_source_file(Path::new("__init__")));
+
+    assert!(!spec().is_source_file(Path::new("test.rs")));
+    assert!(!spec().is_source_file(Path::new("test.js")));
+    assert!(!spec().is_source_file(Path::new("README.md")));
   }
 
   #[test]
   fn test_should_skip_dir() {
-    assert!(should_skip_dir(Path::new("__pycache__")));
-    assert!(should_skip_dir(Path::new("venv")));
-    assert!(should_skip_dir(Path::new(".pytest_cache")));
-    assert!(should_skip_dir(Path::new("site-packages")));
-    assert!(should_skip_dir(Path::new(".git")));
-    assert!(should_skip_dir(Path::new(".mypy_cache")));
-
-    assert!(!should_skip_dir(Path::new("src")));
-    assert!(!should_skip_dir(Path::new("tests")));
-    assert!(!should_skip_dir(Path::new("lib")));
+    assert!(spec().should_skip_dir(Path::new("__pycache__")));
+    assert!(spec().should_skip_dir(Path::new("venv")));
+    assert!(spec().should_skip_dir(Path::new(".pytest_cache")));
+    assert!(spec().should_skip_dir(Path::new("site-packages")));
+    assert!(spec().should_skip_dir(Path::new(".git")));
+    assert!(spec().should_skip_dir(Path::new(".mypy_cache")));
+
+    assert!(!spec().should_skip_dir(Path::new("src")));
+    assert!(!spec().should_skip_dir(Path::new("tests")));
+    assert!(!spec().should_skip_dir(Path::new("lib")));
   }
 
   #[test]
   fn test_python_keywords_count() {
     // Verify we have a reasonable number of Python keywords
-    assert!(PYTHON_KEYWORDS.len() >= 80); // Should have at least 80 keywords/built-ins
-    assert!(PYTHON_KEYWORDS.len() <= 130); // But not too many
+    assert!(spec().keywords().len() >= 80); // Should have at least 80 keywords/built-ins
+    assert!(spec().keywords().len() <= 135); // But not too many
   }
 
   #[test]
@@ -573,10 +574,110 @@ def documented_function():
 
     // Test that the function works with valid empty directory (like current directory)
     // but doesn't find Python files, which is a valid success case
-    let result = analyze_directory(".", &mut counts, &mut file_count);
+    let result = crate::analyze_directory(
+      Path::new("."),
+      spec().as_ref(),
+      &mut counts,
+      &mut file_count,
+    );
 
     // The function should succeed even if no Python files are found
     assert!(result.is_ok());
     // file_count might be 0 if no Python files in current directory, which is fine
   }
+
+  fn texts<'a>(tokens: &[Token<'a>]) -> Vec<(TokenKind, &'a str)> {
+    tokens
+      .iter()
+      .map(|token| (token.kind, token.text))
+      .collect()
+  }
+
+  #[test]
+  fn test_tokenize_string_prefixes() {
+    let tokens = tokenize(r#"r'\d+ if' b"for" Rb'\x00' u"while" rf"{x}" br"""def""" bad"str""#);
+    assert_eq!(
+      texts(&tokens),
+      vec![
+        (TokenKind::Str, r"r'\d+ if'"),
+        (TokenKind::Str, r#"b"for""#),
+        (TokenKind::Str, r"Rb'\x00'"),
+        (TokenKind::Str, r#"u"while""#),
+        (TokenKind::Str, r#"rf"{"#),
+        (TokenKind::Word, "x"),
+        (TokenKind::Str, r#"}""#),
+        (TokenKind::Str, r#"br"""def""""#),
+        (TokenKind::Word, "bad"),
+        (TokenKind::Str, r#""str""#),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_tokenize_f_strings() {
+    let content = r#"f"{'yes' if ok else 'no'} {{if}} {value!r:>{width}} {f"{lambda: 1}"}""#;
+    let tokens = tokenize(content);
+    assert_eq!(
+      texts(&tokens),
+      vec![
+        (TokenKind::Str, "f\"{"),
+        (TokenKind::Str, "'yes'"),
+        (TokenKind::Word, "if"),
+        (TokenKind::Word, "ok"),
+        (TokenKind::Word, "else"),
+        (TokenKind::Str, "'no'"),
+        (TokenKind::Str, "} {{if}} {"),
+        (TokenKind::Word, "value"),
+        (TokenKind::Str, "!r:>{"),
+        (TokenKind::Word, "width"),
+        (TokenKind::Str, "}"),
+        (TokenKind::Str, "} {"),
+        (TokenKind::Str, "f\"{"),
+        (TokenKind::Word, "lambda"),
+        (TokenKind::Str, ": 1"),
+        (TokenKind::Str, "}\""),
+        (TokenKind::Str, "}\""),
+      ]
+    );
+
+    let counts = count_keywords("print(f'{len(items)} items for {\", \".join(x for x in names)}')");
+    assert_eq!(counts.get("len"), Some(&1));
+    assert_eq!(counts.get("for"), Some(&1));
+    assert_eq!(counts.get("in"), Some(&1));
+  }
+
+  #[test]
+  fn test_line_continuations() {
+    let content = "total = first + \\\n    second if ok else \\\n    third\nmatch = 1\n";
+    let counts = count_keywords(content);
+    assert_eq!(counts.get("if"), Some(&1));
+    assert_eq!(counts.get("else"), Some(&1));
+    assert_eq!(counts.get("match"), None);
+  }
+
+  #[test]
+  fn test_soft_keywords() {
+    let content = r#"
+match command.split():
+    case [action]:
+        pass
+    case [_, obj] if obj:
+        pass
+    case _: return
+match = re.match(pattern, text)
+case = cases[0]
+_ = compute()
+print(_("translated"))
+type Point = tuple[float, float]
+type Pair[T] = tuple[T, T]
+kind = type(value)
+node.type = "leaf"
+def handler(self, type=None): pass
+"#;
+    let counts = count_keywords(content);
+    assert_eq!(counts.get("match"), Some(&1));
+    assert_eq!(counts.get("case"), Some(&3));
+    assert_eq!(counts.get("_"), Some(&2));
+    assert_eq!(counts.get("type"), Some(&3));
+  }
 }
@@ -0,0 +1,230 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::spec::LanguageSpec;
+use crate::Language;
+
+/// A set of languages that can be looked up by id or alias.
+#[derive(Clone, Default)]
+pub struct LanguageRegistry {
+  languages: Vec<Arc<dyn LanguageSpec>>,
+}
+
+impl LanguageRegistry {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// A registry pre-populated with every built-in [`Language`].
+  pub fn with_builtins() -> Self {
+    let mut registry = Self::new();
+    for language in Language::ALL {
+      registry.register(language.spec());
+    }
+    registry
+  }
+
+  /// Adds a language, replacing any previously registered language with the
+  /// same id.
+  pub fn register(&mut self, spec: Arc<dyn LanguageSpec>) {
+    self.languages.retain(|existing| existing.id() != spec.id());
+    self.languages.push(spec);
+  }
+
+  pub fn find(&self, name: &str) -> Option<Arc<dyn LanguageSpec>> {
+    self
+      .languages
+      .iter()
+      .find(|spec| spec.matches_name(name))
+      .cloned()
+  }
+
+  pub fn languages(&self) -> &[Arc<dyn LanguageSpec>] {
+    &self.languages
+  }
+
+  /// Picks the language of a file from its extension or file name, falling
+  /// back to the `#!` line for extensionless scripts.
+  pub fn detect(&self, path: &Path) -> Option<Arc<dyn LanguageSpec>> {
+    let all = vec![true; self.languages.len()];
+    detect_among(&self.languages, &all, path).map(|index| self.languages[index].clone())
+  }
+}
+
+/// Index of the first language in `languages` whose `active` flag is set and
+/// that claims `path`.
+pub(crate) fn detect_among(
+  languages: &[Arc<dyn LanguageSpec>],
+  active: &[bool],
+  path: &Path,
+) -> Option<usize> {
+  let candidates = || {
+    languages
+      .iter()
+      .enumerate()
+      .filter(|(index, _)| active[*index])
+  };
+
+  if let Some((index, _)) = candidates().find(|(_, spec)| spec.is_source_file(path)) {
+    return Some(index);
+  }
+  if path.extension().is_some() {
+    return None;
+  }
+
+  let interpreter = read_shebang_interpreter(path)?;
+  candidates()
+    .find(|(_, spec)| spec.matches_interpreter(&interpreter))
+    .map(|(index, _)| index)
+}
+
+fn read_shebang_interpreter(path: &Path) -> Option<String> {
+  let mut head = Vec::with_capacity(256);
+  fs::File::open(path)
+    .ok()?
+    .take(256)
+    .read_to_end(&mut head)
+    .ok()?;
+  let first_line = String::from_utf8_lossy(&head);
+  shebang_interpreter(first_line.lines().next()?)
+}
+
+/// Extracts the interpreter from a shebang line, looking through `env` and
+/// dropping version suffixes: `#!/usr/bin/env python3.11` gives `python`.
+pub fn shebang_interpreter(line: &str) -> Option<String> {
+  let mut parts = line.strip_prefix("#!")?.split_whitespace();
+  let program = parts.next()?;
+  let mut interpreter = program.rsplit('/').next()?;
+
+  if interpreter == "env" {
+    interpreter = parts.find(|arg| !arg.starts_with('-') && !arg.contains('='))?;
+  }
+
+  let name = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+  if name.is_empty() {
+    None
+  } else {
+    Some(name.to_string())
+  }
+}
+
+/// The process-wide registry, initialised with the built-in languages.
+pub fn global() -> &'static RwLock<LanguageRegistry> {
+  static REGISTRY: OnceLock<RwLock<LanguageRegistry>> = OnceLock::new();
+  REGISTRY.get_or_init(|| RwLock::new(LanguageRegistry::with_builtins()))
+}
+
+/// Registers a language in the global registry so it can be found by name.
+pub fn register_language(spec: Arc<dyn LanguageSpec>) {
+  global().write().unwrap().register(spec);
+}
+
+/// Looks a language up by id or alias in the global registry.
+pub fn find_language(name: &str) -> Option<Arc<dyn LanguageSpec>> {
+  global().read().unwrap().find(name)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::spec::{to_strings, LanguageDefinition};
+  use crate::test_dir::TestDir;
+
+  fn custom_language() -> LanguageDefinition {
+    LanguageDefinition {
+      id: "toy".to_string(),
+      name: "Toy".to_string(),
+      aliases: to_strings(&["toylang"]),
+      keywords: to_strings(&["when", "then"]),
+      extensions: to_strings(&["toy"]),
+      line_comments: to_strings(&["--"]),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_builtins_are_registered() {
+    let registry = LanguageRegistry::with_builtins();
+    assert_eq!(registry.languages().len(), Language::ALL.len());
+    assert_eq!(registry.find("rs").unwrap().id(), "rust");
+    assert_eq!(registry.find("Golang").unwrap().id(), "go");
+    assert_eq!(registry.find("py").unwrap().name(), "Python");
+    assert!(registry.find("cpp").is_none());
+  }
+
+  #[test]
+  fn test_register_custom_language() {
+    let mut registry = LanguageRegistry::new();
+    registry.register(Arc::new(custom_language()));
+
+    let spec = registry.find("toylang").unwrap();
+    let counts = spec.count_keywords("when x then y -- when");
+    assert_eq!(counts.get("when"), Some(&1));
+    assert_eq!(counts.get("then"), Some(&1));
+
+    // Registering the same id again replaces the previous entry
+    let mut replacement = custom_language();
+    replacement.keywords = to_strings(&["x"]);
+    registry.register(Arc::new(replacement));
+    assert_eq!(registry.languages().len(), 1);
+    assert!(registry.find("toy").unwrap().is_keyword("x"));
+  }
+
+  #[test]
+  fn test_shebang_interpreter() {
+    assert_eq!(
+      shebang_interpreter("#!/usr/bin/env python3.11").as_deref(),
+      Some("python")
+    );
+    assert_eq!(
+      shebang_interpreter("#!/usr/bin/env -S node --harmony").as_deref(),
+      Some("node")
+    );
+    assert_eq!(
+      shebang_interpreter("#! /usr/local/bin/ruby -w").as_deref(),
+      Some("ruby")
+    );
+    assert_eq!(shebang_interpreter("#!/bin/sh").as_deref(), Some("sh"));
+    assert_eq!(shebang_interpreter("# not a shebang"), None);
+    assert_eq!(shebang_interpreter("#!/usr/bin/env"), None);
+  }
+
+  #[test]
+  fn test_detect() {
+    let registry = LanguageRegistry::with_builtins();
+    let detect = |path: &str| {
+      registry
+        .detect(Path::new(path))
+        .map(|spec| spec.id().to_string())
+    };
+    assert_eq!(detect("src/main.rs").as_deref(), Some("rust"));
+    assert_eq!(detect("app/Gemfile").as_deref(), Some("ruby"));
+    assert_eq!(detect("web/index.mjs").as_deref(), Some("javascript"));
+    assert_eq!(detect("web/App.tsx").as_deref(), Some("typescript"));
+    assert_eq!(detect("web/config.cts").as_deref(), Some("typescript"));
+    assert_eq!(detect("README.md"), None);
+
+    let dir = TestDir::new(&[
+      ("tool", "#!/usr/bin/env python3\nimport sys\n"),
+      ("plain", "no shebang here\n"),
+    ]);
+    let tool = detect(dir.join("tool").to_str().unwrap());
+    let plain = detect(dir.join("plain").to_str().unwrap());
+
+    assert_eq!(tool.as_deref(), Some("python"));
+    assert_eq!(plain, None);
+  }
+
+  #[test]
+  fn test_global_registry() {
+    let mut definition = custom_language();
+    definition.id = "global-toy".to_string();
+    definition.aliases.clear();
+    register_language(Arc::new(definition));
+
+    assert!(find_language("global-toy").is_some());
+    assert!(find_language("rust").is_some());
+  }
+}
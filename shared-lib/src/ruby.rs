@@ -1,9 +1,53 @@
 use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
+use std::sync::{Arc, OnceLock};
 
-pub const RUBY_KEYWORDS: &[&str] = &[
-  // Core Ruby keywords
+use crate::lexer::{Token, TokenKind};
+use crate::spec::{LanguageDefinition, LanguageSpec};
+
+const DEFINITION: &str = include_str!("../languages/ruby.toml");
+
+pub struct RubySpec {
+  definition: LanguageDefinition,
+}
+
+impl RubySpec {
+  pub fn new() -> Self {
+    Self {
+      definition: LanguageDefinition::from_toml_str(DEFINITION)
+        .expect("bundled Ruby definition should be valid"),
+    }
+  }
+}
+
+impl Default for RubySpec {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl LanguageSpec for RubySpec {
+  fn definition(&self) -> &LanguageDefinition {
+    &self.definition
+  }
+
+  fn tokenize<'a>(&self, content: &'a str) -> Vec<Token<'a>> {
+    tokenize(content)
+  }
+
+  /// Reserved words called as methods (`foo.end`, `obj.class`, `x&.then`)
+  /// are method names, not keywords.
+  fn is_keyword_token(&self, _content: &str, tokens: &[Token], index: usize) -> bool {
+    self.is_keyword(tokens[index].text) && !is_method_call(tokens, index)
+  }
+}
+
+/// Ruby's reserved words, which only name a method after `.` or `&.`.
+const RESERVED_WORDS: [&str; 41] = [
+  "__ENCODING__",
+  "__FILE__",
+  "__LINE__",
+  "BEGIN",
+  "END",
   "alias",
   "and",
   "begin",
@@ -40,202 +84,406 @@ pub const RUBY_KEYWORDS: &[&str] = &[
   "when",
   "while",
   "yield",
-  // Special variables and constants
-  "__FILE__",
-  "__LINE__",
-  "__ENCODING__",
-  // Access modifiers
-  "private",
-  "protected",
-  "public",
-  // Block keywords
-  "proc",
-  "lambda",
-  // Exception handling
-  "raise",
-  "throw",
-  "catch",
-  // Iteration and enumeration
-  "each",
-  "map",
-  "select",
-  "reject",
-  "find",
-  "collect",
-  "inject",
-  "reduce",
-  // String and regex
-  "gsub",
-  "sub",
-  "match",
-  // Metaprogramming
-  "attr_reader",
-  "attr_writer",
-  "attr_accessor",
-  "include",
-  "extend",
-  "prepend",
-  // Class and module methods
-  "initialize",
-  "new",
-  "allocate",
-  "freeze",
-  "dup",
-  "clone",
-  // Comparison and logical
-  "eql?",
-  "equal?",
-  "respond_to?",
-  "kind_of?",
-  "instance_of?",
-  "is_a?",
 ];
 
-pub fn analyze_directory(
-  path: &str,
-  total_counts: &mut HashMap<String, usize>,
-  file_count: &mut usize,
-) -> Result<(), Box<dyn std::error::Error>> {
-  let path = Path::new(path);
-
-  if path.is_file() && is_ruby_file(path) {
-    eprintln!("Analyzing file: {}", path.display());
-    analyze_file(path, total_counts)?;
-    *file_count += 1;
-    eprintln!("Files processed: {file_count}");
-  } else if path.is_dir() {
-    for entry in fs::read_dir(path)? {
-      let entry = entry?;
-      let entry_path = entry.path();
-
-      if entry_path.is_dir() && !should_skip_dir(&entry_path) {
-        eprintln!("Entering directory: {}", entry_path.display());
-        analyze_directory(entry_path.to_str().unwrap(), total_counts, file_count)?;
-      } else if is_ruby_file(&entry_path) {
-        eprintln!("Analyzing file: {}", entry_path.display());
-        analyze_file(&entry_path, total_counts)?;
-        *file_count += 1;
-        eprintln!("Files processed: {file_count}");
-      }
-    }
+/// Whether `tokens[index]` is a reserved word called as a method, i.e. follows
+/// a `.` (so also `&.`) that is not part of a `..` range.
+fn is_method_call(tokens: &[Token], index: usize) -> bool {
+  if !RESERVED_WORDS.contains(&tokens[index].text) {
+    return false;
   }
+  let mut code = tokens[..index]
+    .iter()
+    .rev()
+    .filter(|token| token.kind != TokenKind::Comment);
+  let dot =
+    |token: Option<&Token>| token.is_some_and(|t| t.kind == TokenKind::Punct && t.text == ".");
 
-  Ok(())
+  dot(code.next()) && !dot(code.next())
 }
 
-pub fn should_skip_dir(path: &Path) -> bool {
-  if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-    matches!(
-      name,
-      "target" | ".git" | "node_modules" | "vendor" | "tmp" | "log" | ".bundle"
-    )
-  } else {
-    false
-  }
+/// Words after which `/`, `%` and `<<` start a literal rather than an operator.
+const EXPRESSION_KEYWORDS: [&str; 16] = [
+  "and", "case", "do", "else", "elsif", "if", "in", "not", "or", "return", "then", "unless",
+  "until", "when", "while", "yield",
+];
+
+/// Splits Ruby source into tokens.
+///
+/// Handles heredocs, percent literals, regexes, `=begin`/`=end` blocks and
+/// `__END__` data. Interpolated code inside `"#{...}"`, backticks, regexes and
+/// interpolating heredocs and percent literals is lexed as code, with the
+/// literal text around it emitted as `Str` tokens. Symbols (`:end`), hash
+/// labels (`end:`) and `@`/`$` variables are single `Word` tokens that keep
+/// their sigil, so they never match a keyword.
+pub fn tokenize(content: &str) -> Vec<Token<'_>> {
+  let mut lexer = Lexer::new(content, 0);
+  lexer.lex_code(false);
+  lexer.tokens
 }
 
-pub fn is_ruby_file(path: &Path) -> bool {
-  if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-    matches!(ext, "rb" | "rake" | "gemspec")
-  } else if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-    matches!(
-      filename,
-      "Rakefile" | "Gemfile" | "Guardfile" | "Capfile" | "Vagrantfile"
-    )
-  } else {
-    false
-  }
+/// A heredoc whose body starts after the current line.
+struct PendingHeredoc {
+  terminator: String,
+  /// `<<~` and `<<-` allow an indented terminator.
+  indented: bool,
+  interpolate: bool,
 }
 
-pub fn analyze_file(
-  path: &Path,
-  total_counts: &mut HashMap<String, usize>,
-) -> Result<(), Box<dyn std::error::Error>> {
-  let content = fs::read_to_string(path)?;
-  let file_counts = count_keywords(&content);
+struct Lexer<'a> {
+  content: &'a str,
+  pos: usize,
+  tokens: Vec<Token<'a>>,
+  heredocs: Vec<PendingHeredoc>,
+}
 
-  for (keyword, count) in file_counts {
-    *total_counts.entry(keyword).or_insert(0) += count;
+impl<'a> Lexer<'a> {
+  fn new(content: &'a str, pos: usize) -> Self {
+    Self {
+      content,
+      pos,
+      tokens: Vec::new(),
+      heredocs: Vec::new(),
+    }
   }
 
-  Ok(())
-}
+  fn rest(&self) -> &'a str {
+    &self.content[self.pos..]
+  }
 
-pub fn count_keywords(content: &str) -> HashMap<String, usize> {
-  let mut counts = HashMap::new();
-  let mut chars = content.chars().peekable();
-  let mut current_token = String::new();
-  let mut in_string = false;
-  let mut in_comment = false;
-  let mut string_char = '\0';
-
-  while let Some(c) = chars.next() {
-    match c {
-      // Handle single-line comments (Ruby uses #)
-      '#' if !in_string => {
-        in_comment = true;
-        if !current_token.is_empty() {
-          check_and_count_token(&current_token, &mut counts);
-          current_token.clear();
-        }
-        continue;
+  fn emit(&mut self, kind: TokenKind, start: usize, end: usize) {
+    self
+      .tokens
+      .push(Token::new(kind, &self.content[start..end], start));
+    self.pos = end;
+  }
+
+  fn at_line_start(&self) -> bool {
+    self.pos == 0 || self.content[..self.pos].ends_with('\n')
+  }
+
+  /// Whether a literal may start here: after an operator, a keyword such as
+  /// `if`, or a method name followed by a space (`puts %w[a b]`) when the
+  /// literal itself is not followed by one.
+  fn literal_allowed(&self, prefix_len: usize) -> bool {
+    let Some(index) = self
+      .tokens
+      .iter()
+      .rposition(|t| t.kind != TokenKind::Comment)
+    else {
+      return true;
+    };
+    let previous = &self.tokens[index];
+
+    match previous.kind {
+      TokenKind::Punct => !matches!(previous.text, ")" | "]" | "}"),
+      TokenKind::Word
+        if EXPRESSION_KEYWORDS.contains(&previous.text) && !is_method_call(&self.tokens, index) =>
+      {
+        true
       }
-      // End single-line comment
-      '\n' if in_comment => {
-        in_comment = false;
-        continue;
+      TokenKind::Word => {
+        let spaced_before = self.content[..self.pos].ends_with([' ', '\t']);
+        let spaced_after = self.content[self.pos + prefix_len..].starts_with([' ', '\t', '\n']);
+        spaced_before && !spaced_after
       }
-      // Handle strings (double quotes and single quotes)
-      '"' | '\'' if !in_comment => {
-        if !in_string {
-          in_string = true;
-          string_char = c;
-          if !current_token.is_empty() {
-            check_and_count_token(&current_token, &mut counts);
-            current_token.clear();
+      _ => false,
+    }
+  }
+
+  /// Lexes code until the end of input or, when `nested`, until the `}`
+  /// closing the enclosing `#{`. Returns whether that `}` was found.
+  fn lex_code(&mut self, nested: bool) -> bool {
+    let mut depth: usize = 0;
+
+    while let Some(c) = self.rest().chars().next() {
+      let rest = self.rest();
+      let start = self.pos;
+
+      if c == '\n' {
+        self.pos += 1;
+        self.lex_heredoc_bodies();
+      } else if c.is_whitespace() {
+        self.pos += c.len_utf8();
+      } else if self.at_line_start() && rest.starts_with("=begin") {
+        let end = rest.find("\n=end").map_or(self.content.len(), |i| {
+          let close = start + i + 1;
+          self.content[close..]
+            .find('\n')
+            .map_or(self.content.len(), |j| close + j)
+        });
+        self.emit(TokenKind::Comment, start, end);
+      } else if self.at_line_start() && (rest == "__END__" || rest.starts_with("__END__\n")) {
+        self.emit(TokenKind::Comment, start, self.content.len());
+      } else if c == '#' {
+        let end = rest.find('\n').map_or(self.content.len(), |i| start + i);
+        self.emit(TokenKind::Comment, start, end);
+      } else if c == '"' || c == '`' {
+        self.pos += 1;
+        self.lex_literal(start, None, Some(c), true);
+      } else if c == '\'' {
+        self.pos += 1;
+        self.lex_literal(start, None, Some('\''), false);
+      } else if c == '/' && self.literal_allowed(1) {
+        self.pos += 1;
+        self.lex_literal(start, None, Some('/'), true);
+        self.pos = identifier_end(self.content, self.pos);
+        self.extend_last_token(start);
+      } else if (c == '%' && self.lex_percent_literal())
+        || (rest.starts_with("<<") && self.lex_heredoc_marker())
+      {
+        continue;
+      } else if rest.starts_with("::") {
+        self.emit(TokenKind::Punct, start, start + 1);
+        self.emit(TokenKind::Punct, start + 1, start + 2);
+      } else if c == ':' && rest[1..].starts_with('"') {
+        self.pos += 2;
+        self.lex_literal(start, None, Some('"'), true);
+      } else if c == ':' && rest[1..].starts_with(is_identifier_start) {
+        self.emit(
+          TokenKind::Word,
+          start,
+          identifier_end(self.content, start + 1),
+        );
+      } else if let Some(sigils) = variable_sigils(rest) {
+        let end = identifier_end(self.content, start + sigils);
+        self.emit(TokenKind::Word, start, end);
+      } else if is_identifier_char(c) {
+        let mut end = identifier_end(self.content, start);
+        // Hash labels such as `end: 1`
+        let after = &self.content[end..];
+        if after.starts_with(':') && !after.starts_with("::") {
+          end += 1;
+        }
+        self.emit(TokenKind::Word, start, end);
+      } else {
+        if c == '{' {
+          depth += 1;
+        } else if c == '}' {
+          if nested && depth == 0 {
+            return true;
           }
-        } else if c == string_char {
-          in_string = false;
-          string_char = '\0';
+          depth = depth.saturating_sub(1);
         }
-        continue;
+        self.emit(TokenKind::Punct, start, start + c.len_utf8());
       }
-      // Handle escape sequences in strings
-      '\\' if in_string => {
-        // Skip the next character if we're in a string (escape sequence)
-        chars.next();
-        continue;
-      }
-      // Skip content inside strings and comments
-      _ if in_string || in_comment => {
-        continue;
+    }
+
+    !nested
+  }
+
+  /// Merges every token emitted since `start` into the last one, used to
+  /// attach regex flags.
+  fn extend_last_token(&mut self, start: usize) {
+    if let Some(last) = self.tokens.last_mut() {
+      if last.offset >= start {
+        last.text = &self.content[last.offset..self.pos];
       }
-      // Handle regular tokens (Ruby allows ? in method names)
-      _ => {
-        if c.is_alphanumeric() || c == '_' || c == '?' {
-          current_token.push(c);
-        } else if !current_token.is_empty() {
-          check_and_count_token(&current_token, &mut counts);
-          current_token.clear();
+    }
+  }
+
+  /// Lexes the body of a literal whose opening delimiter ends at `self.pos`.
+  /// `open` is set for bracket delimiters, which nest. Without `close` the
+  /// literal runs to the end of input.
+  fn lex_literal(
+    &mut self,
+    start: usize,
+    open: Option<char>,
+    close: Option<char>,
+    interpolate: bool,
+  ) {
+    let mut chunk_start = start;
+    let mut depth: usize = 0;
+
+    while let Some(c) = self.rest().chars().next() {
+      if c == '\\' {
+        self.pos += 1;
+        self.pos += self.rest().chars().next().map_or(0, char::len_utf8);
+      } else if interpolate && self.rest().starts_with("#{") {
+        self.emit(TokenKind::Str, chunk_start, self.pos + 2);
+        if !self.lex_code(true) {
+          return;
         }
+        // The closing `}` starts the next chunk of literal text
+        chunk_start = self.pos;
+        self.pos += 1;
+      } else if Some(c) == open {
+        depth += 1;
+        self.pos += c.len_utf8();
+      } else if Some(c) == close {
+        self.pos += c.len_utf8();
+        if depth == 0 {
+          break;
+        }
+        depth -= 1;
+      } else {
+        self.pos += c.len_utf8();
       }
     }
+
+    if self.pos > chunk_start {
+      self.emit(TokenKind::Str, chunk_start, self.pos);
+    }
   }
 
-  // Check final token
-  if !current_token.is_empty() {
-    check_and_count_token(&current_token, &mut counts);
+  /// Lexes `%w[...]`, `%q{...}`, `%r{...}`, `%(...)` and friends at `%`.
+  fn lex_percent_literal(&mut self) -> bool {
+    let start = self.pos;
+    let mut chars = self.rest()[1..].chars();
+    let (kind, delimiter) = match (chars.next(), chars.next()) {
+      (Some(kind @ ('w' | 'W' | 'i' | 'I' | 'q' | 'Q' | 'r' | 's' | 'x')), Some(delimiter))
+        if is_percent_delimiter(delimiter) =>
+      {
+        (Some(kind), delimiter)
+      }
+      (Some(delimiter), _) if is_percent_delimiter(delimiter) => (None, delimiter),
+      _ => return false,
+    };
+
+    let prefix_len = 1 + kind.map_or(0, char::len_utf8);
+    if !self.literal_allowed(prefix_len) {
+      return false;
+    }
+
+    let close = match delimiter {
+      '(' => ')',
+      '[' => ']',
+      '{' => '}',
+      '<' => '>',
+      other => other,
+    };
+    let open = (close != delimiter).then_some(delimiter);
+    let interpolate = !matches!(kind, Some('w' | 'i' | 'q' | 's'));
+
+    self.pos += prefix_len + delimiter.len_utf8();
+    self.lex_literal(start, open, Some(close), interpolate);
+    if kind == Some('r') {
+      self.pos = identifier_end(self.content, self.pos);
+      self.extend_last_token(start);
+    }
+    true
   }
 
-  counts
-}
+  /// Lexes a heredoc marker such as `<<~SQL`, `<<-'EOS'` or `<<HTML` and
+  /// queues its body for the next line.
+  fn lex_heredoc_marker(&mut self) -> bool {
+    let start = self.pos;
+    let rest = &self.rest()[2..];
+    let indented = rest.starts_with(['~', '-']);
+    let rest = if indented { &rest[1..] } else { rest };
+
+    let (terminator, interpolate, marker_len) = match rest.chars().next() {
+      Some(quote @ ('\'' | '"' | '`')) => {
+        let Some(len) = rest[1..].find(quote) else {
+          return false;
+        };
+        (&rest[1..1 + len], quote != '\'', len + 2)
+      }
+      // A bare `<<name` needs an uppercase name so `a <<b` stays a shift
+      Some(c) if is_identifier_start(c) && (indented || c.is_uppercase()) => {
+        let len = identifier_end(rest, 0);
+        (&rest[..len], true, len)
+      }
+      _ => return false,
+    };
+    if terminator.is_empty() || terminator.contains('\n') || !self.literal_allowed(2) {
+      return false;
+    }
+
+    self.heredocs.push(PendingHeredoc {
+      terminator: terminator.to_string(),
+      indented,
+      interpolate,
+    });
+    let end = start + 2 + usize::from(indented) + marker_len;
+    self.emit(TokenKind::Str, start, end);
+    true
+  }
+
+  /// Lexes the bodies of heredocs opened on the line that just ended.
+  fn lex_heredoc_bodies(&mut self) {
+    for heredoc in std::mem::take(&mut self.heredocs) {
+      let body_start = self.pos;
+      let mut line_start = body_start;
+      let mut body_end = self.content.len();
+      let mut terminator_end = self.content.len();
+
+      while line_start < self.content.len() {
+        let line_end = self.content[line_start..]
+          .find('\n')
+          .map_or(self.content.len(), |i| line_start + i);
+        let line = &self.content[line_start..line_end];
+        let line = if heredoc.indented {
+          line.trim_start()
+        } else {
+          line
+        };
+        if line.trim_end_matches('\r') == heredoc.terminator {
+          body_end = line_start;
+          terminator_end = line_end;
+          break;
+        }
+        line_start = line_end + 1;
+      }
 
-fn check_and_count_token(token: &str, counts: &mut HashMap<String, usize>) {
-  if RUBY_KEYWORDS.contains(&token) {
-    *counts.entry(token.to_string()).or_insert(0) += 1;
+      if body_end > body_start {
+        // Lex the body on its own so interpolation cannot run past it
+        let mut body = Lexer::new(&self.content[..body_end], body_start);
+        body.lex_literal(body_start, None, None, heredoc.interpolate);
+        self.tokens.append(&mut body.tokens);
+      }
+      if terminator_end > body_end {
+        self.emit(TokenKind::Str, body_end, terminator_end);
+      }
+      // Code resumes on the line after the terminator
+      self.pos = (terminator_end + 1).min(self.content.len());
+    }
   }
 }
 
+fn is_identifier_start(c: char) -> bool {
+  c.is_alphabetic() || c == '_'
+}
+
+fn is_identifier_char(c: char) -> bool {
+  c.is_alphanumeric() || c == '_' || c == '?'
+}
+
+fn identifier_end(content: &str, pos: usize) -> usize {
+  content[pos..]
+    .char_indices()
+    .find(|(_, c)| !is_identifier_char(*c))
+    .map_or(content.len(), |(i, _)| pos + i)
+}
+
+/// Length of the `@`, `@@` or `$` sigil of a variable name at the start of
+/// `text`.
+fn variable_sigils(text: &str) -> Option<usize> {
+  let sigils = if text.starts_with("@@") {
+    2
+  } else if text.starts_with(['@', '$']) {
+    1
+  } else {
+    return None;
+  };
+  text[sigils..]
+    .starts_with(is_identifier_start)
+    .then_some(sigils)
+}
+
+fn is_percent_delimiter(c: char) -> bool {
+  !c.is_alphanumeric() && !c.is_whitespace() && c != '='
+}
+
+pub fn spec() -> Arc<RubySpec> {
+  static SPEC: OnceLock<Arc<RubySpec>> = OnceLock::new();
+  SPEC.get_or_init(|| Arc::new(RubySpec::new())).clone()
+}
+
+pub fn count_keywords(content: &str) -> HashMap<String, usize> {
+  spec().count_keywords(content)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -473,8 +721,9 @@ mod tests {
     let counts = count_keywords(&comprehensive_content);
 
     // Verify that ALL keywords are present in the comprehensive example
+    let language = spec();
     let mut missing_keywords = Vec::new();
-    for &keyword in RUBY_KEYWORDS {
+    for keyword in language.keywords() {
       if !counts.contains_key(keyword) {
         missing_keywords.push(keyword);
       }
@@ -487,7 +736,7 @@ mod tests {
     }
 
     // Verify specific keywords appear at least once
-    for &keyword in RUBY_KEYWORDS {
+    for keyword in language.keywords() {
       assert!(
         counts.get(keyword).unwrap_or(&0) >= &1,
         "Keyword '{keyword}' should appear at least once in the comprehensive example"
@@ -496,7 +745,7 @@ mod tests {
 
     println!(
       "✅ All {} Ruby keywords are properly tested!",
-      RUBY_KEYWORDS.len()
+      spec().keywords().len()
     );
 
     // Test edge case: no keywords
@@ -510,24 +759,24 @@ mod tests {
     use std::path::PathBuf;
 
     // Ruby files should be detected
-    assert!(is_ruby_file(&PathBuf::from("app.rb")));
-    assert!(is_ruby_file(&PathBuf::from("config.rb")));
-    assert!(is_ruby_file(&PathBuf::from("script.rake")));
-    assert!(is_ruby_file(&PathBuf::from("my_gem.gemspec")));
-    assert!(is_ruby_file(&PathBuf::from("Rakefile")));
-    assert!(is_ruby_file(&PathBuf::from("Gemfile")));
-    assert!(is_ruby_file(&PathBuf::from("Guardfile")));
-    assert!(is_ruby_file(&PathBuf::from("Capfile")));
-    assert!(is_ruby_file(&PathBuf::from("Vagrantfile")));
-    assert!(is_ruby_file(&PathBuf::from("path/to/file.rb")));
+    assert!(spec().is_source_file(&PathBuf::from("app.rb")));
+    assert!(spec().is_source_file(&PathBuf::from("config.rb")));
+    assert!(spec().is_source_file(&PathBuf::from("script.rake")));
+    assert!(spec().is_source_file(&PathBuf::from("my_gem.gemspec")));
+    assert!(spec().is_source_file(&PathBuf::from("Rakefile")));
+    assert!(spec().is_source_file(&PathBuf::from("Gemfile")));
+    assert!(spec().is_source_file(&PathBuf::from("Guardfile")));
+    assert!(spec().is_source_file(&PathBuf::from("Capfile")));
+    assert!(spec().is_source_file(&PathBuf::from("Vagrantfile")));
+    assert!(spec().is_source_file(&PathBuf::from("path/to/file.rb")));
 
     // Non-Ruby files should not be detected
-    assert!(!is_ruby_file(&PathBuf::from("file.rs")));
-    assert!(!is_ruby_file(&PathBuf::from("file.py")));
-    assert!(!is_ruby_file(&PathBuf::from("file.js")));
-    assert!(!is_ruby_file(&PathBuf::from("file.txt")));
-    assert!(!is_ruby_file(&PathBuf::from("file")));
-    assert!(!is_ruby_file(&PathBuf::from("app.rb.bak")));
+    assert!(!spec().is_source_file(&PathBuf::from("file.rs")));
+    assert!(!spec().is_source_file(&PathBuf::from("file.py")));
+    assert!(!spec().is_source_file(&PathBuf::from("file.js")));
+    assert!(!spec().is_source_file(&PathBuf::from("file.txt")));
+    assert!(!spec().is_source_file(&PathBuf::from("file")));
+    assert!(!spec().is_source_file(&PathBuf::from("app.rb.bak")));
   }
 
   #[test]
@@ -535,20 +784,20 @@ mod tests {
     use std::path::PathBuf;
 
     // Directories that should be skipped
-    assert!(should_skip_dir(&PathBuf::from("vendor")));
-    assert!(should_skip_dir(&PathBuf::from("tmp")));
-    assert!(should_skip_dir(&PathBuf::from("log")));
-    assert!(should_skip_dir(&PathBuf::from(".bundle")));
-    assert!(should_skip_dir(&PathBuf::from("target")));
-    assert!(should_skip_dir(&PathBuf::from(".git")));
-    assert!(should_skip_dir(&PathBuf::from("node_modules")));
+    assert!(spec().should_skip_dir(&PathBuf::from("vendor")));
+    assert!(spec().should_skip_dir(&PathBuf::from("tmp")));
+    assert!(spec().should_skip_dir(&PathBuf::from("log")));
+    assert!(spec().should_skip_dir(&PathBuf::from(".bundle")));
+    assert!(spec().should_skip_dir(&PathBuf::from("target")));
+    assert!(spec().should_skip_dir(&PathBuf::from(".git")));
+    assert!(spec().should_skip_dir(&PathBuf::from("node_modules")));
 
     // Directories that should not be skipped
-    assert!(!should_skip_dir(&PathBuf::from("lib")));
-    assert!(!should_skip_dir(&PathBuf::from("app")));
-    assert!(!should_skip_dir(&PathBuf::from("config")));
-    assert!(!should_skip_dir(&PathBuf::from("spec")));
-    assert!(!should_skip_dir(&PathBuf::from("test")));
+    assert!(!spec().should_skip_dir(&PathBuf::from("lib")));
+    assert!(!spec().should_skip_dir(&PathBuf::from("app")));
+    assert!(!spec().should_skip_dir(&PathBuf::from("config")));
+    assert!(!spec().should_skip_dir(&PathBuf::from("spec")));
+    assert!(!spec().should_skip_dir(&PathBuf::from("test")));
   }
 
   #[test]
@@ -604,12 +853,12 @@ mod tests {
 
   #[test]
   fn test_all_ruby_keywords_recognized() {
-    // Test that all keywords in RUBY_KEYWORDS are properly recognized
-    for keyword in RUBY_KEYWORDS {
+    // Test that all keywords of the language are properly recognized
+    for keyword in spec().keywords() {
       let content = format!("{keyword} ");
       let counts = count_keywords(&content);
       assert_eq!(
-        counts.get(*keyword),
+        counts.get(keyword),
         Some(&1),
         "Keyword '{keyword}' was not properly counted"
       );
@@ -622,11 +871,15 @@ mod tests {
     let mut counts = HashMap::new();
 
     // Test with non-existent file
-    let result = analyze_file(&PathBuf::from("non_existent_file.rb"), &mut counts);
+    let result = crate::analyze_file(
+      &PathBuf::from("non_existent_file.rb"),
+      spec().as_ref(),
+      &mut counts,
+    );
     assert!(result.is_err());
 
     // Test with directory instead of file (should error)
-    let result = analyze_file(&PathBuf::from("/"), &mut counts);
+    let result = crate::analyze_file(&PathBuf::from("/"), spec().as_ref(), &mut counts);
     assert!(result.is_err());
   }
 
@@ -719,4 +972,129 @@ mod tests {
     assert_eq!(counts.get("ensure"), Some(&1));
     assert_eq!(counts.get("end"), Some(&1));
   }
+
+  fn texts<'a>(tokens: &[Token<'a>]) -> Vec<(TokenKind, &'a str)> {
+    tokens
+      .iter()
+      .map(|token| (token.kind, token.text))
+      .collect()
+  }
+
+  #[test]
+  fn test_tokenize_heredocs() {
+    let content = "sql = <<~SQL.strip + <<-'RAW'\n  select * from users if #{where}\n  SQL\n  raw #{end}\n  RAW\nreturn sql\n";
+    let tokens = tokenize(content);
+    assert_eq!(
+      texts(&tokens),
+      vec![
+        (TokenKind::Word, "sql"),
+        (TokenKind::Punct, "="),
+        (TokenKind::Str, "<<~SQL"),
+        (TokenKind::Punct, "."),
+        (TokenKind::Word, "strip"),
+        (TokenKind::Punct, "+"),
+        (TokenKind::Str, "<<-'RAW'"),
+        (TokenKind::Str, "  select * from users if #{"),
+        (TokenKind::Word, "where"),
+        (TokenKind::Str, "}\n"),
+        (TokenKind::Str, "  SQL"),
+        (TokenKind::Str, "  raw #{end}\n"),
+        (TokenKind::Str, "  RAW"),
+        (TokenKind::Word, "return"),
+        (TokenKind::Word, "sql"),
+      ]
+    );
+
+    // `<<` with a space or lowercase name is a shift
+    let counts = count_keywords("list << item if ok\nbits = flags <<shift if ok\n");
+    assert_eq!(counts.get("if"), Some(&2));
+  }
+
+  #[test]
+  fn test_tokenize_percent_literals() {
+    let content =
+      "KEYWORDS = %w[if unless end]\nre = %r{(do|end)}i\nmsg = %Q(#{name} (then) #{x ? 1 : 2})\nn = total % count if ok";
+    let counts = count_keywords(content);
+    assert_eq!(counts.get("unless"), None);
+    assert_eq!(counts.get("do"), None);
+    assert_eq!(counts.get("then"), None);
+    assert_eq!(counts.get("end"), None);
+    assert_eq!(counts.get("if"), Some(&1));
+
+    let tokens = tokenize("[%i<a b>, %q{nested {braces} if}]");
+    assert_eq!(
+      texts(&tokens),
+      vec![
+        (TokenKind::Punct, "["),
+        (TokenKind::Str, "%i<a b>"),
+        (TokenKind::Punct, ","),
+        (TokenKind::Str, "%q{nested {braces} if}"),
+        (TokenKind::Punct, "]"),
+      ]
+    );
+
+    // Method calls without parentheses take literals too
+    let counts = count_keywords("puts %w[class def]\nvalidates :name, format: /\\A(if|end)\\z/");
+    assert_eq!(counts.get("class"), None);
+    assert_eq!(counts.get("if"), None);
+  }
+
+  #[test]
+  fn test_tokenize_comments_symbols_and_variables() {
+    let content = "=begin\ndef hidden; end\n=end\nredirect_to :end, if: true\n@class = $end\nFoo::Bar\n__END__\ndef data; end\n";
+    let tokens = tokenize(content);
+    assert_eq!(
+      texts(&tokens),
+      vec![
+        (TokenKind::Comment, "=begin\ndef hidden; end\n=end"),
+        (TokenKind::Word, "redirect_to"),
+        (TokenKind::Word, ":end"),
+        (TokenKind::Punct, ","),
+        (TokenKind::Word, "if:"),
+        (TokenKind::Word, "true"),
+        (TokenKind::Word, "@class"),
+        (TokenKind::Punct, "="),
+        (TokenKind::Word, "$end"),
+        (TokenKind::Word, "Foo"),
+        (TokenKind::Punct, ":"),
+        (TokenKind::Punct, ":"),
+        (TokenKind::Word, "Bar"),
+        (TokenKind::Comment, "__END__\ndef data; end\n"),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_interpolation_counts_code() {
+    let content = r#"
+      message = "Hello #{user.admin? ? "admin" : name} and #{items.map { |i| i.to_s }.join}"
+      label = 'no #{interpolation} if single quoted'
+      cmd = `ls #{dir if dir}`
+    "#;
+    let counts = count_keywords(content);
+    assert_eq!(counts.get("map"), Some(&1));
+    assert_eq!(counts.get("if"), Some(&1));
+    assert_eq!(counts.get("and"), None);
+  }
+
+  #[test]
+  fn test_reserved_words_as_method_names() {
+    let content = "foo.end
+obj.class.name
+x.then { |v| v }
+user&.then(&:save)
+r = 1..nil
+t = x.then / 2
+if ok then go end
+";
+    let counts = count_keywords(content);
+    assert_eq!(counts.get("end"), Some(&1));
+    assert_eq!(counts.get("class"), None);
+    assert_eq!(counts.get("then"), Some(&1));
+    assert_eq!(counts.get("nil"), Some(&1));
+    assert_eq!(counts.get("if"), Some(&1));
+
+    let tokens = tokenize("t = x.then / 2 / 3");
+    assert!(tokens.iter().all(|token| token.kind != TokenKind::Str));
+  }
 }
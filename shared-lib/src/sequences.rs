@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::lexer::{Token, TokenKind};
+use crate::spec::LanguageSpec;
+
+/// How keyword sequences (n-grams) are counted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceOptions {
+  /// Keywords per sequence; 2 counts pairs such as `unsafe fn`.
+  pub length: usize,
+  /// Most tokens from one keyword of a sequence to the next, not counting
+  /// comments. 1 only joins adjacent keywords; 2, the default, also joins
+  /// `pub(crate)` and `impl Display for`.
+  pub window: usize,
+}
+
+impl Default for SequenceOptions {
+  fn default() -> Self {
+    Self {
+      length: 2,
+      window: 2,
+    }
+  }
+}
+
+/// Keyword pairs and sequences found in a file, keyed by their keywords
+/// joined with spaces, e.g. `unsafe fn`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SequenceCounts {
+  /// Keywords followed by another within the window, whatever the
+  /// sequence length; the co-occurrence matrix is built from these.
+  pub pairs: HashMap<String, usize>,
+  /// Sequences of `length` keywords.
+  pub sequences: HashMap<String, usize>,
+}
+
+/// Counts the keyword pairs and sequences among `tokens`, already lexed from
+/// `content`. Only keywords take part, so `pub(crate) fn` is the sequence
+/// `pub crate fn`.
+pub fn count_sequences(
+  spec: &dyn LanguageSpec,
+  content: &str,
+  tokens: &[Token],
+  options: SequenceOptions,
+) -> SequenceCounts {
+  let mut counts = SequenceCounts::default();
+  let length = options.length.max(2);
+
+  // Runs of keywords, each at most `window` tokens after the previous one
+  let mut runs: Vec<Vec<&str>> = Vec::new();
+  let mut last_keyword = None;
+  for (position, (index, token)) in tokens
+    .iter()
+    .enumerate()
+    .filter(|(_, token)| token.kind != TokenKind::Comment)
+    .enumerate()
+  {
+    if token.kind != TokenKind::Word || !spec.is_keyword_token(content, tokens, index) {
+      continue;
+    }
+    match (runs.last_mut(), last_keyword) {
+      (Some(run), Some(last)) if position - last <= options.window => run.push(token.text),
+      _ => runs.push(vec![token.text]),
+    }
+    last_keyword = Some(position);
+  }
+
+  for run in &runs {
+    for pair in run.windows(2) {
+      *counts.pairs.entry(pair.join(" ")).or_insert(0) += 1;
+    }
+    for sequence in run.windows(length) {
+      *counts.sequences.entry(sequence.join(" ")).or_insert(0) += 1;
+    }
+  }
+
+  counts
+}
+
+/// How often each of the most paired keywords is followed by each other one.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CooccurrenceMatrix {
+  /// Row and column labels, the keyword in the most pairs first.
+  pub keywords: Vec<String>,
+  /// `counts[row][column]`: how often `keywords[column]` follows
+  /// `keywords[row]`.
+  pub counts: Vec<Vec<usize>>,
+}
+
+impl CooccurrenceMatrix {
+  /// A matrix of the `size` keywords taking part in the most pairs.
+  pub fn from_pairs(pairs: &HashMap<String, usize>, size: usize) -> Self {
+    let split = |pair: &str| -> Option<(String, String)> {
+      pair
+        .split_once(' ')
+        .map(|(first, second)| (first.to_string(), second.to_string()))
+    };
+
+    let mut totals: HashMap<String, usize> = HashMap::new();
+    for (pair, count) in pairs {
+      if let Some((first, second)) = split(pair) {
+        *totals.entry(first).or_insert(0) += count;
+        *totals.entry(second).or_insert(0) += count;
+      }
+    }
+    let mut keywords: Vec<(String, usize)> = totals.into_iter().collect();
+    keywords.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let keywords: Vec<String> = keywords
+      .into_iter()
+      .take(size)
+      .map(|(keyword, _)| keyword)
+      .collect();
+
+    let mut counts = vec![vec![0; keywords.len()]; keywords.len()];
+    for (pair, count) in pairs {
+      let Some((first, second)) = split(pair) else {
+        continue;
+      };
+      let row = keywords.iter().position(|keyword| *keyword == first);
+      let column = keywords.iter().position(|keyword| *keyword == second);
+      if let (Some(row), Some(column)) = (row, column) {
+        counts[row][column] += count;
+      }
+    }
+
+    Self { keywords, counts }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.keywords.is_empty()
+  }
+
+  /// The largest count in the matrix.
+  pub fn max(&self) -> usize {
+    self.counts.iter().flatten().copied().max().unwrap_or(0)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Language;
+
+  fn sequences(source: &str, length: usize, window: usize) -> SequenceCounts {
+    let spec = Language::Rust.spec();
+    let tokens = spec.tokenize(source);
+    count_sequences(
+      spec.as_ref(),
+      source,
+      &tokens,
+      SequenceOptions { length, window },
+    )
+  }
+
+  #[test]
+  fn test_count_sequences() {
+    let source = "\
+pub(crate) unsafe fn a() {}
+pub /* comment */ async fn b() {}
+";
+    let counts = sequences(source, 2, 2);
+    assert_eq!(counts.sequences, counts.pairs);
+    assert_eq!(counts.pairs["pub crate"], 1);
+    assert_eq!(counts.pairs["crate unsafe"], 1);
+    assert_eq!(counts.pairs["unsafe fn"], 1);
+    assert_eq!(counts.pairs["pub async"], 1);
+    assert_eq!(counts.pairs["async fn"], 1);
+    assert_eq!(counts.pairs.len(), 5);
+
+    // `pub ( crate` is too far apart for a window of one token
+    let counts = sequences(source, 3, 1);
+    assert!(!counts.pairs.contains_key("pub crate"));
+    assert_eq!(counts.sequences.len(), 1);
+    assert_eq!(counts.sequences["pub async fn"], 1);
+  }
+
+  #[test]
+  fn test_cooccurrence_matrix() {
+    let pairs: HashMap<String, usize> = [("unsafe fn", 3), ("pub fn", 5), ("pub unsafe", 1)]
+      .into_iter()
+      .map(|(pair, count)| (pair.to_string(), count))
+      .collect();
+    let matrix = CooccurrenceMatrix::from_pairs(&pairs, 2);
+    assert_eq!(matrix.keywords, ["fn", "pub"]);
+    assert_eq!(matrix.counts, [[0, 0], [5, 0]]);
+    assert_eq!(matrix.max(), 5);
+    assert!(CooccurrenceMatrix::from_pairs(&HashMap::new(), 10).is_empty());
+  }
+}
@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AnalyzerError;
+use crate::identifiers::NamingConvention;
+use crate::lexer::{self, Token, TokenKind};
+
+/// A block comment delimiter pair such as `/*` ... `*/`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlockComment {
+  pub start: String,
+  pub end: String,
+  #[serde(default)]
+  pub nested: bool,
+}
+
+impl BlockComment {
+  pub fn new(start: &str, end: &str) -> Self {
+    Self {
+      start: start.to_string(),
+      end: end.to_string(),
+      nested: false,
+    }
+  }
+}
+
+/// A string literal delimiter pair. `escape` is `None` for raw strings.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StringDelimiter {
+  pub start: String,
+  pub end: String,
+  #[serde(default)]
+  pub escape: Option<char>,
+}
+
+impl StringDelimiter {
+  pub fn new(start: &str, end: &str, escape: Option<char>) -> Self {
+    Self {
+      start: start.to_string(),
+      end: end.to_string(),
+      escape,
+    }
+  }
+}
+
+/// A named group of keywords, e.g. "Control flow".
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeywordCategory {
+  pub name: String,
+  #[serde(default)]
+  pub keywords: Vec<String>,
+}
+
+/// Category reported for keywords that are not in any category.
+pub const UNCATEGORIZED: &str = "Other";
+
+/// Everything the analyzer needs to know about a language: how to find its
+/// files, which directories to skip and how to lex its source.
+///
+/// Definitions can be written in TOML or JSON using the field names below;
+/// the built-in languages ship as TOML files in `shared-lib/languages`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LanguageDefinition {
+  /// Short lowercase identifier, used for lookups and output file names.
+  pub id: String,
+  /// Human readable name shown in reports.
+  pub name: String,
+  /// Alternative names accepted when looking the language up.
+  pub aliases: Vec<String>,
+  pub keywords: Vec<String>,
+  /// Keywords grouped by category. Loading a definition adds them to
+  /// `keywords`, so they need not be listed twice.
+  pub categories: Vec<KeywordCategory>,
+  /// File extensions without the leading dot.
+  pub extensions: Vec<String>,
+  /// Exact file names that belong to the language, e.g. `Rakefile`.
+  pub file_names: Vec<String>,
+  /// Interpreter names recognised in a `#!` line of extensionless scripts.
+  pub interpreters: Vec<String>,
+  pub line_comments: Vec<String>,
+  pub block_comments: Vec<BlockComment>,
+  pub strings: Vec<StringDelimiter>,
+  /// Characters besides alphanumerics and `_` that may appear in identifiers.
+  pub identifier_chars: String,
+  pub skip_dirs: Vec<String>,
+  /// Comment prefixes that document the item that follows, e.g. `///`.
+  pub doc_comments: Vec<String>,
+  /// Keywords introducing the items measured for doc coverage, e.g. `fn`.
+  pub documented_keywords: Vec<String>,
+  /// Whether an item is documented by a string starting its body, as with
+  /// Python docstrings.
+  pub docstrings: bool,
+  /// Naming conventions identifiers are expected to follow; an identifier
+  /// matching none of them is reported as a violation.
+  pub naming_conventions: Vec<NamingConvention>,
+  /// Keywords starting a function, e.g. `fn`, used for complexity.
+  pub function_keywords: Vec<String>,
+  /// Keywords adding a decision point, e.g. `if` or `case`.
+  pub decision_keywords: Vec<String>,
+  /// Operators adding a decision point, e.g. `&&`.
+  pub decision_operators: Vec<String>,
+  /// Whether blocks are delimited by indentation instead of braces.
+  pub indentation_blocks: bool,
+}
+
+impl LanguageDefinition {
+  pub fn from_toml_str(content: &str) -> Result<Self, AnalyzerError> {
+    let mut definition: Self =
+      toml::from_str(content).map_err(AnalyzerError::invalid_definition)?;
+    definition.merge_categories();
+    definition.validate()?;
+    Ok(definition)
+  }
+
+  pub fn from_json_str(content: &str) -> Result<Self, AnalyzerError> {
+    let mut definition: Self =
+      serde_json::from_str(content).map_err(AnalyzerError::invalid_definition)?;
+    definition.merge_categories();
+    definition.validate()?;
+    Ok(definition)
+  }
+
+  /// Adds every categorized keyword missing from `keywords`. Called by the
+  /// loaders; use it directly for definitions deserialized some other way.
+  pub fn merge_categories(&mut self) {
+    for category in &self.categories {
+      for keyword in &category.keywords {
+        if !self.keywords.contains(keyword) {
+          self.keywords.push(keyword.clone());
+        }
+      }
+    }
+  }
+
+  /// Loads a definition file, choosing the format from its extension.
+  pub fn from_file(path: &Path) -> Result<Self, AnalyzerError> {
+    let result = match path.extension().and_then(|ext| ext.to_str()) {
+      Some("toml") => Self::from_toml_str(&read_definition(path)?),
+      Some("json") => Self::from_json_str(&read_definition(path)?),
+      _ => Err(AnalyzerError::invalid_definition(
+        "unsupported format (expected .toml or .json)",
+      )),
+    };
+
+    result.map_err(|e| e.in_definition(path))
+  }
+
+  /// Loads every `.toml` and `.json` definition in `dir`, sorted by file name.
+  pub fn load_dir(dir: &Path) -> Result<Vec<Self>, AnalyzerError> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| AnalyzerError::io(dir, e))? {
+      let path = entry.map_err(|e| AnalyzerError::io(dir, e))?.path();
+      if path.is_file()
+        && matches!(
+          path.extension().and_then(|ext| ext.to_str()),
+          Some("toml" | "json")
+        )
+      {
+        paths.push(path);
+      }
+    }
+    paths.sort();
+
+    paths.iter().map(|path| Self::from_file(path)).collect()
+  }
+
+  /// Checks the invariants the lexer relies on. Called by the loaders; use it
+  /// directly for definitions deserialized some other way.
+  pub fn validate(&self) -> Result<(), AnalyzerError> {
+    if self.id.trim().is_empty() {
+      return Err(AnalyzerError::invalid_definition(
+        "language definition is missing an `id`",
+      ));
+    }
+    if self.extensions.is_empty() && self.file_names.is_empty() {
+      return Err(AnalyzerError::invalid_definition(format!(
+        "language `{}` has no extensions or file names",
+        self.id
+      )));
+    }
+    if let Some(empty) = self
+      .line_comments
+      .iter()
+      .chain(self.block_comments.iter().flat_map(|c| [&c.start, &c.end]))
+      .chain(self.strings.iter().flat_map(|s| [&s.start, &s.end]))
+      .find(|marker| marker.is_empty())
+    {
+      return Err(AnalyzerError::invalid_definition(format!(
+        "language `{}` has an empty delimiter {empty:?}",
+        self.id
+      )));
+    }
+    for (field, keywords) in [
+      ("documented_keywords", &self.documented_keywords),
+      ("function_keywords", &self.function_keywords),
+      ("decision_keywords", &self.decision_keywords),
+    ] {
+      if let Some(keyword) = keywords
+        .iter()
+        .find(|keyword| !self.keywords.contains(keyword))
+      {
+        return Err(AnalyzerError::invalid_definition(format!(
+          "language `{}` lists `{keyword}` in `{field}`, but it is not one of its keywords",
+          self.id
+        )));
+      }
+    }
+    Ok(())
+  }
+}
+
+fn read_definition(path: &Path) -> Result<String, AnalyzerError> {
+  fs::read_to_string(path).map_err(|e| AnalyzerError::io(path, e))
+}
+
+/// A language the analyzer can count keywords for.
+///
+/// Implementors only have to provide a [`LanguageDefinition`]; lexing, file
+/// matching and directory filtering are derived from it and can be overridden
+/// when a language needs more than the table-driven behaviour.
+pub trait LanguageSpec: Send + Sync {
+  fn definition(&self) -> &LanguageDefinition;
+
+  fn id(&self) -> &str {
+    &self.definition().id
+  }
+
+  fn name(&self) -> &str {
+    let definition = self.definition();
+    if definition.name.is_empty() {
+      &definition.id
+    } else {
+      &definition.name
+    }
+  }
+
+  fn keywords(&self) -> &[String] {
+    &self.definition().keywords
+  }
+
+  fn is_keyword(&self, token: &str) -> bool {
+    self.keywords().iter().any(|keyword| keyword == token)
+  }
+
+  /// Name of the category `keyword` is listed under, if any.
+  fn keyword_category(&self, keyword: &str) -> Option<&str> {
+    self
+      .definition()
+      .categories
+      .iter()
+      .find(|category| category.keywords.iter().any(|k| k == keyword))
+      .map(|category| category.name.as_str())
+  }
+
+  /// Whether `name` is this language's id or one of its aliases.
+  fn matches_name(&self, name: &str) -> bool {
+    let definition = self.definition();
+    definition.id.eq_ignore_ascii_case(name)
+      || definition
+        .aliases
+        .iter()
+        .any(|alias| alias.eq_ignore_ascii_case(name))
+  }
+
+  fn is_source_file(&self, path: &Path) -> bool {
+    let definition = self.definition();
+    let extension_matches = path
+      .extension()
+      .and_then(|ext| ext.to_str())
+      .is_some_and(|ext| {
+        definition
+          .extensions
+          .iter()
+          .any(|candidate| candidate.eq_ignore_ascii_case(ext))
+      });
+    let file_name_matches = path
+      .file_name()
+      .and_then(|name| name.to_str())
+      .is_some_and(|name| {
+        definition
+          .file_names
+          .iter()
+          .any(|candidate| candidate == name)
+      });
+
+    extension_matches || file_name_matches
+  }
+
+  /// Whether `interpreter` (as found in a shebang, without any version
+  /// suffix) runs this language.
+  fn matches_interpreter(&self, interpreter: &str) -> bool {
+    self
+      .definition()
+      .interpreters
+      .iter()
+      .any(|candidate| candidate == interpreter)
+  }
+
+  fn should_skip_dir(&self, path: &Path) -> bool {
+    path
+      .file_name()
+      .and_then(|name| name.to_str())
+      .is_some_and(|name| self.definition().skip_dirs.iter().any(|dir| dir == name))
+  }
+
+  fn tokenize<'a>(&self, content: &'a str) -> Vec<Token<'a>> {
+    lexer::tokenize(self.definition(), content)
+  }
+
+  /// Whether the word at `tokens[index]` is used as a keyword. Languages
+  /// with context-dependent (soft) keywords override this to look at the
+  /// surrounding tokens; `content` is the source they were taken from.
+  fn is_keyword_token(&self, _content: &str, tokens: &[Token], index: usize) -> bool {
+    self.is_keyword(tokens[index].text)
+  }
+
+  fn count_keywords(&self, content: &str) -> HashMap<String, usize> {
+    self.count_keywords_in(content, &self.tokenize(content))
+  }
+
+  /// Counts the keywords among `tokens`, already lexed from `content`.
+  fn count_keywords_in(&self, content: &str, tokens: &[Token]) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+
+    for (index, token) in tokens.iter().enumerate() {
+      if token.kind == TokenKind::Word && self.is_keyword_token(content, tokens, index) {
+        *counts.entry(token.text.to_string()).or_insert(0) += 1;
+      }
+    }
+
+    counts
+  }
+}
+
+impl LanguageSpec for LanguageDefinition {
+  fn definition(&self) -> &LanguageDefinition {
+    self
+  }
+}
+
+#[cfg(test)]
+pub(crate) fn to_strings(values: &[&str]) -> Vec<String> {
+  values.iter().map(|value| value.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const TOY_TOML: &str = r#"
+id = "toy"
+name = "Toy"
+extensions = ["toy"]
+line_comments = ["--"]
+keywords = ["when", "then"]
+
+[[categories]]
+name = "Blocks"
+keywords = ["then", "end"]
+
+[[block_comments]]
+start = "{-"
+end = "-}"
+nested = true
+
+[[strings]]
+start = "\""
+end = "\""
+escape = "\\"
+
+[[strings]]
+start = "`"
+end = "`"
+"#;
+
+  #[test]
+  fn test_from_toml_str() {
+    let definition = LanguageDefinition::from_toml_str(TOY_TOML).unwrap();
+    assert_eq!(definition.id, "toy");
+    assert!(definition.block_comments[0].nested);
+    assert_eq!(definition.strings[0].escape, Some('\\'));
+    assert_eq!(definition.strings[1].escape, None);
+    assert!(definition.skip_dirs.is_empty());
+    assert_eq!(definition.keywords, ["when", "then", "end"]);
+    assert_eq!(definition.keyword_category("end"), Some("Blocks"));
+    assert_eq!(definition.keyword_category("when"), None);
+
+    let counts =
+      definition.count_keywords("when x -- then\n{- end {- end -} end -} then \"end\" `end\\` end");
+    assert_eq!(counts.get("when"), Some(&1));
+    assert_eq!(counts.get("then"), Some(&1));
+    assert_eq!(counts.get("end"), Some(&1));
+  }
+
+  #[test]
+  fn test_from_json_str() {
+    let json = r#"{
+      "id": "toy",
+      "extensions": ["toy"],
+      "keywords": ["when"],
+      "strings": [{ "start": "'", "end": "'" }]
+    }"#;
+    let definition = LanguageDefinition::from_json_str(json).unwrap();
+    assert_eq!(definition.name(), "toy"); // Falls back to the id
+    assert_eq!(
+      definition.count_keywords("when 'when'").get("when"),
+      Some(&1)
+    );
+  }
+
+  #[test]
+  fn test_invalid_definitions() {
+    assert!(LanguageDefinition::from_toml_str("name = \"No id\"\nextensions = [\"x\"]").is_err());
+    assert!(LanguageDefinition::from_toml_str("id = \"noext\"").is_err());
+    assert!(LanguageDefinition::from_toml_str(
+      "id = \"x\"\nextensions = [\"x\"]\nline_comments = [\"\"]"
+    )
+    .is_err());
+    assert!(LanguageDefinition::from_toml_str(
+      "id = \"x\"\nextensions = [\"x\"]\ndocumented_keywords = [\"fn\"]"
+    )
+    .is_err());
+    assert!(LanguageDefinition::from_toml_str(
+      "id = \"x\"\nextensions = [\"x\"]\ndecision_keywords = [\"if\"]"
+    )
+    .is_err());
+    assert!(LanguageDefinition::from_toml_str("id = [").is_err());
+    assert!(LanguageDefinition::from_file(Path::new("definition.yaml")).is_err());
+    assert!(LanguageDefinition::from_file(Path::new("missing.toml")).is_err());
+  }
+
+  #[test]
+  fn test_load_bundled_definitions_dir() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("languages");
+    let definitions = LanguageDefinition::load_dir(&dir).unwrap();
+    let ids: Vec<_> = definitions.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(
+      ids,
+      [
+        "dart",
+        "go",
+        "javascript",
+        "python",
+        "ruby",
+        "rust",
+        "typescript"
+      ]
+    );
+  }
+}
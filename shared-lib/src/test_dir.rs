@@ -0,0 +1,59 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A fresh directory of test files below the system temp directory, removed
+/// when dropped, so also when an assertion fails.
+pub(crate) struct TestDir {
+  root: PathBuf,
+}
+
+impl TestDir {
+  /// Creates the directory with `files`, given as paths relative to it and
+  /// their contents. A path ending in `/` creates an empty directory.
+  pub(crate) fn new(files: &[(&str, &str)]) -> Self {
+    static DIRS: AtomicUsize = AtomicUsize::new(0);
+    let root = std::env::temp_dir().join(format!(
+      "keyword_test_{}_{}",
+      std::process::id(),
+      DIRS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+
+    let dir = Self { root };
+    for (path, contents) in files {
+      match path.strip_suffix('/') {
+        Some(path) => fs::create_dir_all(dir.join(path)).unwrap(),
+        None => dir.write(path, contents),
+      }
+    }
+    dir
+  }
+
+  /// Writes `contents` to `path`, creating its parent directories.
+  pub(crate) fn write(&self, path: &str, contents: impl AsRef<[u8]>) {
+    let path = self.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+  }
+
+  pub(crate) fn path(&self) -> &Path {
+    &self.root
+  }
+
+  /// The directory as a `&str`, as most analysis entry points take it.
+  pub(crate) fn as_str(&self) -> &str {
+    self.root.to_str().unwrap()
+  }
+
+  pub(crate) fn join(&self, path: &str) -> PathBuf {
+    self.root.join(path)
+  }
+}
+
+impl Drop for TestDir {
+  fn drop(&mut self) {
+    let _ = fs::remove_dir_all(&self.root);
+  }
+}
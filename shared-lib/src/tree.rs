@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::metrics::LineCounts;
+use crate::FileAnalysis;
+
+/// Manifest files that mark the root of a crate or package.
+const MANIFESTS: [&str; 4] = ["Cargo.toml", "package.json", "go.mod", "pubspec.yaml"];
+
+/// A crate or package found while walking, identified by its manifest.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Package {
+  /// Directory holding the manifest, relative to the analyzed directory.
+  /// Empty for the analyzed directory itself.
+  pub path: String,
+  pub name: String,
+  /// File name of the manifest, e.g. `Cargo.toml`.
+  pub manifest: String,
+}
+
+/// Looks for a package manifest in `dir`. The name comes from the manifest,
+/// or from the directory name when the manifest has none (e.g. a Cargo
+/// workspace root).
+pub fn detect_package(dir: &Path, root: &Path) -> Option<Package> {
+  MANIFESTS.iter().find_map(|manifest| {
+    let content = fs::read_to_string(dir.join(manifest)).ok()?;
+    let name = package_name(manifest, &content).or_else(|| {
+      let dir = dir.canonicalize().ok()?;
+      Some(dir.file_name()?.to_string_lossy().into_owned())
+    })?;
+
+    Some(Package {
+      path: dir
+        .strip_prefix(root)
+        .map(|relative| relative.display().to_string())
+        .unwrap_or_default(),
+      name,
+      manifest: manifest.to_string(),
+    })
+  })
+}
+
+fn package_name(manifest: &str, content: &str) -> Option<String> {
+  let name = match manifest {
+    "Cargo.toml" => {
+      let table: toml::Table = toml::from_str(content).ok()?;
+      table.get("package")?.get("name")?.as_str()?.to_string()
+    }
+    "package.json" => {
+      let value: serde_json::Value = serde_json::from_str(content).ok()?;
+      value.get("name")?.as_str()?.to_string()
+    }
+    "go.mod" => content
+      .lines()
+      .find_map(|line| line.trim().strip_prefix("module "))?
+      .trim()
+      .to_string(),
+    "pubspec.yaml" => content
+      .lines()
+      .find_map(|line| line.strip_prefix("name:"))?
+      .trim()
+      .trim_matches(|c| c == '"' || c == '\'')
+      .to_string(),
+    _ => return None,
+  };
+
+  (!name.is_empty()).then_some(name)
+}
+
+/// The innermost package in `packages` containing `path`.
+pub fn package_for<'a>(packages: &'a [Package], path: &str) -> Option<&'a Package> {
+  packages
+    .iter()
+    .filter(|package| Path::new(path).starts_with(&package.path))
+    .max_by_key(|package| package.path.len())
+}
+
+/// Keyword counts rolled up for a directory and everything below it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DirectoryNode {
+  /// Path relative to the analyzed directory; empty for the root.
+  pub path: String,
+  /// Last path component, `.` for the root.
+  pub name: String,
+  /// The package whose manifest lives in this directory, if any.
+  pub package: Option<Package>,
+  pub file_count: usize,
+  pub line_count: usize,
+  /// Code, comment, blank and mixed lines of the files below.
+  pub lines: LineCounts,
+  pub total_keywords: usize,
+  pub keyword_counts: HashMap<String, usize>,
+  /// Subdirectories, sorted by name.
+  pub children: Vec<DirectoryNode>,
+}
+
+/// A node of a flattened [`DirectoryNode`] tree.
+pub struct DirectoryEntry<'a> {
+  pub depth: usize,
+  pub node: &'a DirectoryNode,
+  /// Name of the innermost package containing the directory.
+  pub package: Option<&'a str>,
+}
+
+impl DirectoryNode {
+  /// Rolls `files` up into a tree of the directories containing them and
+  /// marks the directories of `packages`.
+  pub fn build(files: &[FileAnalysis], packages: &[Package]) -> Self {
+    let mut root = DirectoryNode {
+      name: ".".to_string(),
+      ..Default::default()
+    };
+
+    for file in files {
+      let mut node = &mut root;
+      node.add(file);
+      for name in directory_names(Path::new(&file.path).parent()) {
+        node = node.child(&name);
+        node.add(file);
+      }
+    }
+
+    for package in packages {
+      let mut node = Some(&mut root);
+      for name in directory_names(Some(Path::new(&package.path))) {
+        node = node.and_then(|node| node.children.iter_mut().find(|child| child.name == name));
+      }
+      if let Some(node) = node {
+        node.package = Some(package.clone());
+      }
+    }
+
+    root.sort();
+    root
+  }
+
+  /// The path for display, `.` for the root.
+  pub fn display_path(&self) -> &str {
+    if self.path.is_empty() {
+      "."
+    } else {
+      &self.path
+    }
+  }
+
+  /// The tree in depth-first order, parents before their children.
+  pub fn flatten(&self) -> Vec<DirectoryEntry<'_>> {
+    let mut entries = Vec::new();
+    self.flatten_into(0, None, &mut entries);
+    entries
+  }
+
+  fn flatten_into<'a>(
+    &'a self,
+    depth: usize,
+    package: Option<&'a str>,
+    entries: &mut Vec<DirectoryEntry<'a>>,
+  ) {
+    let package = self
+      .package
+      .as_ref()
+      .map(|package| package.name.as_str())
+      .or(package);
+    entries.push(DirectoryEntry {
+      depth,
+      node: self,
+      package,
+    });
+    for child in &self.children {
+      child.flatten_into(depth + 1, package, entries);
+    }
+  }
+
+  fn add(&mut self, file: &FileAnalysis) {
+    self.file_count += 1;
+    self.line_count += file.line_count;
+    self.lines.add(&file.lines);
+    self.total_keywords += file.total_keywords;
+    for (keyword, count) in &file.keyword_counts {
+      *self.keyword_counts.entry(keyword.clone()).or_insert(0) += count;
+    }
+  }
+
+  fn child(&mut self, name: &str) -> &mut DirectoryNode {
+    let index = match self.children.iter().position(|child| child.name == name) {
+      Some(index) => index,
+      None => {
+        let path = if self.path.is_empty() {
+          name.to_string()
+        } else {
+          format!("{}/{name}", self.path)
+        };
+        self.children.push(DirectoryNode {
+          path,
+          name: name.to_string(),
+          ..Default::default()
+        });
+        self.children.len() - 1
+      }
+    };
+    &mut self.children[index]
+  }
+
+  fn sort(&mut self) {
+    self.children.sort_by(|a, b| a.name.cmp(&b.name));
+    for child in &mut self.children {
+      child.sort();
+    }
+  }
+}
+
+fn directory_names(path: Option<&Path>) -> Vec<String> {
+  path
+    .into_iter()
+    .flat_map(Path::components)
+    .filter_map(|component| match component {
+      Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+      _ => None,
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_dir::TestDir;
+
+  fn file(path: &str, lines: usize, counts: &[(&str, usize)]) -> FileAnalysis {
+    let counts = counts
+      .iter()
+      .map(|(keyword, count)| (keyword.to_string(), *count))
+      .collect();
+    FileAnalysis::new(path.to_string(), lines, counts)
+  }
+
+  #[test]
+  fn test_build_tree() {
+    let files = [
+      file("build.rs", 5, &[("fn", 1)]),
+      file("crates/core/src/lib.rs", 100, &[("fn", 4), ("unsafe", 2)]),
+      file("crates/core/src/ffi.rs", 50, &[("unsafe", 7)]),
+      file("crates/cli/main.rs", 20, &[("fn", 2)]),
+    ];
+    let packages = [Package {
+      path: "crates/core".to_string(),
+      name: "core".to_string(),
+      manifest: "Cargo.toml".to_string(),
+    }];
+    let tree = DirectoryNode::build(&files, &packages);
+
+    assert_eq!(tree.file_count, 4);
+    assert_eq!(tree.total_keywords, 16);
+    let crates = &tree.children[0];
+    assert_eq!(crates.path, "crates");
+    assert_eq!(crates.line_count, 170);
+    assert_eq!(crates.lines.code, 170);
+
+    let rows: Vec<_> = tree
+      .flatten()
+      .iter()
+      .map(|entry| {
+        (
+          entry.depth,
+          entry.node.path.as_str(),
+          entry.package,
+          entry.node.keyword_counts.get("unsafe").copied(),
+        )
+      })
+      .collect();
+    assert_eq!(
+      package_for(&packages, "crates/core/src/ffi.rs").map(|p| p.name.as_str()),
+      Some("core")
+    );
+    assert!(package_for(&packages, "crates/cli/main.rs").is_none());
+    assert_eq!(
+      rows,
+      [
+        (0, "", None, Some(9)),
+        (1, "crates", None, Some(9)),
+        (2, "crates/cli", None, None),
+        (2, "crates/core", Some("core"), Some(9)),
+        (3, "crates/core/src", Some("core"), Some(9)),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_detect_package() {
+    let root = TestDir::new(&[
+      (
+        "rs/Cargo.toml",
+        "[package]\nname = \"engine\"\nversion = \"0.1.0\"\n",
+      ),
+      ("js/package.json", r#"{"name": "@acme/web"}"#),
+      (
+        "go/go.mod",
+        "// comment\nmodule example.com/tool\n\ngo 1.22\n",
+      ),
+      ("dart/pubspec.yaml", "name: 'app'\nversion: 1.0.0\n"),
+      ("workspace/Cargo.toml", "[workspace]\nmembers = []\n"),
+      ("none/", ""),
+    ]);
+
+    let detect = |dir: &str| detect_package(&root.join(dir), root.path()).map(|p| (p.path, p.name));
+    let detected = [
+      detect("rs"),
+      detect("js"),
+      detect("go"),
+      detect("dart"),
+      detect("workspace"),
+      detect("none"),
+    ];
+
+    let expected = [
+      Some(("rs", "engine")),
+      Some(("js", "@acme/web")),
+      Some(("go", "example.com/tool")),
+      Some(("dart", "app")),
+      Some(("workspace", "workspace")),
+      None,
+    ];
+    for (detected, expected) in detected.iter().zip(expected) {
+      assert_eq!(
+        detected
+          .as_ref()
+          .map(|(path, name)| (path.as_str(), name.as_str())),
+        expected
+      );
+    }
+  }
+}
@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+use crate::javascript;
+use crate::lexer::Token;
+use crate::spec::{LanguageDefinition, LanguageSpec};
+
+const DEFINITION: &str = include_str!("../languages/typescript.toml");
+
+/// TypeScript, counted separately from JavaScript so type-level keywords such
+/// as `interface` or `keyof` only show up for `.ts`/`.tsx` sources. Lexing is
+/// shared with [`javascript::tokenize`].
+pub struct TypeScriptSpec {
+  definition: LanguageDefinition,
+}
+
+impl TypeScriptSpec {
+  pub fn new() -> Self {
+    Self {
+      definition: LanguageDefinition::from_toml_str(DEFINITION)
+        .expect("bundled TypeScript definition should be valid"),
+    }
+  }
+}
+
+impl Default for TypeScriptSpec {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl LanguageSpec for TypeScriptSpec {
+  fn definition(&self) -> &LanguageDefinition {
+    &self.definition
+  }
+
+  fn tokenize<'a>(&self, content: &'a str) -> Vec<Token<'a>> {
+    javascript::tokenize(content)
+  }
+}
+
+pub fn spec() -> Arc<TypeScriptSpec> {
+  static SPEC: OnceLock<Arc<TypeScriptSpec>> = OnceLock::new();
+  SPEC.get_or_init(|| Arc::new(TypeScriptSpec::new())).clone()
+}
+
+pub fn count_keywords(content: &str) -> HashMap<String, usize> {
+  spec().count_keywords(content)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::path::PathBuf;
+
+  #[test]
+  fn test_count_keywords() {
+    // Import/Export and Module System
+    let imports_exports = r#"
+            import { Component } from 'react';
+            export default class App extends Component {
+                const fs = require('fs');
+                
+                declare module 'external-lib' {
+                    export function method(): void;
+                }
+                
+                declare package 'my-package' {
+                    export const version: string;
+                }
+            }
+        "#;
+
+    // Classes and Object-Oriented Programming
+    let classes_oop = r#"
+            abstract class BaseEntity {
+                private readonly id: number;
+                protected name: string;
+                public static count: number = 0;
+                
+                constructor(name: string) {
+                    this.name = name;
+                    this.id = BaseEntity.count++;
+                }
+                
+                abstract process(): void;
+                
+                get getName(): string {
+                    return this.name;
+                }
+                
+                set setName(value: string) {
+                    this.name = value;
+                }
+            }
+            
+            class UserService implements Service {
+                override async fetchUser(id: bigint): Promise<User | null> {
+                    const result = await super.fetchUser(id);
+                    return result;
+                }
+            }
+        "#;
+
+    // Interface and Type Definitions
+    let interfaces_types = r#"
+            interface User {
+                name: string;
+                age: number;
+                isActive: boolean;
+                data: any;
+                tags: symbol[];
+                metadata: object;
+                id: bigint;
+            }
+            
+            type Status = 'active' | 'inactive';
+            type UserKey = keyof User;
+            type StringOrNumber = string | number;
+            type ReadonlyUser = Readonly<User>;
+            
+            enum Color {
+                Red,
+                Green,
+                Blue
+            }
+        "#;
+
+    // Advanced TypeScript Types
+    let advanced_types = r#"
+            type NonNullable<T> = T extends null | undefined ? never : T;
+            type ReturnType<T> = T extends (...args: any[]) => infer R ? R : never;
+            type Contravariant<out T> = {
+                consume: (value: T) => void;
+            };
+            type Uppercase<S extends string> = intrinsic;
+            
+            const config = {
+                apiUrl: 'https://api.example.com',
+                timeout: 5000
+            } satisfies Config;
+        "#;
+
+    // Control Flow and Logic
+    let control_flow = r#"
+            function processUsers(): void {
+                for (const user of users) {
+                    if (user.isActive) {
+                        switch (user.status) {
+                            case 'active':
+                                break;
+                            case 'inactive':
+                                break;
+                            default:
+                                debugger;
+                                continue;
+                        }
+                    } else {
+                        do {
+                            user.retryCount++;
+                        } while (user.retryCount < 3);
+                    }
+                }
+            }
+        "#;
+
+    // Async/Await and Error Handling
+    let async_error_handling = r#"
+            async function fetchUser(id: bigint): Promise<User | null> {
+                try {
+                    const response = await fetch(`/api/user/${id}`);
+                    if (!response.ok) {
+                        throw new Error('Failed to fetch user');
+                    }
+                    return await response.json() as User;
+                } catch (error) {
+                    console.error('Error fetching user:', error);
+                    return null;
+                } finally {
+                    console.log('Fetch attempt completed');
+                }
+            }
+        "#;
+
+    // Functions and Generators
+    let functions_generators = r#"
+            function isUser(obj: any): obj is User {
+                return obj && typeof obj.name === 'string';
+            }
+            
+            function assertUser(obj: unknown): asserts obj is User {
+                if (!isUser(obj)) {
+                    throw new Error('Not a user');
+                }
+            }
+            
+            function* generateUsers(): Generator<User, void, unknown> {
+                for (let i = 0; i < users.length; i++) {
+                    yield users[i];
+                }
+            }
+            
+            function logMessage(message: string): void {
+                console.log(message);
+            }
+        "#;
+
+    // Variables and Operators
+    let variables_operators = r#"
+            const API_URL: string = 'https://api.example.com';
+            let currentUser: User | null = null;
+            var globalConfig: any = {};
+            
+            const isReady: boolean = true;
+            const isDisabled: boolean = false;
+            const emptyValue: null = null;
+            const notSet: undefined = undefined;
+            const dynamicValue: unknown = 'could be anything';
+            const neverValue: never = (() => { throw new Error(); })();
+            const uniqueSymbol: unique symbol = Symbol('unique');
+            
+            if (user instanceof UserModel && typeof user.name === 'string') {
+                return user.name in validNames && user.age > 0;
+            }
+            
+            delete globalConfig.temporaryProperty;
+        "#;
+
+    // Namespace and Global Declarations
+    let namespaces_globals = r#"
+            namespace Utils {
+                export function helper(): void {}
+            }
+            
+            declare global {
+                interface Window {
+                    customProperty: unknown;
+                }
+            }
+            
+            var oldVar = 'legacy';
+            with (config) {
+                // Legacy code example
+            }
+        "#;
+
+    // Combine all sections
+    let comprehensive_content = format!(
+      "{imports_exports}\n{classes_oop}\n{interfaces_types}\n{advanced_types}\n{control_flow}\n{async_error_handling}\n{functions_generators}\n{variables_operators}\n{namespaces_globals}"
+    );
+
+    let counts = count_keywords(&comprehensive_content);
+
+    let language = spec();
+    let mut missing_keywords = Vec::new();
+    for keyword in language.keywords() {
+      if !counts.contains_key(keyword) {
+        missing_keywords.push(keyword);
+      }
+    }
+
+    if !missing_keywords.is_empty() {
+      panic!(
+        "The following keywords are missing from the comprehensive test: {missing_keywords:?}"
+      );
+    }
+
+    for keyword in language.keywords() {
+      assert!(
+        counts.get(keyword).unwrap_or(&0) >= &1,
+        "Keyword '{keyword}' should appear at least once in the comprehensive example"
+      );
+    }
+
+    println!(
+      "✅ All {} TypeScript keywords are properly tested!",
+      spec().keywords().len()
+    );
+
+    let no_keywords_content = "hello world 123 test";
+    let no_keywords_counts = count_keywords(no_keywords_content);
+    assert!(no_keywords_counts.is_empty());
+  }
+
+  #[test]
+  fn test_typescript_specific_keywords() {
+    // Test TypeScript-only keywords
+    let content = "abstract class Base { abstract method(): void; }";
+    let counts = count_keywords(content);
+    assert_eq!(counts.get("abstract"), Some(&2));
+    assert_eq!(counts.get("class"), Some(&1));
+    assert_eq!(counts.get("void"), Some(&1));
+
+    // Test that keywords in strings are not counted
+    let content =
+      "type Result<T> = T | undefined; const x: unknown = null; const y: string = 'type';";
+    let counts = count_keywords(content);
+    assert_eq!(counts.get("type"), Some(&1)); // Only the actual type keyword, not the string 'type'
+    assert_eq!(counts.get("undefined"), Some(&1));
+    assert_eq!(counts.get("const"), Some(&2)); // Two const declarations
+    assert_eq!(counts.get("unknown"), Some(&1));
+    assert_eq!(counts.get("null"), Some(&1));
+    assert_eq!(counts.get("string"), Some(&1));
+  }
+
+  #[test]
+  fn test_is_typescript_file() {
+    assert!(spec().is_source_file(&PathBuf::from("app.ts")));
+    assert!(spec().is_source_file(&PathBuf::from("component.tsx")));
+    assert!(spec().is_source_file(&PathBuf::from("module.mts")));
+    assert!(spec().is_source_file(&PathBuf::from("path/to/config.cts")));
+
+    assert!(!spec().is_source_file(&PathBuf::from("script.js")));
+    assert!(!spec().is_source_file(&PathBuf::from("component.jsx")));
+    assert!(!spec().is_source_file(&PathBuf::from("file.rs")));
+  }
+
+  #[test]
+  fn test_includes_javascript_keywords() {
+    let javascript = javascript::spec();
+    for keyword in javascript.keywords() {
+      assert!(
+        spec().is_keyword(keyword),
+        "{keyword} missing from TypeScript"
+      );
+    }
+    assert!(spec().keywords().len() > javascript.keywords().len());
+  }
+
+  #[test]
+  fn test_tsx() {
+    let content = r#"
+      export function Badge<T extends object>({ item }: { item: T }): JSX.Element {
+        return <span title="interface keyof">{item satisfies T ? "readonly" : null}</span>;
+      }
+    "#;
+    let counts = count_keywords(content);
+    assert_eq!(counts.get("function"), Some(&1));
+    assert_eq!(counts.get("extends"), Some(&1));
+    assert_eq!(counts.get("object"), Some(&1));
+    assert_eq!(counts.get("satisfies"), Some(&1));
+    assert_eq!(counts.get("interface"), None);
+    assert_eq!(counts.get("keyof"), None);
+    assert_eq!(counts.get("readonly"), None);
+  }
+
+  #[test]
+  fn test_non_ascii_punctuation() {
+    let counts = count_keywords("const s: number = a × 2; type Arrow = A → B;");
+    assert_eq!(counts.get("const"), Some(&1));
+    assert_eq!(counts.get("number"), Some(&1));
+    assert_eq!(counts.get("type"), Some(&1));
+  }
+}
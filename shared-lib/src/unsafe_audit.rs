@@ -0,0 +1,467 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::lexer::{Source, Token, TokenKind};
+use crate::tree::{package_for, Package};
+
+/// What an `unsafe` keyword introduces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnsafeKind {
+  Block,
+  Fn,
+  Impl,
+  Trait,
+}
+
+impl UnsafeKind {
+  pub fn name(self) -> &'static str {
+    match self {
+      UnsafeKind::Block => "unsafe block",
+      UnsafeKind::Fn => "unsafe fn",
+      UnsafeKind::Impl => "unsafe impl",
+      UnsafeKind::Trait => "unsafe trait",
+    }
+  }
+}
+
+/// An `unsafe` block, function, impl or trait.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UnsafeUse {
+  pub path: String,
+  /// 1-based line of the `unsafe` keyword.
+  pub line: usize,
+  /// 1-based column, in characters.
+  pub column: usize,
+  pub kind: UnsafeKind,
+  /// The function or trait name, or the implemented type, e.g.
+  /// `Send for Buffer`. `None` for blocks.
+  pub name: Option<String>,
+  /// Name of the crate containing the file.
+  #[serde(rename = "crate")]
+  pub package: Option<String>,
+  /// Whether a `// SAFETY:` comment or a `# Safety` doc section comes right
+  /// before it.
+  pub safety_comment: bool,
+}
+
+/// Finds every `unsafe` block, `unsafe fn`, `unsafe impl` and `unsafe trait`
+/// in Rust source, skipping comments, strings, `unsafe fn(..)` pointer types
+/// and `#[unsafe(..)]` attributes.
+pub fn find_unsafe(path: &str, content: &str) -> Vec<UnsafeUse> {
+  let (comments, tokens): (Vec<Token>, Vec<Token>) = crate::rust::tokenize(content)
+    .into_iter()
+    .partition(|token| token.kind == TokenKind::Comment);
+  let source = Source::new(content);
+
+  let mut uses = Vec::new();
+  for (index, token) in tokens.iter().enumerate() {
+    if token.kind != TokenKind::Word || token.text != "unsafe" {
+      continue;
+    }
+    let Some((kind, name)) = classify(&tokens, index, content) else {
+      continue;
+    };
+    uses.push(UnsafeUse {
+      path: path.to_string(),
+      line: source.line_of(token.offset) + 1,
+      column: source.before(token.offset).chars().count() + 1,
+      kind,
+      name,
+      package: None,
+      safety_comment: has_safety_comment(&tokens, &comments, index, &source),
+    });
+  }
+  uses
+}
+
+/// What the `unsafe` at `tokens[index]` introduces, with its name.
+fn classify(tokens: &[Token], index: usize, content: &str) -> Option<(UnsafeKind, Option<String>)> {
+  let word = |index: usize| {
+    tokens
+      .get(index)
+      .filter(|token| token.kind == TokenKind::Word)
+      .map(|token| token.text)
+  };
+  let next = tokens.get(index + 1)?;
+
+  match next.text {
+    "{" if next.kind == TokenKind::Punct => Some((UnsafeKind::Block, None)),
+    "fn" => Some((UnsafeKind::Fn, Some(word(index + 2)?.to_string()))),
+    // `unsafe extern "C" fn name`
+    "extern" => {
+      let fn_index = if tokens.get(index + 2)?.kind == TokenKind::Str {
+        index + 3
+      } else {
+        index + 2
+      };
+      if word(fn_index)? != "fn" {
+        return None;
+      }
+      Some((UnsafeKind::Fn, Some(word(fn_index + 1)?.to_string())))
+    }
+    "trait" => Some((UnsafeKind::Trait, Some(word(index + 2)?.to_string()))),
+    "auto" if word(index + 2) == Some("trait") => {
+      Some((UnsafeKind::Trait, Some(word(index + 3)?.to_string())))
+    }
+    "impl" => {
+      // The header up to the body or `where` clause, generics included
+      let start = tokens.get(index + 2)?;
+      let end = tokens[index + 2..]
+        .iter()
+        .position(|token| token.text == "{" || token.text == "where" || token.text == ";")
+        .map_or(tokens.len(), |position| index + 2 + position);
+      let last = &tokens[end - 1];
+      let header = &content[start.offset..last.offset + last.text.len()];
+      let header = header.split_whitespace().collect::<Vec<_>>().join(" ");
+      Some((UnsafeKind::Impl, Some(header)))
+    }
+    _ => None,
+  }
+}
+
+/// Whether a safety comment precedes the `unsafe` at `tokens[index]`: on its
+/// line before it, or in the comments above the statement or item it is
+/// part of. The statement is followed upwards across line breaks until a
+/// line ends with `;`, `{`, `}` or `,`, which also looks past attributes.
+/// `tokens` holds no comments; they are in `comments`, in source order.
+fn has_safety_comment(tokens: &[Token], comments: &[Token], index: usize, source: &Source) -> bool {
+  // The statement starts after the last `;`, `{`, `}` or `,` before `unsafe`
+  // that ends its line
+  let mut start = index;
+  let mut line = source.line_of(tokens[index].offset);
+  while start > 0 {
+    let previous = &tokens[start - 1];
+    let previous_line = source.line_of(previous.offset);
+    if previous_line != line {
+      if matches!(previous.text, ";" | "{" | "}" | ",") && previous.kind == TokenKind::Punct {
+        break;
+      }
+      line = previous_line;
+    }
+    start -= 1;
+  }
+
+  let statement_start = tokens[start].offset;
+  let code_before = start.checked_sub(1).map(|previous| {
+    let previous = &tokens[previous];
+    previous.offset + previous.text.len()
+  });
+
+  // Comments between the end of the previous statement and `unsafe`, the
+  // closest first. A trailing comment of the previous statement ends them.
+  let unsafe_offset = tokens[index].offset;
+  for comment in comments
+    .iter()
+    .rev()
+    .skip_while(|comment| comment.offset > unsafe_offset)
+  {
+    if code_before.is_some_and(|end| comment.offset < end) {
+      break;
+    }
+    if comment.offset < statement_start && !source.before(comment.offset).trim().is_empty() {
+      break;
+    }
+    if is_safety_comment(comment.text) {
+      return true;
+    }
+  }
+  false
+}
+
+fn is_safety_comment(comment: &str) -> bool {
+  comment.lines().any(|line| {
+    let text = line.trim_start_matches(|c: char| c.is_whitespace() || matches!(c, '/' | '*' | '!'));
+    let text = text.trim_end();
+    text.to_uppercase().starts_with("SAFETY:") || text.eq_ignore_ascii_case("# safety")
+  })
+}
+
+/// `unsafe` uses by kind, in a crate or overall.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnsafeTotals {
+  pub blocks: usize,
+  pub functions: usize,
+  pub impls: usize,
+  pub traits: usize,
+  /// Uses without a safety comment.
+  pub undocumented: usize,
+}
+
+impl UnsafeTotals {
+  pub fn add(&mut self, unsafe_use: &UnsafeUse) {
+    match unsafe_use.kind {
+      UnsafeKind::Block => self.blocks += 1,
+      UnsafeKind::Fn => self.functions += 1,
+      UnsafeKind::Impl => self.impls += 1,
+      UnsafeKind::Trait => self.traits += 1,
+    }
+    if !unsafe_use.safety_comment {
+      self.undocumented += 1;
+    }
+  }
+
+  pub fn total(&self) -> usize {
+    self.blocks + self.functions + self.impls + self.traits
+  }
+}
+
+/// The `unsafe` uses of one crate.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CrateUnsafe {
+  /// `None` for files outside any crate.
+  pub name: Option<String>,
+  /// Directory of the crate's `Cargo.toml`, relative to the analyzed
+  /// directory.
+  pub path: Option<String>,
+  pub totals: UnsafeTotals,
+}
+
+/// Every `unsafe` use below a directory, with totals per crate.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct UnsafeAudit {
+  /// Ordered by path, line and column.
+  pub uses: Vec<UnsafeUse>,
+  /// Every crate found, including those without `unsafe`, ordered by path.
+  pub crates: Vec<CrateUnsafe>,
+}
+
+impl UnsafeAudit {
+  /// Sorts `uses` and assigns each to the innermost of the Cargo `packages`
+  /// containing it.
+  pub fn new(mut uses: Vec<UnsafeUse>, packages: &[Package]) -> Self {
+    let packages: Vec<Package> = packages
+      .iter()
+      .filter(|package| package.manifest == "Cargo.toml")
+      .cloned()
+      .collect();
+    uses.sort_by(|a, b| {
+      (a.path.as_str(), a.line, a.column).cmp(&(b.path.as_str(), b.line, b.column))
+    });
+
+    let mut crates: Vec<CrateUnsafe> = packages
+      .iter()
+      .map(|package| CrateUnsafe {
+        name: Some(package.name.clone()),
+        path: Some(package.path.clone()),
+        totals: UnsafeTotals::default(),
+      })
+      .collect();
+    crates.sort_by(|a, b| a.path.cmp(&b.path));
+    crates.dedup_by(|a, b| a.path == b.path);
+
+    for unsafe_use in &mut uses {
+      let package = package_for(&packages, &unsafe_use.path);
+      unsafe_use.package = package.map(|package| package.name.clone());
+      let path = package.map(|package| package.path.clone());
+      let index = match crates.iter().position(|entry| entry.path == path) {
+        Some(index) => index,
+        None => {
+          crates.insert(
+            0,
+            CrateUnsafe {
+              name: None,
+              path: None,
+              totals: UnsafeTotals::default(),
+            },
+          );
+          0
+        }
+      };
+      crates[index].totals.add(unsafe_use);
+    }
+
+    Self { uses, crates }
+  }
+
+  pub fn totals(&self) -> UnsafeTotals {
+    let mut totals = UnsafeTotals::default();
+    for unsafe_use in &self.uses {
+      totals.add(unsafe_use);
+    }
+    totals
+  }
+}
+
+/// Totals, a table of crates and every use as `path:line:column: kind`,
+/// flagging those without a safety comment.
+pub fn format_unsafe_audit(audit: &UnsafeAudit) -> String {
+  let totals = audit.totals();
+  let mut output = format!(
+    "\n=== Rust Unsafe Audit ===\n\
+     Unsafe uses: {} (blocks {}, fns {}, impls {}, traits {})\n\
+     Without a SAFETY comment: {}\n",
+    totals.total(),
+    totals.blocks,
+    totals.functions,
+    totals.impls,
+    totals.traits,
+    totals.undocumented
+  );
+
+  if !audit.crates.is_empty() {
+    output.push_str(&format!(
+      "\n{:<32} {:>7} {:>7} {:>7} {:>7} {:>12}\n",
+      "Crate", "Blocks", "Fns", "Impls", "Traits", "Undocumented"
+    ));
+    for entry in &audit.crates {
+      let totals = &entry.totals;
+      output.push_str(&format!(
+        "{:<32} {:>7} {:>7} {:>7} {:>7} {:>12}\n",
+        entry.name.as_deref().unwrap_or("(no crate)"),
+        totals.blocks,
+        totals.functions,
+        totals.impls,
+        totals.traits,
+        totals.undocumented
+      ));
+    }
+  }
+
+  if !audit.uses.is_empty() {
+    output.push('\n');
+  }
+  for unsafe_use in &audit.uses {
+    output.push_str(&format!(
+      "{}:{}:{}: {}",
+      unsafe_use.path,
+      unsafe_use.line,
+      unsafe_use.column,
+      unsafe_use.kind.name()
+    ));
+    if let Some(name) = &unsafe_use.name {
+      output.push_str(&format!(" {name}"));
+    }
+    if !unsafe_use.safety_comment {
+      output.push_str(" (no SAFETY comment)");
+    }
+    output.push('\n');
+  }
+  output
+}
+
+/// The audit as JSON. Nothing depends on the time or order of the walk, so
+/// the reports of two releases can be diffed.
+pub fn generate_unsafe_audit_json(audit: &UnsafeAudit) -> String {
+  let report = json!({
+    "totals": audit.totals(),
+    "crates": audit.crates,
+    "uses": audit.uses,
+  });
+  serde_json::to_string_pretty(&report).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::Value;
+
+  fn summary(uses: &[UnsafeUse]) -> Vec<(usize, UnsafeKind, Option<&str>, bool)> {
+    uses
+      .iter()
+      .map(|u| (u.line, u.kind, u.name.as_deref(), u.safety_comment))
+      .collect()
+  }
+
+  #[test]
+  fn test_find_unsafe() {
+    let source = "\
+/// Reads the value.
+///
+/// # Safety
+/// `ptr` must be valid.
+#[inline]
+pub unsafe fn read(ptr: *const u8) -> u8 {
+    // SAFETY: the caller upholds the contract
+    unsafe { *ptr }
+}
+
+unsafe impl<T: Send> Send for Buffer<T> where T: Sync {}
+unsafe trait Zeroable {}
+
+fn main() {
+    let f: unsafe fn(u8) = todo(); // unsafe { in a comment }
+    let s = \"unsafe { in a string }\";
+    // SAFETY: checked above
+    let x = call(
+        unsafe { read(p) },
+    );
+    foo(); // SAFETY: belongs to foo
+    unsafe { r#unsafe() };
+}
+
+#[unsafe(no_mangle)]
+unsafe extern \"C\" fn exported() {}
+";
+    let uses = find_unsafe("src/lib.rs", source);
+    assert_eq!(
+      summary(&uses),
+      [
+        (6, UnsafeKind::Fn, Some("read"), true),
+        (8, UnsafeKind::Block, None, true),
+        (
+          11,
+          UnsafeKind::Impl,
+          Some("<T: Send> Send for Buffer<T>"),
+          false
+        ),
+        (12, UnsafeKind::Trait, Some("Zeroable"), false),
+        (19, UnsafeKind::Block, None, true),
+        (22, UnsafeKind::Block, None, false),
+        (26, UnsafeKind::Fn, Some("exported"), false),
+      ]
+    );
+    assert_eq!(uses[1].column, 5);
+  }
+
+  #[test]
+  fn test_unsafe_audit() {
+    let package = |path: &str, name: &str, manifest: &str| Package {
+      path: path.to_string(),
+      name: name.to_string(),
+      manifest: manifest.to_string(),
+    };
+    let mut uses = find_unsafe(
+      "core/src/lib.rs",
+      "unsafe fn a() {}\n// SAFETY: ok\nunsafe impl Send for A {}\n",
+    );
+    uses.extend(find_unsafe("build.rs", "fn main() { unsafe { exit() } }\n"));
+    let audit = UnsafeAudit::new(
+      uses,
+      &[
+        package("core", "core", "Cargo.toml"),
+        package("cli", "cli", "Cargo.toml"),
+        package("web", "web", "package.json"),
+      ],
+    );
+
+    assert_eq!(audit.uses[0].path, "build.rs");
+    assert_eq!(audit.uses[1].package.as_deref(), Some("core"));
+    let crates: Vec<_> = audit
+      .crates
+      .iter()
+      .map(|entry| {
+        (
+          entry.name.as_deref(),
+          entry.totals.total(),
+          entry.totals.undocumented,
+        )
+      })
+      .collect();
+    assert_eq!(
+      crates,
+      [(None, 1, 1), (Some("cli"), 0, 0), (Some("core"), 2, 1)]
+    );
+
+    let plain = format_unsafe_audit(&audit);
+    assert!(plain.contains("Unsafe uses: 3 (blocks 1, fns 1, impls 1, traits 0)\n"));
+    assert!(plain.contains("core/src/lib.rs:1:1: unsafe fn a (no SAFETY comment)\n"));
+    assert!(plain.contains("core/src/lib.rs:3:1: unsafe impl Send for A\n"));
+
+    let json: Value = serde_json::from_str(&generate_unsafe_audit_json(&audit)).unwrap();
+    assert_eq!(json["totals"]["undocumented"], 2);
+    assert_eq!(json["crates"][2]["totals"]["impls"], 1);
+    assert_eq!(json["uses"][2]["kind"], "impl");
+    assert_eq!(json["uses"][2]["crate"], "core");
+  }
+}